@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use legion::*;
+use systems::Builder;
+
+use crate::config::Config;
+
+#[system]
+fn start_autosave(#[resource] autosave: &mut Autosave) {
+    autosave.tick();
+}
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources, config: &Config) {
+    schedule.add_system(start_autosave_system());
+    let interval = match config.autosave_interval {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    resources.insert(Autosave::new(interval));
+}
+
+/// Decides when the world and the online players should be saved.
+/// Systems check `is_saving` and store their own state when it's true
+pub struct Autosave {
+    interval: Option<Duration>,
+    last_save: Instant,
+    requested: AtomicBool,
+    saving: bool,
+}
+
+impl Autosave {
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            last_save: Instant::now(),
+            requested: AtomicBool::new(false),
+            saving: false,
+        }
+    }
+
+    /// Saves everything on the next tick, regardless of the interval
+    pub fn save_all(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+
+    /// True only during the tick in which a save starts
+    pub fn is_saving(&self) -> bool {
+        self.saving
+    }
+
+    fn tick(&mut self) {
+        let due = self.interval
+            .map(|interval| self.last_save.elapsed() >= interval)
+            .unwrap_or(false);
+        self.saving = self.requested.swap(false, Ordering::Relaxed) || due;
+        if self.saving {
+            self.last_save = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+const BIOMES_JSON: &str = include_str!("biomes.json");
+const SNOW_TEMPERATURE: f32 = 0.15;
+const SEA_LEVEL: i32 = 64;
+pub const PLAINS: u16 = 1;
+
+lazy_static! {
+    static ref BIOMES: HashMap<u16, Biome> = {
+        serde_json::from_str::<HashMap<String, Biome>>(BIOMES_JSON).unwrap()
+            .into_iter()
+            .map(|(name, mut biome)| {
+                biome.name = name;
+                (biome.id, biome)
+            })
+            .collect()
+    };
+
+    static ref NAME_TO_BIOME: HashMap<String, &'static Biome> = BIOMES.values()
+        .map(|biome| (biome.name.clone(), biome))
+        .collect();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Biome {
+    #[serde(skip)]
+    pub name: String,
+    pub id: u16,
+    pub temperature: f32,
+    pub precipitation: Precipitation,
+}
+
+impl PartialEq for Biome {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Biome {}
+
+impl Biome {
+    pub fn from_id(id: u16) -> Option<&'static Self> {
+        BIOMES.get(&id)
+    }
+
+    pub fn from_name(name: &str) -> Option<&'static Self> {
+        NAME_TO_BIOME.get(name).map(|x| *x)
+    }
+
+    pub fn plains() -> &'static Self {
+        &BIOMES[&PLAINS]
+    }
+
+    /// Temperature gets lower above sea level, like in vanilla
+    pub fn temperature_at(&self, y: i32) -> f32 {
+        if y > SEA_LEVEL {
+            self.temperature - (y - SEA_LEVEL) as f32 * 0.05 / 30.0
+        } else {
+            self.temperature
+        }
+    }
+
+    pub fn precipitation_at(&self, y: i32) -> Precipitation {
+        match self.precipitation {
+            Precipitation::None => Precipitation::None,
+            _ if self.is_cold_at(y) => Precipitation::Snow,
+            _ => Precipitation::Rain,
+        }
+    }
+
+    /// Water freezes and snow stays in cold places
+    pub fn is_cold_at(&self, y: i32) -> bool {
+        self.temperature_at(y) < SNOW_TEMPERATURE
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::mem::take;
+
+use lazy_static::lazy_static;
+
+use crate::items::{ItemStack, ItemType};
+use crate::recipes::{self, CookingMethod, Recipe, RecipeKind, find_cooking, remainder};
+use crate::tags::item_tag;
+use super::{BlockEntity, BlockEntityData};
+
+pub const INPUT: usize = 0;
+pub const FUEL: usize = 1;
+pub const RESULT: usize = 2;
+
+/// Burn time in ticks in a normal furnace, items with # are tags
+const FUELS: &[(&str, i16)] = &[
+    ("minecraft:lava_bucket", 20000),
+    ("minecraft:coal_block", 16000),
+    ("minecraft:blaze_rod", 2400),
+    ("minecraft:coal", 1600),
+    ("minecraft:charcoal", 1600),
+    ("#logs", 300),
+    ("#planks", 300),
+    ("#wooden_stairs", 300),
+    ("#wooden_slabs", 150),
+    ("#wooden_trapdoors", 300),
+    ("#wooden_pressure_plates", 300),
+    ("#wooden_fences", 300),
+    ("minecraft:oak_fence_gate", 300),
+    ("minecraft:spruce_fence_gate", 300),
+    ("minecraft:birch_fence_gate", 300),
+    ("minecraft:jungle_fence_gate", 300),
+    ("minecraft:acacia_fence_gate", 300),
+    ("minecraft:dark_oak_fence_gate", 300),
+    ("minecraft:note_block", 300),
+    ("minecraft:bookshelf", 300),
+    ("minecraft:lectern", 300),
+    ("minecraft:jukebox", 300),
+    ("minecraft:chest", 300),
+    ("minecraft:trapped_chest", 300),
+    ("minecraft:crafting_table", 300),
+    ("minecraft:daylight_detector", 300),
+    ("#banners", 300),
+    ("minecraft:bow", 300),
+    ("minecraft:fishing_rod", 300),
+    ("minecraft:ladder", 300),
+    ("#signs", 200),
+    ("minecraft:wooden_shovel", 200),
+    ("minecraft:wooden_sword", 200),
+    ("minecraft:wooden_hoe", 200),
+    ("minecraft:wooden_axe", 200),
+    ("minecraft:wooden_pickaxe", 200),
+    ("#wooden_doors", 200),
+    ("#boats", 1200),
+    ("#wool", 100),
+    ("#wooden_buttons", 100),
+    ("minecraft:stick", 100),
+    ("#saplings", 100),
+    ("minecraft:bowl", 100),
+    ("#carpets", 67),
+    ("minecraft:dried_kelp_block", 4001),
+    ("minecraft:crossbow", 300),
+    ("minecraft:bamboo", 50),
+    ("minecraft:dead_bush", 100),
+    ("minecraft:scaffolding", 400),
+    ("minecraft:loom", 300),
+    ("minecraft:barrel", 300),
+    ("minecraft:cartography_table", 300),
+    ("minecraft:fletching_table", 300),
+    ("minecraft:smithing_table", 300),
+    ("minecraft:composter", 300),
+];
+
+lazy_static! {
+    static ref FUEL_TIMES: HashMap<u16, i16> = {
+        // Crimson and warped wood is in the wooden tags but doesn't burn
+        let non_flammable: Vec<u16> = item_tag("minecraft:non_flammable_wood")
+            .unwrap_or_default()
+            .iter().map(|item| item.to_numeric()).collect();
+        let mut times = HashMap::new();
+        for (name, time) in FUELS {
+            match name.strip_prefix('#') {
+                Some(tag) => {
+                    let items = item_tag(&format!("minecraft:{}", tag)).unwrap_or_default();
+                    for item in items {
+                        if !non_flammable.contains(&item.to_numeric()) {
+                            times.insert(item.to_numeric(), *time);
+                        }
+                    }
+                },
+                None => {
+                    if let Ok(item) = ItemType::from_str(name) {
+                        times.insert(item.to_numeric(), *time);
+                    }
+                },
+            }
+        }
+        times
+    };
+}
+
+/// What a tick did to a block entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TickChange {
+    None,
+    Data,
+    /// The furnace started or stopped burning, its block has to change too
+    Lit(bool),
+}
+
+/// Recipes a furnace, blast furnace or smoker uses
+pub fn cooking_method(id: &str) -> Option<CookingMethod> {
+    match id {
+        "minecraft:furnace" => Some(CookingMethod::Smelting),
+        "minecraft:blast_furnace" => Some(CookingMethod::Blasting),
+        "minecraft:smoker" => Some(CookingMethod::Smoking),
+        _ => None,
+    }
+}
+
+pub fn is_fuel(item: &ItemType) -> bool {
+    FUEL_TIMES.contains_key(&item.to_numeric())
+}
+
+/// Ticks an item burns for, blast furnaces and smokers go through fuel twice as fast
+pub fn fuel_burn_time(id: &str, item: &ItemType) -> Option<i16> {
+    let time = *FUEL_TIMES.get(&item.to_numeric())?;
+    match cooking_method(id)? {
+        CookingMethod::Smelting => Some(time),
+        _ => Some(time / 2),
+    }
+}
+
+impl BlockEntity {
+    /// Burns fuel and cooks the input of furnaces, other block entities don't tick
+    pub fn tick(&mut self) -> TickChange {
+        let method = match cooking_method(&self.id) {
+            Some(method) => method,
+            None => return TickChange::None,
+        };
+        let id = self.id.clone();
+        let (items, burn_time, burn_time_total, cook_time, cook_time_total, recipes_used) =
+            match &mut self.data
+        {
+            BlockEntityData::Furnace {
+                items, burn_time, burn_time_total, cook_time, cook_time_total, recipes_used
+            } => (items, burn_time, burn_time_total, cook_time, cook_time_total, recipes_used),
+            _ => return TickChange::None,
+        };
+        let was_lit = *burn_time > 0;
+        let mut changed = was_lit;
+        if was_lit {
+            *burn_time -= 1;
+        }
+        let recipe = items[INPUT].as_ref()
+            .and_then(|input| find_cooking(method, input.item));
+        if let Some(recipe) = recipe {
+            *cook_time_total = cook_time_of(recipe);
+        }
+        let can_cook = recipe
+            .map(|recipe| fits(&items[RESULT], recipe.result()))
+            .unwrap_or(false);
+        if *burn_time > 0 || (items[FUEL].is_some() && items[INPUT].is_some()) {
+            if *burn_time == 0 && can_cook {
+                let fuel_time = items[FUEL].as_ref()
+                    .and_then(|fuel| fuel_burn_time(&id, fuel.item))
+                    .unwrap_or(0);
+                if fuel_time > 0 {
+                    *burn_time = fuel_time;
+                    *burn_time_total = fuel_time;
+                    items[FUEL] = items[FUEL].take().and_then(use_up);
+                    changed = true;
+                }
+            }
+            if *burn_time > 0 && can_cook {
+                *cook_time += 1;
+                if *cook_time >= *cook_time_total {
+                    *cook_time = 0;
+                    let recipe = recipe.unwrap();
+                    cook(items, recipe);
+                    *recipes_used.entry(recipe.id.clone()).or_insert(0) += 1;
+                }
+                changed = true;
+            } else if *cook_time != 0 {
+                *cook_time = 0;
+                changed = true;
+            }
+        } else if *cook_time > 0 {
+            // Cooling down loses progress twice as fast as it's made
+            *cook_time = (*cook_time - 2).max(0);
+            changed = true;
+        }
+        let lit = *burn_time > 0;
+        if lit != was_lit {
+            TickChange::Lit(lit)
+        } else if changed {
+            TickChange::Data
+        } else {
+            TickChange::None
+        }
+    }
+
+    /// Experience for what was cooked since the result was last taken,
+    /// which starts counting again. Like vanilla, the fraction of a point
+    /// each recipe gives is the chance of one more
+    pub fn take_experience(&mut self) -> i32 {
+        let recipes_used = match &mut self.data {
+            BlockEntityData::Furnace { recipes_used, .. } => take(recipes_used),
+            _ => return 0,
+        };
+        recipes_used.iter()
+            .map(|(id, times)| {
+                let experience = match recipes::find(id).map(|recipe| &recipe.kind) {
+                    Some(RecipeKind::Cooking { experience, .. }) => *experience,
+                    _ => 0.0,
+                };
+                let total = experience * *times as f32;
+                let points = total.floor();
+                points as i32 + (rand::random::<f32>() < total - points) as i32
+            })
+            .sum()
+    }
+
+    /// Flame and arrow of the furnace window: burn time left, burn time
+    /// of the fuel, cook progress and total cook time
+    pub fn furnace_properties(&self) -> Option<[i16; 4]> {
+        match &self.data {
+            BlockEntityData::Furnace {
+                burn_time, burn_time_total, cook_time, cook_time_total, ..
+            } => Some([*burn_time, *burn_time_total, *cook_time, *cook_time_total]),
+            _ => None,
+        }
+    }
+}
+
+fn cook_time_of(recipe: &Recipe) -> i16 {
+    match recipe.kind {
+        RecipeKind::Cooking { time, .. } => time as i16,
+        _ => 200,
+    }
+}
+
+fn fits(slot: &Option<ItemStack>, result: &ItemStack) -> bool {
+    match slot {
+        Some(stack) => stack.is_same_kind(result)
+            && stack.count + result.count <= stack.max_stack_size(),
+        None => true,
+    }
+}
+
+/// One less fuel item, buckets of lava leave the bucket behind
+fn use_up(mut fuel: ItemStack) -> Option<ItemStack> {
+    fuel.count -= 1;
+    if fuel.count > 0 {
+        return Some(fuel);
+    }
+    remainder(fuel.item).map(|item| ItemStack { item, count: 1, nbt: None })
+}
+
+fn cook(items: &mut [Option<ItemStack>], recipe: &Recipe) {
+    let result = recipe.result();
+    match &mut items[RESULT] {
+        Some(stack) => stack.count += result.count,
+        slot => *slot = Some(result.clone()),
+    }
+    // Drying a sponge fills a bucket in the fuel slot
+    let is_sponge = result.item.to_str() == "minecraft:sponge";
+    let fuel_is_bucket = items[FUEL].as_ref()
+        .map(|fuel| fuel.item.to_str() == "minecraft:bucket" && fuel.count == 1)
+        .unwrap_or(false);
+    let input = items[INPUT].as_mut().unwrap();
+    input.count -= 1;
+    if input.count == 0 {
+        items[INPUT] = None;
+    }
+    if is_sponge && fuel_is_bucket {
+        items[FUEL] = ItemType::from_str("minecraft:water_bucket").ok()
+            .map(|item| ItemStack { item, count: 1, nbt: None });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(name: &str, count: u8) -> Option<ItemStack> {
+        Some(ItemStack {
+            item: ItemType::from_str(name).unwrap(),
+            count,
+            nbt: None,
+        })
+    }
+
+    #[test]
+    fn smelts_with_fuel() {
+        let mut furnace = BlockEntity::new("minecraft:furnace");
+        *furnace.items_mut().unwrap() = vec![
+            stack("minecraft:iron_ore", 2),
+            stack("minecraft:stick", 2),
+            None,
+        ];
+        assert_eq!(furnace.tick(), TickChange::Lit(true));
+        for _ in 0..199 {
+            furnace.tick();
+        }
+        let items = furnace.items().unwrap();
+        assert_eq!(items[RESULT].as_ref().unwrap().item.to_str(), "minecraft:iron_ingot");
+        assert_eq!(items[INPUT].as_ref().unwrap().count, 1);
+        // The second stick was lit halfway through
+        assert!(items[FUEL].is_none());
+        assert_eq!(furnace.furnace_properties(), Some([1, 100, 0, 200]));
+        assert_eq!(furnace.tick(), TickChange::Lit(false));
+    }
+
+    #[test]
+    fn experience_is_taken_once() {
+        let mut furnace = BlockEntity::new("minecraft:furnace");
+        *furnace.items_mut().unwrap() = vec![
+            stack("minecraft:gold_ore", 2),
+            stack("minecraft:coal", 1),
+            None,
+        ];
+        for _ in 0..400 {
+            furnace.tick();
+        }
+        // Gold gives a whole point per ingot
+        assert_eq!(furnace.take_experience(), 2);
+        assert_eq!(furnace.take_experience(), 0);
+    }
+
+    #[test]
+    fn blast_furnace_burns_twice_as_fast() {
+        let coal = ItemType::from_str("minecraft:coal").unwrap();
+        assert_eq!(fuel_burn_time("minecraft:blast_furnace", coal), Some(800));
+        let stem = ItemType::from_str("minecraft:crimson_stem").unwrap();
+        assert_eq!(fuel_burn_time("minecraft:furnace", stem), None);
+    }
+}
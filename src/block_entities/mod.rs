@@ -0,0 +1,472 @@
+mod furnaces;
+
+use std::collections::HashMap;
+
+use anvil_nbt::{CompoundTag, Tag};
+use nalgebra::Vector3;
+use nbt::Value as Nbt;
+
+use crate::items::{ItemStack, ItemType};
+use crate::serialization::{compound_to_value, value_to_compound};
+
+/// Slots of a chest, barrel or shulker box
+pub const CONTAINER_SLOTS: usize = 27;
+const FURNACE_SLOTS: usize = 3;
+
+pub use furnaces::{TickChange, cooking_method, is_fuel};
+
+/// Extra data some blocks keep, stored in the chunk next to the block
+#[derive(Clone, Debug)]
+pub struct BlockEntity {
+    /// Like minecraft:chest, decides what the client does with the data
+    pub id: String,
+    pub data: BlockEntityData,
+    /// What it was read from, so the keys that aren't modeled are saved back
+    tag: CompoundTag,
+}
+
+#[derive(Clone, Debug)]
+pub enum BlockEntityData {
+    /// Chests, trapped chests, barrels and shulker boxes
+    Container {
+        items: Vec<Option<ItemStack>>,
+        custom_name: Option<String>,
+    },
+    Sign {
+        /// Json text components
+        lines: [String; 4],
+        color: String,
+    },
+    Banner {
+        /// Pattern code and dye color
+        patterns: Vec<(String, i32)>,
+        custom_name: Option<String>,
+    },
+    Skull {
+        owner: Option<CompoundTag>,
+    },
+    /// Furnaces, blast furnaces and smokers
+    Furnace {
+        /// Input, fuel and result
+        items: Vec<Option<ItemStack>>,
+        /// Ticks left until the fuel runs out
+        burn_time: i16,
+        /// What the last fuel lasted, not saved, vanilla takes it
+        /// from the fuel slot when loading
+        burn_time_total: i16,
+        cook_time: i16,
+        cook_time_total: i16,
+        /// Times each recipe was used, the experience they give is
+        /// kept until someone takes the result
+        recipes_used: HashMap<String, i32>,
+    },
+    Spawner {
+        entity: String,
+        delay: i16,
+    },
+    Beehive {
+        /// Bees are entities, we only keep their data around
+        bees: Vec<CompoundTag>,
+    },
+    /// Everything else, only saved back as it was read
+    Other,
+}
+
+impl BlockEntity {
+    /// Empty block entity of the given kind, as it is right after placing the block
+    pub fn new(id: &str) -> Self {
+        let data = match id {
+            "minecraft:chest" | "minecraft:trapped_chest" | "minecraft:barrel" 
+                | "minecraft:shulker_box" => {
+                BlockEntityData::Container {
+                    items: vec![None; CONTAINER_SLOTS],
+                    custom_name: None,
+                }
+            },
+            "minecraft:sign" => BlockEntityData::Sign {
+                lines: [(); 4].map(|_| text_component("")),
+                color: "black".into(),
+            },
+            "minecraft:banner" => BlockEntityData::Banner {
+                patterns: vec![],
+                custom_name: None,
+            },
+            "minecraft:skull" => BlockEntityData::Skull { owner: None },
+            "minecraft:furnace" | "minecraft:blast_furnace" | "minecraft:smoker" => {
+                BlockEntityData::Furnace {
+                    items: vec![None; FURNACE_SLOTS],
+                    burn_time: 0,
+                    burn_time_total: 0,
+                    cook_time: 0,
+                    cook_time_total: 0,
+                    recipes_used: HashMap::new(),
+                }
+            },
+            "minecraft:mob_spawner" => BlockEntityData::Spawner {
+                entity: "minecraft:pig".into(),
+                delay: 20,
+            },
+            "minecraft:beehive" => BlockEntityData::Beehive { bees: vec![] },
+            _ => BlockEntityData::Other,
+        };
+        Self {
+            id: id.into(),
+            data,
+            tag: CompoundTag::new(),
+        }
+    }
+
+    /// Reads an entry of a chunk's TileEntities list
+    pub fn from_nbt(tag: &CompoundTag) -> Option<Self> {
+        let id = tag.get_str("id").ok()?.to_string();
+        let mut entity = Self::new(&id);
+        entity.tag = tag.clone();
+        match &mut entity.data {
+            BlockEntityData::Container { items, custom_name } => {
+                read_items(tag, items);
+                *custom_name = tag.get_str("CustomName").ok().map(String::from);
+            },
+            BlockEntityData::Sign { lines, color } => {
+                for (i, line) in lines.iter_mut().enumerate() {
+                    *line = tag.get_str(&format!("Text{}", i + 1))
+                        .unwrap_or("").into();
+                }
+                *color = tag.get_str("Color").unwrap_or("black").into();
+            },
+            BlockEntityData::Banner { patterns, custom_name } => {
+                *patterns = tag.get_compound_tag_vec("Patterns")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|pattern| Some((
+                        pattern.get_str("Pattern").ok()?.to_string(),
+                        pattern.get_i32("Color").ok()?,
+                    )))
+                    .collect();
+                *custom_name = tag.get_str("CustomName").ok().map(String::from);
+            },
+            BlockEntityData::Skull { owner } => {
+                *owner = tag.get_compound_tag("SkullOwner").ok().cloned();
+            },
+            BlockEntityData::Furnace { 
+                items, burn_time, burn_time_total, cook_time, cook_time_total, recipes_used 
+            } => {
+                read_items(tag, items);
+                *burn_time = tag.get_i16("BurnTime").unwrap_or(0);
+                *cook_time = tag.get_i16("CookTime").unwrap_or(0);
+                *cook_time_total = tag.get_i16("CookTimeTotal").unwrap_or(0);
+                *burn_time_total = items[furnaces::FUEL].as_ref()
+                    .and_then(|fuel| furnaces::fuel_burn_time(&id, fuel.item))
+                    .unwrap_or(0);
+                if let Ok(used) = tag.get_compound_tag("RecipesUsed") {
+                    for (recipe, times) in used.iter() {
+                        if let Tag::Int(times) = times {
+                            recipes_used.insert(recipe.clone(), *times);
+                        }
+                    }
+                }
+            },
+            BlockEntityData::Spawner { entity, delay } => {
+                if let Ok(data) = tag.get_compound_tag("SpawnData") {
+                    *entity = data.get_str("id").unwrap_or("minecraft:pig").into();
+                }
+                *delay = tag.get_i16("Delay").unwrap_or(20);
+            },
+            BlockEntityData::Beehive { bees } => {
+                *bees = tag.get_compound_tag_vec("Bees")
+                    .unwrap_or_default()
+                    .into_iter().cloned().collect();
+            },
+            BlockEntityData::Other => (),
+        }
+        Some(entity)
+    }
+
+    /// Same format vanilla uses in region files
+    pub fn to_nbt(&self, pos: Vector3<i32>) -> CompoundTag {
+        // Optional fields are left out so clearing them sticks
+        let optional: &[&str] = match self.data {
+            BlockEntityData::Container { .. } | BlockEntityData::Banner { .. } => &["CustomName"],
+            BlockEntityData::Skull { .. } => &["SkullOwner"],
+            _ => &[],
+        };
+        let mut tag = CompoundTag::new();
+        for (key, value) in self.tag.iter() {
+            if !optional.contains(&key.as_str()) {
+                tag.insert(key, value.clone());
+            }
+        }
+        tag.insert_str("id", &self.id);
+        tag.insert_i32("x", pos.x);
+        tag.insert_i32("y", pos.y);
+        tag.insert_i32("z", pos.z);
+        match &self.data {
+            BlockEntityData::Container { items, custom_name } => {
+                tag.insert_compound_tag_vec("Items", write_items(items, &self.tag));
+                if let Some(name) = custom_name {
+                    tag.insert_str("CustomName", name);
+                }
+            },
+            BlockEntityData::Sign { lines, color } => {
+                for (i, line) in lines.iter().enumerate() {
+                    tag.insert_str(&format!("Text{}", i + 1), line);
+                }
+                tag.insert_str("Color", color);
+            },
+            BlockEntityData::Banner { patterns, custom_name } => {
+                tag.insert_compound_tag_vec("Patterns", patterns.iter()
+                    .map(|(pattern, color)| {
+                        let mut entry = CompoundTag::new();
+                        entry.insert_str("Pattern", pattern);
+                        entry.insert_i32("Color", *color);
+                        entry
+                    })
+                    .collect());
+                if let Some(name) = custom_name {
+                    tag.insert_str("CustomName", name);
+                }
+            },
+            BlockEntityData::Skull { owner } => {
+                if let Some(owner) = owner {
+                    tag.insert_compound_tag("SkullOwner", owner.clone());
+                }
+            },
+            BlockEntityData::Furnace { 
+                items, burn_time, cook_time, cook_time_total, recipes_used, .. 
+            } => {
+                tag.insert_compound_tag_vec("Items", write_items(items, &self.tag));
+                tag.insert_i16("BurnTime", *burn_time);
+                tag.insert_i16("CookTime", *cook_time);
+                tag.insert_i16("CookTimeTotal", *cook_time_total);
+                let mut used = CompoundTag::new();
+                for (recipe, times) in recipes_used {
+                    used.insert_i32(recipe, *times);
+                }
+                tag.insert_compound_tag("RecipesUsed", used);
+            },
+            BlockEntityData::Spawner { entity, delay } => {
+                let mut data = self.tag.get_compound_tag("SpawnData").ok()
+                    .cloned()
+                    .unwrap_or_else(CompoundTag::new);
+                data.insert_str("id", entity);
+                tag.insert_compound_tag("SpawnData", data);
+                tag.insert_i16("Delay", *delay);
+            },
+            BlockEntityData::Beehive { bees } => {
+                tag.insert_compound_tag_vec("Bees", bees.clone());
+            },
+            BlockEntityData::Other => (),
+        }
+        tag
+    }
+
+    /// What clients get in Chunk Data and Block Entity Data packets, 
+    /// they don't need to know what's inside containers
+    pub fn to_network(&self, pos: Vector3<i32>) -> Nbt {
+        let mut tag = self.to_nbt(pos);
+        if let BlockEntityData::Container { .. } | BlockEntityData::Furnace { .. } = self.data {
+            tag = CompoundTag::new();
+            tag.insert_str("id", &self.id);
+            tag.insert_i32("x", pos.x);
+            tag.insert_i32("y", pos.y);
+            tag.insert_i32("z", pos.z);
+        }
+        compound_to_value(&tag)
+    }
+
+    /// Slots of containers and furnaces
+    pub fn items(&self) -> Option<&Vec<Option<ItemStack>>> {
+        match &self.data {
+            BlockEntityData::Container { items, .. } 
+                | BlockEntityData::Furnace { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn items_mut(&mut self) -> Option<&mut Vec<Option<ItemStack>>> {
+        match &mut self.data {
+            BlockEntityData::Container { items, .. } 
+                | BlockEntityData::Furnace { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Replaces the slots of a container or furnace, a furnace starts cooking
+    /// from zero when its input changes to another item
+    pub fn set_items(&mut self, new_items: &[Option<ItemStack>]) {
+        if let BlockEntityData::Furnace { items, cook_time, .. } = &mut self.data {
+            let same_input = match (&items[furnaces::INPUT], &new_items[furnaces::INPUT]) {
+                (Some(old), Some(new)) => old.is_same_kind(new),
+                (old, new) => old.is_none() && new.is_none(),
+            };
+            if !same_input {
+                *cook_time = 0;
+            }
+        }
+        if let Some(items) = self.items_mut() {
+            *items = new_items.to_vec();
+        }
+    }
+
+    /// Name given with an anvil, as a json text component
+    pub fn custom_name(&self) -> Option<&String> {
+        match &self.data {
+            BlockEntityData::Container { custom_name, .. } 
+                | BlockEntityData::Banner { custom_name, .. } => custom_name.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Copies the BlockEntityTag and custom name of the item that placed the block, 
+    /// false if there was nothing to copy
+    pub fn apply_item(&mut self, pos: Vector3<i32>, stack: &ItemStack) -> bool {
+        let item_tag = stack.block_entity_tag().and_then(value_to_compound);
+        let name = stack.display_name()
+            .filter(|_| matches!(self.data, 
+                BlockEntityData::Container { .. } | BlockEntityData::Banner { .. }));
+        if item_tag.is_none() && name.is_none() {
+            return false;
+        }
+        let mut tag = self.to_nbt(pos);
+        for (key, value) in item_tag.iter().flat_map(|item_tag| item_tag.iter()) {
+            if !matches!(key.as_str(), "id" | "x" | "y" | "z") {
+                tag.insert(key, value.clone());
+            }
+        }
+        if let Some(name) = name {
+            tag.insert_str("CustomName", name);
+        }
+        match Self::from_nbt(&tag) {
+            Some(entity) => {
+                *self = entity;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Sets the text of a sign from what a player typed, false if this isn't a sign
+    pub fn set_sign_text(&mut self, text: &[String; 4]) -> bool {
+        if let BlockEntityData::Sign { lines, .. } = &mut self.data {
+            for (line, text) in lines.iter_mut().zip(text) {
+                // Formatting codes can't be typed in vanilla
+                *line = text_component(&text.replace('§', ""));
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Action of the Block Entity Data packet, None for the ones
+    /// clients don't need updates of
+    pub fn update_action(&self) -> Option<u8> {
+        let action = match self.id.as_str() {
+            "minecraft:mob_spawner" => 1,
+            "minecraft:skull" => 4,
+            "minecraft:banner" => 6,
+            "minecraft:sign" => 9,
+            "minecraft:beehive" => 14,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+fn text_component(text: &str) -> String {
+    serde_json::json!({ "text": text }).to_string()
+}
+
+fn read_items(tag: &CompoundTag, items: &mut Vec<Option<ItemStack>>) {
+    for item in tag.get_compound_tag_vec("Items").unwrap_or_default() {
+        let slot = item.get_i8("Slot").unwrap_or(-1);
+        let stack = item.get_str("id").ok()
+            .and_then(|id| ItemType::from_str(id).ok())
+            .map(|item_type| ItemStack {
+                item: item_type,
+                count: item.get_i8("Count").unwrap_or(1) as u8,
+                nbt: item.get_compound_tag("tag").ok().map(compound_to_value),
+            });
+        if slot >= 0 && (slot as usize) < items.len() {
+            items[slot as usize] = stack;
+        }
+    }
+}
+
+/// Items with ids we don't know are kept from the tag the block entity
+/// was read from, as long as nothing took their slot
+fn write_items(items: &[Option<ItemStack>], read_from: &CompoundTag) -> Vec<CompoundTag> {
+    let unknown = read_from.get_compound_tag_vec("Items").unwrap_or_default()
+        .into_iter()
+        .filter(|item| {
+            let slot = item.get_i8("Slot").unwrap_or(-1);
+            let known = item.get_str("id").map_or(false, |id| ItemType::from_str(id).is_ok());
+            !known && slot >= 0 && matches!(items.get(slot as usize), Some(None))
+        })
+        .cloned();
+    items.iter().enumerate()
+        .filter_map(|(slot, stack)| {
+            let stack = stack.as_ref()?;
+            let mut item = CompoundTag::new();
+            item.insert_i8("Slot", slot as i8);
+            item.insert_str("id", stack.item.to_str());
+            item.insert_i8("Count", stack.count as i8);
+            if let Some(tag) = stack.nbt.as_ref().and_then(value_to_compound) {
+                item.insert_compound_tag("tag", tag);
+            }
+            Some(item)
+        })
+        .chain(unknown)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use super::*;
+
+    #[test]
+    fn sign_round_trip() {
+        let mut sign = BlockEntity::new("minecraft:sign");
+        if let BlockEntityData::Sign { lines, .. } = &mut sign.data {
+            lines[1] = r#"{"text":"hi"}"#.into();
+        }
+        let tag = sign.to_nbt(vector!(1, 64, -2));
+        assert_eq!(tag.get_i32("z").unwrap(), -2);
+        match BlockEntity::from_nbt(&tag).unwrap().data {
+            BlockEntityData::Sign { lines, color } => {
+                assert_eq!(lines[1], r#"{"text":"hi"}"#);
+                assert_eq!(color, "black");
+            },
+            _ => panic!("not a sign"),
+        }
+    }
+
+    #[test]
+    fn unmodeled_data_survives() {
+        let mut unknown = CompoundTag::new();
+        unknown.insert_i8("Slot", 3);
+        unknown.insert_str("id", "somemod:gadget");
+        unknown.insert_i8("Count", 1);
+        let mut tag = CompoundTag::new();
+        tag.insert_str("id", "minecraft:chest");
+        tag.insert_str("LootTable", "minecraft:chests/simple_dungeon");
+        tag.insert_str("Lock", "key");
+        tag.insert_compound_tag_vec("Items", vec![unknown]);
+        let mut chest = BlockEntity::from_nbt(&tag).unwrap();
+        let saved = chest.to_nbt(vector!(0, 0, 0));
+        assert_eq!(saved.get_str("LootTable").unwrap(), "minecraft:chests/simple_dungeon");
+        assert_eq!(saved.get_str("Lock").unwrap(), "key");
+        assert_eq!(saved.get_compound_tag_vec("Items").unwrap().len(), 1);
+
+        // Something put in its slot replaces the unknown item
+        chest.items_mut().unwrap()[3] = Some(ItemStack {
+            item: ItemType::from_str("minecraft:dirt").unwrap(),
+            count: 1,
+            nbt: None,
+        });
+        let saved = chest.to_nbt(vector!(0, 0, 0));
+        let items = saved.get_compound_tag_vec("Items").unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].get_str("id").unwrap(), "minecraft:dirt");
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use nalgebra::{Vector3, vector};
+
+use crate::chunks::WorldView;
+use crate::blocks::{Block, BlockType};
+use crate::util::cardinal_to_vec;
+
+/// Offset of the other half of a double chest
+pub fn chest_partner(block: &Block) -> Option<Vector3<i32>> {
+    let facing = cardinal_to_vec(&block.props["facing"]);
+    let clockwise = vector!(-facing.z, 0, facing.x);
+    match block.props["type"].as_str() {
+        "left" => Some(clockwise),
+        "right" => Some(-clockwise),
+        _ => None,
+    }
+}
+
+/// Joins a chest being placed with a single chest next to it facing the
+/// same way, the neighbour is changed right away
+pub fn connect_chest(btype: &BlockType, props: &mut BTreeMap<String, String>, 
+    view: &WorldView) 
+{
+    let facing = cardinal_to_vec(&props["facing"]);
+    let clockwise = vector!(-facing.z, 0, facing.x);
+    for (side, this_type, other_type) in [(clockwise, "left", "right"), (-clockwise, "right", "left")] {
+        let other = view.get(side.x, side.y, side.z);
+        if other.btype == btype && other.props["type"] == "single" 
+            && other.props["facing"] == props["facing"]
+        {
+            let mut other_props = other.props.clone();
+            other_props.insert("type".into(), other_type.into());
+            view.set(side.x, side.y, side.z, btype.with_props(&other_props).unwrap());
+            props.insert("type".into(), this_type.into());
+            return;
+        }
+    }
+}
+
+/// Half of a double chest goes back to single when the other half is gone
+pub fn chest_update(block: &'static Block, view: &WorldView) {
+    if let Some(side) = chest_partner(block) {
+        let other = view.get(side.x, side.y, side.z);
+        let paired = other.btype == block.btype
+            && chest_partner(other) == Some(-side);
+        if !paired {
+            let mut props = block.props.clone();
+            props.insert("type".into(), "single".into());
+            view.set(0, 0, 0, block.btype.with_props(&props).unwrap());
+        }
+    }
+}
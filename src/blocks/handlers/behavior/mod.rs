@@ -1,3 +1,5 @@
 pub mod connections;
 pub mod stairs;
 pub mod plants;
+pub mod portals;
+pub mod chests;
@@ -0,0 +1,182 @@
+use nalgebra::{Vector3, vector};
+
+use crate::chunks::WorldView;
+use crate::blocks::{Block, BlockType};
+
+const MIN_WIDTH: i32 = 2;
+const MAX_WIDTH: i32 = 21;
+const MIN_HEIGHT: i32 = 3;
+const MAX_HEIGHT: i32 = 21;
+
+/// Inside of an obsidian frame, relative to the center of the view it was found from
+pub struct PortalFrame {
+    axis: &'static str,
+    bottom_left: Vector3<i32>,
+    width: i32,
+    height: i32,
+}
+
+impl PortalFrame {
+    /// Tries both axes, like vanilla does when fire is lit
+    pub fn find_any(view: &WorldView) -> Option<Self> {
+        Self::find(view, "x").or_else(|| Self::find(view, "z"))
+    }
+
+    pub fn find(view: &WorldView, axis: &'static str) -> Option<Self> {
+        let dir = axis_direction(axis);
+        let mut pos = vector!(0, 0, 0);
+        if !is_inside(get(view, pos)) {
+            return None;
+        }
+        for _ in 0..MAX_HEIGHT {
+            if !is_inside(get(view, pos - vector!(0, 1, 0))) {
+                break;
+            }
+            pos.y -= 1;
+        }
+        for _ in 0..MAX_WIDTH {
+            let next = pos - dir;
+            if !is_inside(get(view, next)) || !is_obsidian(get(view, next - vector!(0, 1, 0))) {
+                break;
+            }
+            pos = next;
+        }
+        if !is_obsidian(get(view, pos - dir)) {
+            return None;
+        }
+        let bottom_left = pos;
+        let width = (0..=MAX_WIDTH)
+            .find(|i| {
+                let pos = bottom_left + dir * *i;
+                !is_inside(get(view, pos)) || !is_obsidian(get(view, pos - vector!(0, 1, 0)))
+            })?;
+        if width < MIN_WIDTH || !is_obsidian(get(view, bottom_left + dir * width)) {
+            return None;
+        }
+        for h in 0..=MAX_HEIGHT {
+            let row = bottom_left + vector!(0, h, 0);
+            if (0..width).all(|i| is_inside(get(view, row + dir * i))) {
+                if !is_obsidian(get(view, row - dir))
+                    || !is_obsidian(get(view, row + dir * width))
+                {
+                    return None;
+                }
+            } else if h >= MIN_HEIGHT && (0..width).all(|i| is_obsidian(get(view, row + dir * i))) {
+                return Some(Self {
+                    axis,
+                    bottom_left,
+                    width,
+                    height: h,
+                });
+            } else {
+                return None;
+            }
+        }
+        None
+    }
+
+    pub fn fill(&self, view: &WorldView) {
+        let portal = nether_portal(self.axis);
+        let dir = axis_direction(self.axis);
+        for h in 0..self.height {
+            for i in 0..self.width {
+                let pos = self.bottom_left + dir * i + vector!(0, h, 0);
+                view.set(pos.x, pos.y, pos.z, portal);
+            }
+        }
+    }
+}
+
+/// Lights a fire at the center of the view, or a portal if it's inside a frame
+pub fn light_fire(view: &WorldView) {
+    if !view.get(0, 0, 0).material.replaceable {
+        return;
+    }
+    if let Some(frame) = PortalFrame::find_any(view) {
+        frame.fill(view);
+    } else if view.get(0, -1, 0).opaque {
+        let fire = BlockType::from_name("minecraft:fire").unwrap();
+        view.set(0, 0, 0, fire.with_props(&fire.default_state).unwrap());
+    }
+}
+
+/// Portal blocks go away as soon as a neighbour in the portal plane isn't
+/// obsidian or another portal block
+pub fn nether_portal_update(block: &'static Block, view: &WorldView) {
+    let dir = axis_direction(&block.props["axis"]);
+    let neighbours = [dir, -dir, vector!(0, 1, 0), vector!(0, -1, 0)];
+    let broken = neighbours.iter().any(|delta| {
+        let other = get(view, *delta);
+        other.btype != block.btype && !is_obsidian(other)
+    });
+    if broken {
+        view.set(0, 0, 0, Block::air());
+    }
+}
+
+/// Fills the 3x3 inside of a ring of end portal frames with eyes, called
+/// after an eye is put into the frame at the center of the view
+pub fn try_fill_end_portal(view: &WorldView) {
+    let mut candidates = vec![];
+    for side in -1..=1 {
+        for edge in [-2, 2] {
+            candidates.push(vector!(edge, 0, side));
+            candidates.push(vector!(side, 0, edge));
+        }
+    }
+    let portal = BlockType::from_name("minecraft:end_portal").unwrap();
+    let portal = portal.with_props(&portal.default_state).unwrap();
+    for center in candidates {
+        if end_ring_complete(view, center) {
+            for x in -1..=1 {
+                for z in -1..=1 {
+                    view.set(center.x + x, center.y, center.z + z, portal);
+                }
+            }
+            return;
+        }
+    }
+}
+
+fn end_ring_complete(view: &WorldView, center: Vector3<i32>) -> bool {
+    (-1..=1).all(|side| {
+        [-2, 2].iter().all(|edge| {
+            has_eye(get(view, center + vector!(*edge, 0, side)))
+                && has_eye(get(view, center + vector!(side, 0, *edge)))
+        })
+    })
+}
+
+fn has_eye(block: &Block) -> bool {
+    block.btype.name == "minecraft:end_portal_frame"
+        && block.props.get("eye").map(|eye| eye == "true").unwrap_or(false)
+}
+
+pub fn nether_portal(axis: &str) -> &'static Block {
+    let btype = BlockType::from_name("minecraft:nether_portal").unwrap();
+    let mut props = btype.default_state.clone();
+    props.insert("axis".into(), axis.into());
+    btype.with_props(&props).unwrap()
+}
+
+fn axis_direction(axis: &str) -> Vector3<i32> {
+    if axis == "x" {
+        vector!(1, 0, 0)
+    } else {
+        vector!(0, 0, 1)
+    }
+}
+
+fn get(view: &WorldView, pos: Vector3<i32>) -> &'static Block {
+    view.get(pos.x, pos.y, pos.z)
+}
+
+fn is_obsidian(block: &Block) -> bool {
+    block.btype.name == "minecraft:obsidian"
+}
+
+fn is_inside(block: &Block) -> bool {
+    block.id == Block::air().id
+        || block.btype.name == "minecraft:fire"
+        || block.btype.name == "minecraft:nether_portal"
+}
@@ -0,0 +1,27 @@
+use crate::blocks::{BlockType, BlockClass};
+
+impl BlockType {
+    /// Id of the block entity that goes with this block, if it has one
+    pub fn block_entity_id(&self) -> Option<&'static str> {
+        let id = match self.class {
+            BlockClass::ChestBlock => "minecraft:chest",
+            BlockClass::TrappedChestBlock => "minecraft:trapped_chest",
+            BlockClass::BarrelBlock => "minecraft:barrel",
+            BlockClass::ShulkerBoxBlock => "minecraft:shulker_box",
+            BlockClass::EnderChestBlock => "minecraft:ender_chest",
+            BlockClass::SignBlock | BlockClass::WallSignBlock => "minecraft:sign",
+            BlockClass::BannerBlock | BlockClass::WallBannerBlock => "minecraft:banner",
+            BlockClass::SkullBlock | BlockClass::WallSkullBlock 
+                | BlockClass::PlayerSkullBlock | BlockClass::WallPlayerSkullBlock
+                | BlockClass::WitherSkullBlock | BlockClass::WallWitherSkullBlock 
+                => "minecraft:skull",
+            BlockClass::FurnaceBlock => "minecraft:furnace",
+            BlockClass::BlastFurnaceBlock => "minecraft:blast_furnace",
+            BlockClass::SmokerBlock => "minecraft:smoker",
+            BlockClass::SpawnerBlock => "minecraft:mob_spawner",
+            BlockClass::BeehiveBlock => "minecraft:beehive",
+            _ => return None,
+        };
+        Some(id)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::chunks::WorldView;
-use crate::blocks::{Block, BlockClass};
+use crate::blocks::{Block, BlockClass, BlockFace};
+use crate::items::ItemType;
+use super::behavior::portals::{light_fire, try_fill_end_portal};
 
 impl Block {
     pub fn interact(&self, view: &WorldView) -> InteractionResult {
@@ -27,7 +29,45 @@ impl Block {
                     InteractionResult::None
                 }
             },
-            BlockClass::CraftingTableBlock => {
+            BlockClass::ChestBlock | BlockClass::TrappedChestBlock 
+                | BlockClass::EnderChestBlock => 
+            {
+                if view.get(0, 1, 0).opaque {
+                    InteractionResult::PreventPlacing
+                } else {
+                    InteractionResult::OpenContainer
+                }
+            },
+            BlockClass::BarrelBlock | BlockClass::ShulkerBoxBlock 
+                | BlockClass::CraftingTableBlock | BlockClass::FurnaceBlock
+                | BlockClass::BlastFurnaceBlock | BlockClass::SmokerBlock => 
+            {
+                InteractionResult::OpenContainer
+            },
+            _ => InteractionResult::None,
+        }
+    }
+
+    /// Items that do something to the clicked block instead of placing one
+    pub fn use_item(&self, view: &WorldView, item: &ItemType, face: &BlockFace) 
+        -> InteractionResult 
+    {
+        match item.to_str() {
+            "minecraft:flint_and_steel" => {
+                let mut view = view.clone();
+                if !self.material.replaceable {
+                    view.displace(face.get_direction());
+                }
+                light_fire(&view);
+                InteractionResult::PreventPlacing
+            },
+            "minecraft:ender_eye" if self.btype.class == BlockClass::EndPortalFrameBlock => {
+                if self.props["eye"] == "false" {
+                    let mut props = self.props.clone();
+                    props.insert("eye".into(), "true".into());
+                    view.set(0, 0, 0, self.btype.with_props(&props).unwrap());
+                    try_fill_end_portal(view);
+                }
                 InteractionResult::PreventPlacing
             },
             _ => InteractionResult::None,
@@ -38,6 +78,8 @@ impl Block {
 pub enum InteractionResult {
     None,
     PreventPlacing,
+    /// The block has a window, opened by whoever clicked it
+    OpenContainer,
 }
 
 fn toggle_open(block: &Block) -> &'static Block {
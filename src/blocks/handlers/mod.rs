@@ -4,5 +4,9 @@ mod destruction;
 mod random_tick;
 mod interaction;
 mod behavior;
+mod block_entities;
 
 pub use interaction::InteractionResult;
+pub use behavior::plants::can_survive_on;
+pub use behavior::portals::nether_portal;
+pub use behavior::chests::chest_partner;
@@ -6,18 +6,27 @@ use crate::chunks::WorldView;
 use super::behavior::{
     connections::update_connections, 
     plants::can_survive_on, 
-    stairs::get_stair_shape};
+    stairs::get_stair_shape,
+    chests::connect_chest};
 use crate::blocks::{BlockFace, Block, BlockType, BlockClass};
 
 impl BlockType {
+    /// Returns where the block ended up, if it was placed
     pub fn place(&self, view: &WorldView, face: BlockFace, 
-        cursor: Vector3<f32>, angle: (f32, f32))
+        cursor: Vector3<f32>, angle: (f32, f32)) -> Option<Vector3<i32>>
     {
+        if matches!(self.class, BlockClass::SignBlock) 
+            && !matches!(face, BlockFace::PosY | BlockFace::NegY) 
+        {
+            if let Some(wall) = BlockType::from_name(&self.name.replace("_sign", "_wall_sign")) {
+                return wall.place(view, face, cursor, angle);
+            }
+        }
         let mut view = view.clone();
         if !view.get(0, 0, 0).material.replaceable {
             view.displace(face.get_direction());
             if !view.get(0, 0, 0).material.replaceable {
-                return;
+                return None;
             }
         }
         let mut props = self.auto_fill_props(
@@ -37,13 +46,29 @@ impl BlockType {
             },
             BlockClass::LadderBlock => {
                 match face {
-                    BlockFace::PosY | BlockFace::NegY => return,
+                    BlockFace::PosY | BlockFace::NegY => return None,
                     face => {
                         props.insert("facing".into(),
                             facing_from_face(&face));
                     }
                 }
             },
+            BlockClass::SignBlock => {
+                if view.get(0, -1, 0).material.replaceable {
+                    return None;
+                }
+                props.insert("rotation".into(), rotation_from_angle(angle.0));
+            },
+            BlockClass::WallSignBlock => {
+                let behind = -face.get_direction();
+                if view.get(behind.x, behind.y, behind.z).material.replaceable {
+                    return None;
+                }
+                props.insert("facing".into(), facing_from_face(&face));
+            },
+            BlockClass::ChestBlock | BlockClass::TrappedChestBlock => {
+                connect_chest(self, &mut props, &view);
+            },
             BlockClass::SlabBlock => {
                 props.insert("type".into(), calc_half(&face, &cursor));
             },
@@ -52,35 +77,33 @@ impl BlockType {
             },
             BlockClass::DoorBlock => {
                 if !view.get(0, 1, 0).material.replaceable {
-                    return;
+                    return None;
                 }
                 props.insert("facing".into(), 
                 facing_from_angle(angle.0 + 180.0));
-                props.insert("half".into(), "upper".into());
-                let block = self.with_props(&props).unwrap();
-                view.set(0, 1, 0, block);
+                let (_, upper) = self.two_block_halves(&props);
+                view.set(0, 1, 0, upper);
                 props.insert("half".into(), "lower".into());
             },
             BlockClass::TallFlowerBlock | BlockClass::TallPlantBlock => {
                 if !can_survive_on(view.get(0, -1, 0)) {
-                    return;
+                    return None;
                 }
                 if !view.get(0, 1, 0).material.replaceable {
-                    return;
+                    return None;
                 }
-                props.insert("half".into(), "upper".into());
-                let block = self.with_props(&props).unwrap();
-                view.set(0, 1, 0, block);
+                let (_, upper) = self.two_block_halves(&props);
+                view.set(0, 1, 0, upper);
                 props.insert("half".into(), "lower".into());
             },
             BlockClass::FlowerBlock | BlockClass::FernBlock => {
                 if !can_survive_on(view.get(0, -1, 0)) {
-                    return;
+                    return None;
                 }
             },
             BlockClass::CropBlock => {
                 if view.get(0, -1, 0).btype.name != "minecraft:farmland" {
-                    return;
+                    return None;
                 }
             },
             BlockClass::FenceBlock | BlockClass::PaneBlock |
@@ -93,6 +116,24 @@ impl BlockType {
         }
         let block = self.with_props(&props).unwrap();
         view.set(0, 0, 0, block);
+        Some(view.get_center())
+    }
+
+    pub fn is_two_blocks_tall(&self) -> bool {
+        matches!(self.class, BlockClass::DoorBlock | 
+            BlockClass::TallFlowerBlock | BlockClass::TallPlantBlock)
+    }
+
+    /// Lower and upper halves of doors and tall plants
+    pub fn two_block_halves(&self, props: &BTreeMap<String, String>) 
+        -> (&'static Block, &'static Block)
+    {
+        let mut props = props.clone();
+        props.insert("half".into(), "lower".into());
+        let lower = self.with_props(&props).unwrap();
+        props.insert("half".into(), "upper".into());
+        let upper = self.with_props(&props).unwrap();
+        (lower, upper)
     }
 
     fn auto_fill_props(&self, replacing: &Block, face: &BlockFace, 
@@ -130,6 +171,12 @@ fn facing_from_angle(yaw: f32) -> String {
     }.into()
 }
 
+/// Standing signs and banners can face 16 directions
+fn rotation_from_angle(yaw: f32) -> String {
+    let rotation = ((yaw + 180.0) * 16.0 / 360.0 + 0.5).floor() as i32;
+    (rotation & 15).to_string()
+}
+
 fn facing_from_face(face: &BlockFace) -> String {
     match face {
         BlockFace::PosX => "east",
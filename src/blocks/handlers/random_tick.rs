@@ -4,8 +4,10 @@ use rand::Rng;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 
+use crate::biomes::Precipitation;
 use crate::blocks::classes::BlockClass;
 use crate::chunks::WorldView;
+use crate::level::Weather;
 
 use crate::blocks::Block;
 
@@ -19,7 +21,7 @@ const GRASS_GROW_DIRECTIONS: &[Vector3<i32>] = &[
 ];
 
 impl Block {
-    pub fn random_tick(&self, view: &WorldView) {
+    pub fn random_tick(&self, view: &WorldView, weather: &Weather) {
         match self.btype.class {
             BlockClass::GrassBlock | BlockClass::MyceliumBlock => {
                 let mut rng = thread_rng();
@@ -38,6 +40,26 @@ impl Block {
                     }
                 }
             },
+            BlockClass::FluidBlock => {
+                let y = view.get_center().y;
+                if self.id == block_id!(water) && view.biome().is_cold_at(y)
+                    && view.sees_sky()
+                {
+                    let ice = Block::from_state_id(block_id!(ice)).unwrap();
+                    view.set(0, 0, 0, ice);
+                }
+            },
+            BlockClass::AirBlock => {
+                let y = view.get_center().y;
+                if weather.is_raining() 
+                    && view.biome().precipitation_at(y) == Precipitation::Snow
+                    && view.get(0, -1, 0).material.solid_blocking
+                    && view.sees_sky()
+                {
+                    let snow = Block::from_state_id(block_id!(snow)).unwrap();
+                    view.set(0, 0, 0, snow);
+                }
+            },
             _ => (),
         }
     }
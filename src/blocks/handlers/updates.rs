@@ -6,7 +6,9 @@ use crate::util::cardinal_to_vec;
 use super::behavior::{
     plants::can_survive_on, 
     stairs::get_stair_shape,
-    connections::update_connections};
+    connections::update_connections,
+    portals::nether_portal_update,
+    chests::chest_update};
 
 impl Block {
     pub fn update(&'static self, view: &WorldView) {
@@ -44,6 +46,12 @@ impl Block {
                     view.set(0, 0, 0, new);
                 }
             },
+            BlockClass::ChestBlock | BlockClass::TrappedChestBlock => {
+                chest_update(self, view);
+            },
+            BlockClass::NetherPortalBlock => {
+                nether_portal_update(self, view);
+            },
             _ => (),
         }
     }
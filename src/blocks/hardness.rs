@@ -0,0 +1,159 @@
+use serde::Deserialize;
+
+use crate::items::{ItemStack, ToolKind};
+use super::Block;
+use super::maps::HARDNESS;
+
+/// Materials swords cut a bit faster than hands
+const SWORD_MATERIALS: &[&str] = &["minecraft:plant", "minecraft:replaceable_plant",
+    "minecraft:unused_plant", "minecraft:leaves", "minecraft:gourd"];
+
+const UNKNOWN: BlockHardness = BlockHardness {
+    hardness: 0.0,
+    tool: None,
+    tier: 0,
+    requires_tool: false,
+};
+
+/// How hard a block is to dig and what it takes to get its drops
+#[derive(Deserialize, Debug)]
+pub struct BlockHardness {
+    /// Seconds to break it by hand are 1.5 times this, -1 for unbreakable blocks
+    pub hardness: f32,
+    /// Tool that digs it faster
+    #[serde(default)]
+    pub tool: Option<ToolKind>,
+    /// Lowest harvest level that gets the drops
+    #[serde(default)]
+    pub tier: u8,
+    /// Whether it drops nothing without the right tool
+    #[serde(default)]
+    pub requires_tool: bool,
+}
+
+impl Block {
+    pub fn hardness(&self) -> &'static BlockHardness {
+        HARDNESS.get(&self.btype.name).unwrap_or(&UNKNOWN)
+    }
+
+    /// Whether breaking it with the held item gets the drops
+    pub fn can_harvest(&self, held: Option<&ItemStack>) -> bool {
+        let hardness = self.hardness();
+        if !hardness.requires_tool {
+            return true;
+        }
+        let tool = match held.and_then(|stack| stack.item.tool()) {
+            Some(tool) => tool,
+            None => return false,
+        };
+        match tool.kind {
+            ToolKind::Sword | ToolKind::Shears => self.btype.name == "minecraft:cobweb",
+            kind => hardness.tool == Some(kind) && tool.level >= hardness.tier,
+        }
+    }
+
+    /// Share of the block dug each tick, 1 or more breaks it right away
+    pub fn dig_progress(&self, held: Option<&ItemStack>) -> f32 {
+        let hardness = self.hardness().hardness;
+        if hardness < 0.0 {
+            return 0.0;
+        }
+        let is_sword = held.and_then(|stack| stack.item.tool())
+            .map_or(false, |tool| tool.kind == ToolKind::Sword);
+        if is_sword && self.btype.name == "minecraft:bamboo" {
+            return 1.0;
+        }
+        let divider = if self.can_harvest(held) { 30.0 } else { 100.0 };
+        self.dig_speed(held) / hardness / divider
+    }
+
+    fn dig_speed(&self, held: Option<&ItemStack>) -> f32 {
+        let (stack, tool) = match held.and_then(|stack| Some((stack, stack.item.tool()?))) {
+            Some(held) => held,
+            None => return 1.0,
+        };
+        let material = self.material.name.as_str();
+        let speed = match tool.kind {
+            ToolKind::Sword if self.btype.name == "minecraft:cobweb" => 15.0,
+            ToolKind::Sword if SWORD_MATERIALS.contains(&material) => 1.5,
+            ToolKind::Shears if self.btype.name == "minecraft:cobweb"
+                || material == "minecraft:leaves" => 15.0,
+            ToolKind::Shears if material == "minecraft:wool" => 5.0,
+            kind if self.hardness().tool == Some(kind) => tool.speed,
+            _ => 1.0,
+        };
+        let efficiency = stack.enchantment_level("minecraft:efficiency") as f32;
+        if speed > 1.0 && efficiency > 0.0 {
+            speed + efficiency * efficiency + 1.0
+        } else {
+            speed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::BlockType;
+    use nbt::{Map, Value as Nbt};
+    use crate::items::ItemType;
+    use super::*;
+
+    fn block(name: &str) -> &'static Block {
+        let btype = BlockType::from_name(name).unwrap();
+        btype.with_props(&btype.default_state).unwrap()
+    }
+
+    fn item(name: &str) -> ItemStack {
+        ItemStack {
+            item: ItemType::from_str(name).unwrap(),
+            count: 1,
+            nbt: None,
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn hand_is_slow_without_the_tool() {
+        let stone = block("minecraft:stone");
+        assert!(!stone.can_harvest(None));
+        assert_close(stone.dig_progress(None), 1.0 / 1.5 / 100.0);
+        let pickaxe = item("minecraft:wooden_pickaxe");
+        assert!(stone.can_harvest(Some(&pickaxe)));
+        assert_close(stone.dig_progress(Some(&pickaxe)), 2.0 / 1.5 / 30.0);
+        // Blocks that don't need the tool drop by hand at full speed
+        let dirt = block("minecraft:dirt");
+        assert!(dirt.can_harvest(None));
+        assert_close(dirt.dig_progress(None), 1.0 / 0.5 / 30.0);
+    }
+
+    #[test]
+    fn tier_decides_drops() {
+        let ore = block("minecraft:iron_ore");
+        assert!(!ore.can_harvest(Some(&item("minecraft:wooden_pickaxe"))));
+        assert!(!ore.can_harvest(Some(&item("minecraft:golden_pickaxe"))));
+        assert!(ore.can_harvest(Some(&item("minecraft:stone_pickaxe"))));
+        assert!(!ore.can_harvest(Some(&item("minecraft:diamond_shovel"))));
+        let obsidian = block("minecraft:obsidian");
+        assert!(!obsidian.can_harvest(Some(&item("minecraft:iron_pickaxe"))));
+        assert!(obsidian.can_harvest(Some(&item("minecraft:diamond_pickaxe"))));
+        assert_eq!(block("minecraft:bedrock").dig_progress(None), 0.0);
+    }
+
+    #[test]
+    fn efficiency_only_helps_the_right_tool() {
+        let mut efficiency = Map::new();
+        efficiency.insert("id".into(), Nbt::String("minecraft:efficiency".into()));
+        efficiency.insert("lvl".into(), Nbt::Short(2));
+        let mut tag = Map::new();
+        tag.insert("Enchantments".into(), Nbt::List(vec![Nbt::Compound(efficiency)]));
+        let mut pickaxe = item("minecraft:iron_pickaxe");
+        pickaxe.nbt = Some(Nbt::Compound(tag));
+        let stone = block("minecraft:stone");
+        assert_close(stone.dig_progress(Some(&pickaxe)), (6.0 + 4.0 + 1.0) / 1.5 / 30.0);
+        let dirt = block("minecraft:dirt");
+        assert_close(dirt.dig_progress(Some(&pickaxe)), 1.0 / 0.5 / 30.0);
+    }
+}
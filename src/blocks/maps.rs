@@ -3,11 +3,12 @@ use serde_json::Value;
 use serde::Deserialize;
 use std::collections::{HashMap, BTreeMap};
 
-use super::{Block, BlockType, BlockMaterial};
+use super::{Block, BlockType, BlockMaterial, BlockHardness};
 use super::BlockClass;
 
 const MATERIALS_JSON: &str = include_str!("materials.json");
 const BLOCKS_JSON: &str = include_str!("blocks.json");
+const HARDNESS_JSON: &str = include_str!("hardness.json");
 
 lazy_static! {
     pub static ref MATERIALS: HashMap<String, BlockMaterial> = {
@@ -18,6 +19,9 @@ lazy_static! {
         }
         map
     };
+
+    pub static ref HARDNESS: HashMap<String, BlockHardness> =
+        serde_json::from_str(HARDNESS_JSON).unwrap();
     
     pub static ref BLOCK_TYPES: Vec<BlockType> = {
         let mut result: Vec<BlockType> = 
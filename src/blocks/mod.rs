@@ -3,6 +3,7 @@ mod maps;
 mod types;
 mod classes;
 mod material;
+mod hardness;
 mod handlers;
 
 use std::collections::BTreeMap;
@@ -12,7 +13,8 @@ use material::BlockMaterial;
 
 pub use face::BlockFace;
 pub use types::BlockType;
-pub use handlers::InteractionResult;
+pub use hardness::BlockHardness;
+pub use handlers::{InteractionResult, can_survive_on, chest_partner, nether_portal};
 use classes::BlockClass;
 
 pub struct Block {
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, sync::{Arc, RwLock}, time::Duration};
 use nalgebra::Vector3;
 use legion::*;
+use crate::dimensions::Dimensions;
 use tokio::sync::broadcast::Receiver;
 
 use super::{bucket::Bucket, coords::BucketCoords, events::{EntityEvent, EntityEventData}};
@@ -8,12 +9,10 @@ use super::{bucket::Bucket, coords::BucketCoords, events::{EntityEvent, EntityEv
 const UNLOAD_TIME: Duration = Duration::from_secs(10);
 
 #[system]
-pub fn unload_buckets(#[resource] tracker: &mut EntityTracker) {
-    let buckets = tracker.buckets.get_mut().unwrap();
-    buckets.retain(|_, bucket| {
-        let mut bucket = bucket.write().unwrap();
-        bucket.time_unobserved() < UNLOAD_TIME
-    });
+pub fn unload_buckets(#[resource] dimensions: &mut Dimensions) {
+    for dimension in dimensions.iter_mut() {
+        dimension.tracker.unload_unobserved();
+    }
 }
 
 pub struct EntityTracker {
@@ -27,6 +26,14 @@ impl EntityTracker {
         }
     }
 
+    fn unload_unobserved(&mut self) {
+        let buckets = self.buckets.get_mut().unwrap();
+        buckets.retain(|_, bucket| {
+            let mut bucket = bucket.write().unwrap();
+            bucket.time_unobserved() < UNLOAD_TIME
+        });
+    }
+
     pub fn add(&self, id: u32, entity: Entity, pos: &Vector3<f64>) {
         let coords = BucketCoords::from_pos(pos);
         let bucket = self.get_or_create(&coords);
@@ -1,5 +1,6 @@
 use legion::Entity;
 use nalgebra::Vector3;
+use crate::items::ItemStack;
 use super::coords::BucketCoords;
 
 #[derive(Clone, Debug)]
@@ -35,5 +36,19 @@ pub enum EntityEventData {
     },
     RotateHead {
         yaw: f32,
-    }
+    },
+    /// Cracks of a block the entity is digging
+    BreakBlock {
+        pos: Vector3<i32>,
+        stage: i8,
+    },
+    /// Someone picked up part or all of this item
+    Collect {
+        collector: u32,
+        count: u8,
+    },
+    /// What's left of an item after part of it was picked up
+    ChangeItem {
+        stack: ItemStack,
+    },
 }
\ No newline at end of file
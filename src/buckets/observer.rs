@@ -26,6 +26,13 @@ impl Observer {
         }
     }
 
+    /// Stops watching every bucket, used when changing dimensions since
+    /// the client forgets all entities anyway
+    pub fn clear(&mut self) {
+        self.last_coords = None;
+        self.observed.clear();
+    }
+
     pub fn update(&mut self, pos: &Vector3<f64>, tracker: &EntityTracker)
         -> Vec<EntityEvent>
     {
@@ -1,39 +1,84 @@
+use crate::biomes::Biome;
 use crate::blocks::Block;
+use crate::biomes::Precipitation;
+use crate::block_entities::{BlockEntity, TickChange};
+use crate::level::Weather;
 use super::ChunkCoords;
 use super::ChunkData;
+use super::ChunkPackets;
 use super::WorldView;
 use super::events::ChunkEvent;
 use super::saving::ChunkSaver;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem::take;
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use std::time::Instant;
-use nalgebra::{Vector3, vector};
+use nalgebra::vector;
 use rand::{Rng, thread_rng};
+use tokio::task::JoinHandle;
+
+/// Changes in a tick after which the whole chunk is sent again
+const FULL_RESEND_THRESHOLD: usize = 1024;
 
 #[derive(Clone)]
 pub struct Chunk {
+    coords: ChunkCoords,
     data: Option<Arc<RwLock<ChunkData>>>,
+    /// Encoded packets, dropped whenever a block changes
+    packets: Arc<RwLock<Option<ChunkPackets>>>,
+    loading: Arc<Mutex<Option<JoinHandle<()>>>>,
     subscribers: Arc<RwLock<HashMap<u32, Box<dyn Fn(ChunkEvent) + Send + Sync>>>>,
     unobserved_since: Arc<RwLock<Option<Instant>>>,
+    dirty: Arc<AtomicBool>,
+    /// Blocks changed since the last flush, the last change of each position wins
+    changes: Arc<Mutex<HashMap<(usize, usize, usize), &'static Block>>>,
+    /// Block entities changed since the last flush
+    entity_changes: Arc<Mutex<HashSet<(usize, usize, usize)>>>,
 }
 
 impl Chunk {
-    pub fn new() -> Self {
+    pub fn new(coords: ChunkCoords) -> Self {
         Self {
+            coords,
             data: None,
+            packets: Default::default(),
+            loading: Default::default(),
+            changes: Default::default(),
+            entity_changes: Default::default(),
             subscribers: Default::default(),
             unobserved_since: Arc::new(RwLock::new(Some(Instant::now()))),
+            dirty: Arc::new(AtomicBool::new(false)),
         }
     }
 
     pub fn load(&mut self, data: ChunkData) {
-        let data = Arc::new(RwLock::new(data));
-        self.data = Some(data.clone());
-        self.emit_event(ChunkEvent::ChunkLoaded {
-            chunk: data,
-        })
+        self.data = Some(Arc::new(RwLock::new(data)));
+        if let Some(packets) = self.get_packets() {
+            self.emit_event(ChunkEvent::ChunkLoaded { packets })
+        }
+    }
+
+    /// Task loading or generating the chunk, aborted if the chunk
+    /// stops being needed before it's done
+    pub fn set_loading(&self, task: JoinHandle<()>) {
+        *self.loading.lock().unwrap() = Some(task);
+    }
+
+    pub fn cancel_loading(&self) {
+        if let Some(task) = self.loading.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    pub fn is_observed(&self) -> bool {
+        !self.subscribers.read().unwrap().is_empty()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.data.is_some()
     }
 
     pub fn get_block(&self, x: usize, y: usize, z: usize) -> &'static Block {
@@ -43,28 +88,111 @@ impl Chunk {
         }
     }
 
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> &'static Biome {
+        match &self.data {
+            Some(data) => data.read().unwrap().get_biome(x, y, z),
+            None => Biome::plains(),
+        }
+    }
+
     pub fn set_block(&self, x: usize, y: usize, z: usize, block: &'static Block) {
         if let Some(data) = &self.data {
-            data.write().unwrap().set_block(x, y, z, block);
-            self.emit_event(ChunkEvent::BlockChanged {
-                x, y, z, new: block,
-            });
+            let mut data = data.write().unwrap();
+            data.set_block(x, y, z, block);
+            data.modified = true;
+            drop(data);
+            *self.packets.write().unwrap() = None;
+            self.dirty.store(true, Ordering::Relaxed);
+            self.changes.lock().unwrap().insert((x, y, z), block);
+        }
+    }
+
+    pub fn get_block_entity(&self, x: usize, y: usize, z: usize) -> Option<BlockEntity> {
+        self.data.as_ref()?.read().unwrap()
+            .get_block_entity(x, y, z).cloned()
+    }
+
+    pub fn set_block_entity(&self, x: usize, y: usize, z: usize, entity: BlockEntity) {
+        if let Some(data) = &self.data {
+            let mut data = data.write().unwrap();
+            data.set_block_entity(x, y, z, entity);
+            data.modified = true;
+            drop(data);
+            *self.packets.write().unwrap() = None;
+            self.dirty.store(true, Ordering::Relaxed);
+            self.entity_changes.lock().unwrap().insert((x, y, z));
+        }
+    }
+
+    /// Single changes go out on their own, sections with more get a Multi 
+    /// Block Change and past a point it's cheaper to send the whole chunk
+    pub fn flush_changes(&self) {
+        let changes = take(&mut *self.changes.lock().unwrap());
+        if changes.len() > FULL_RESEND_THRESHOLD {
+            self.entity_changes.lock().unwrap().clear();
+            if let Some(packets) = self.get_packets() {
+                self.emit_event(ChunkEvent::ChunkResent { packets });
+            }
+            return;
+        }
+        let mut sections: BTreeMap<usize, Vec<(usize, usize, usize, &'static Block)>> = 
+            BTreeMap::new();
+        for ((x, y, z), block) in changes {
+            sections.entry(y / 16).or_default().push((x, y, z, block));
+        }
+        for (section, blocks) in sections {
+            if let [(x, y, z, new)] = blocks[..] {
+                self.emit_event(ChunkEvent::BlockChanged { x, y, z, new });
+            } else {
+                self.emit_event(ChunkEvent::SectionChanged {
+                    section,
+                    blocks: Arc::new(blocks),
+                });
+            }
+        }
+        self.flush_block_entities();
+    }
+
+    /// Block entities go after blocks so clients already have the block
+    /// the data is for
+    fn flush_block_entities(&self) {
+        let changes = take(&mut *self.entity_changes.lock().unwrap());
+        for (x, y, z) in changes {
+            let entity = match self.get_block_entity(x, y, z) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            if let Some(action) = entity.update_action() {
+                self.emit_event(ChunkEvent::BlockEntityChanged {
+                    x, y, z,
+                    action,
+                    nbt: Arc::new(entity.to_network(self.coords.global(x, y, z))),
+                });
+            }
         }
     }
 
     pub fn subscribe<F>(&self, id: u32, callback: F)
         where F: Fn(ChunkEvent) + 'static + Send + Sync
     {
-        if let Some(data) = &self.data {
-            callback(ChunkEvent::ChunkLoaded { 
-                chunk: data.clone(),
-            });
+        if let Some(packets) = self.get_packets() {
+            callback(ChunkEvent::ChunkLoaded { packets });
         }
         self.subscribers.write().unwrap()
             .insert(id, Box::new(callback));
         *self.unobserved_since.write().unwrap() = None;
     }
 
+    /// Encodes the chunk if nobody did since it last changed
+    pub fn get_packets(&self) -> Option<ChunkPackets> {
+        let data = self.data.as_ref()?;
+        let mut packets = self.packets.write().unwrap();
+        if packets.is_none() {
+            *packets = Some(ChunkPackets::encode(self.coords, &data.read().unwrap()));
+        }
+        packets.clone()
+    }
+
     pub fn unsubscribe(&self, id: u32) {
         let mut subscribers = self.subscribers.write().unwrap();
         subscribers.remove(&id);
@@ -81,7 +209,9 @@ impl Chunk {
         }
     }
 
-    pub fn random_tick(&self, view: &WorldView) {
+    pub fn random_tick(&self, view: &WorldView, ticks_per_section: i32, 
+        weather: &Weather) 
+    {
         let mut rng = thread_rng();
         let bitmask = self.data.as_ref().map(|data| 
             data.read().unwrap()
@@ -89,7 +219,7 @@ impl Chunk {
         if let Some(mut bitmask) = bitmask {
             for section in 0..16 {
                 if bitmask & 1 == 1 {
-                    for _tick in 0..3 {
+                    for _tick in 0..ticks_per_section {
                         let y_delta = section * 16;
                         let x = rng.gen_range(0..16);
                         let y = rng.gen_range(0..16);
@@ -101,16 +231,78 @@ impl Chunk {
                             x as usize, 
                             (y + y_delta) as usize, 
                             z as usize
-                        ).random_tick(&view);
+                        ).random_tick(&view, weather);
                     }
                 }
                 bitmask >>= 1;
             }
+            self.surface_tick(view, weather);
+        }
+    }
+
+    /// Furnaces burning and cooking. Clients don't see what's inside them
+    /// so the chunk packets stay valid, only the lit state is sent
+    pub fn tick_block_entities(&self, view: &WorldView) {
+        let changes = match &self.data {
+            Some(data) => data.write().unwrap().tick_block_entities(),
+            None => return,
+        };
+        if changes.is_empty() {
+            return;
+        }
+        if let Some(data) = &self.data {
+            data.write().unwrap().modified = true;
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        for ((x, y, z), change) in changes {
+            if let TickChange::Lit(lit) = change {
+                let block = self.get_block(x, y, z);
+                let mut props = block.props.clone();
+                if props.contains_key("lit") {
+                    props.insert("lit".into(), lit.to_string());
+                    if let Ok(new) = block.btype.with_props(&props) {
+                        view.set(x as i32, y as i32, z as i32, new);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ticks the top of a random column, where snow and ice form
+    /// and lightning strikes
+    fn surface_tick(&self, view: &WorldView, weather: &Weather) {
+        let mut rng = thread_rng();
+        let lightning = weather.is_thundering() && rng.gen_range(0..100000) == 0;
+        if !lightning && rng.gen_range(0..16) != 0 {
+            return;
+        }
+        let x = rng.gen_range(0..16);
+        let z = rng.gen_range(0..16);
+        let top = self.data.as_ref().and_then(|data|
+            data.read().unwrap().get_top_block(x, z));
+        if let Some(y) = top {
+            let mut view = view.clone();
+            view.displace(vector!(x as i32, y as i32, z as i32));
+            if lightning {
+                if view.biome().precipitation_at(y as i32) == Precipitation::Rain {
+                    weather.strike_lightning(view.get_center() + vector!(0, 1, 0));
+                }
+                return;
+            }
+            self.get_block(x, y, z).random_tick(&view, weather);
+            view.displace(vector!(0, 1, 0));
+            view.get(0, 0, 0).random_tick(&view, weather);
         }
     }
 
+    /// True if the chunk was modified since it was last saved
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
     pub fn save(&self, coords: ChunkCoords, saver: &mut ChunkSaver) {
         if let Some(data) = &self.data {
+            self.dirty.store(false, Ordering::Relaxed);
             saver.save(coords, data.clone());
         }
     }
@@ -121,4 +313,68 @@ impl Chunk {
             callback(event.clone());
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::BlockType;
+    use super::*;
+
+    fn block(name: &str) -> &'static Block {
+        let btype = BlockType::from_name(name).unwrap();
+        btype.with_props(&btype.default_state).unwrap()
+    }
+
+    /// A loaded chunk and what its subscriber got after subscribing
+    fn watched_chunk() -> (Chunk, Arc<Mutex<Vec<ChunkEvent>>>) {
+        let mut chunk = Chunk::new(ChunkCoords(0, 0));
+        chunk.load(ChunkData::new());
+        let events = Arc::new(Mutex::new(vec![]));
+        let received = events.clone();
+        chunk.subscribe(0, move |event| received.lock().unwrap().push(event));
+        events.lock().unwrap().clear();
+        (chunk, events)
+    }
+
+    #[test]
+    fn changes_are_batched_per_section() {
+        let (chunk, events) = watched_chunk();
+        let stone = block("minecraft:stone");
+        let dirt = block("minecraft:dirt");
+        chunk.set_block(1, 5, 2, stone);
+        chunk.set_block(0, 20, 0, stone);
+        chunk.set_block(3, 31, 4, stone);
+        // Only the last change of a position is sent
+        chunk.set_block(0, 20, 0, dirt);
+        chunk.flush_changes();
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            ChunkEvent::BlockChanged { x: 1, y: 5, z: 2, new } => assert_eq!(new.id, stone.id),
+            _ => panic!("expected a single block change in section 0"),
+        }
+        match &events[1] {
+            ChunkEvent::SectionChanged { section: 1, blocks } => {
+                let mut blocks: Vec<_> = blocks.iter()
+                    .map(|(x, y, z, new)| (*x, *y, *z, new.id))
+                    .collect();
+                blocks.sort();
+                assert_eq!(blocks, [(0, 20, 0, dirt.id), (3, 31, 4, stone.id)]);
+            },
+            _ => panic!("expected a section change for section 1"),
+        }
+    }
+
+    #[test]
+    fn many_changes_resend_the_chunk() {
+        let (chunk, events) = watched_chunk();
+        let stone = block("minecraft:stone");
+        for i in 0..=FULL_RESEND_THRESHOLD {
+            chunk.set_block(i % 16, i / 256, (i / 16) % 16, stone);
+        }
+        chunk.flush_changes();
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ChunkEvent::ChunkResent { .. }));
+    }
 }
\ No newline at end of file
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
 use std::iter::repeat_with;
 use anvil_nbt::CompoundTag;
+use nbt::Value as Nbt;
 
+use crate::biomes::{Biome, PLAINS};
+use crate::block_entities::{BlockEntity, TickChange};
 use crate::blocks::Block;
 use crate::chunks::ChunkCoords;
 
@@ -10,9 +14,17 @@ use super::{
     section::{Section, SECTION_WIDTH}
 };
 
+/// Biomes are stored in cells of 4x4x4 blocks
+const BIOME_CELLS: usize = 4 * 4 * 64;
+
 pub struct ChunkData {
     sections: Vec<Option<Section>>,
+    biomes: Vec<u16>,
     pub heightmap: HeightMap,
+    /// Keyed by position inside the chunk
+    block_entities: HashMap<(usize, usize, usize), BlockEntity>,
+    /// Changed in game at some point, as opposed to just generated
+    pub modified: bool,
 }
 
 impl ChunkData {
@@ -21,14 +33,20 @@ impl ChunkData {
             sections: repeat_with(|| None)
                 .take(CHUNK_HEIGHT / SECTION_WIDTH)
                 .collect(),
+            biomes: vec![PLAINS; BIOME_CELLS],
             heightmap: HeightMap::new(),
+            block_entities: HashMap::new(),
+            modified: false,
         }
     }
     
     pub fn from_sections(sections: Vec<Option<Section>>) -> Self {
         Self {
             sections,
+            biomes: vec![PLAINS; BIOME_CELLS],
             heightmap: HeightMap::new(),
+            block_entities: HashMap::new(),
+            modified: false,
         }
     }
 
@@ -43,6 +61,7 @@ impl ChunkData {
     }
 
     pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: &'static Block) {
+        let old = self.get_block(x, y, z);
         let section = y / SECTION_WIDTH;
         match &mut self.sections[section] {
             Some(section) => {
@@ -54,10 +73,78 @@ impl ChunkData {
                 self.sections[section] = Some(new_sect);
             }
         }
+        self.sync_block_entity(x, y, z, old, block);
+    }
+
+    /// Blocks that need a block entity get an empty one when placed,
+    /// and lose it when replaced by something else. The ones read from
+    /// blocks we don't model stay while the block type does
+    fn sync_block_entity(&mut self, x: usize, y: usize, z: usize, old: &'static Block,
+        block: &'static Block)
+    {
+        match block.btype.block_entity_id() {
+            Some(id) => {
+                let current = self.block_entities.get(&(x, y, z));
+                if current.map(|entity| entity.id != id).unwrap_or(true) {
+                    self.block_entities.insert((x, y, z), BlockEntity::new(id));
+                }
+            },
+            None => if old.btype.name != block.btype.name {
+                self.block_entities.remove(&(x, y, z));
+            },
+        }
+    }
+
+    pub fn get_block_entity(&self, x: usize, y: usize, z: usize) -> Option<&BlockEntity> {
+        self.block_entities.get(&(x, y, z))
+    }
+
+    /// Does nothing if the block there can't have a block entity
+    pub fn set_block_entity(&mut self, x: usize, y: usize, z: usize, entity: BlockEntity) {
+        if self.get_block(x, y, z).btype.block_entity_id().is_some() {
+            self.block_entities.insert((x, y, z), entity);
+        }
+    }
+
+    /// Ticks every block entity, returning the ones that changed
+    pub fn tick_block_entities(&mut self) -> Vec<((usize, usize, usize), TickChange)> {
+        self.block_entities.iter_mut()
+            .map(|(pos, entity)| (*pos, entity.tick()))
+            .filter(|(_, change)| *change != TickChange::None)
+            .collect()
+    }
+
+    /// Block entities as clients get them in the Chunk Data packet
+    pub fn get_block_entities_nbt(&self, coords: ChunkCoords) -> Vec<Nbt> {
+        self.block_entities.iter()
+            .map(|(pos, entity)| entity.to_network(coords.global(pos.0, pos.1, pos.2)))
+            .collect()
+    }
+
+    /// Height of the highest block that isn't air in a column
+    pub fn get_top_block(&self, x: usize, z: usize) -> Option<usize> {
+        (0..CHUNK_HEIGHT).rev()
+            .find(|y| self.get_block(x, *y, z).material.name != "minecraft:air")
     }
 
     pub fn get_biome_map(&self) -> Vec<u16> {
-        vec![0; 1024]
+        self.biomes.clone()
+    }
+
+    pub fn set_biome_map(&mut self, biomes: Vec<u16>) {
+        if biomes.len() == BIOME_CELLS {
+            self.biomes = biomes;
+        }
+    }
+
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> &'static Biome {
+        let index = (y >> 2) << 4 | (z >> 2) << 2 | x >> 2;
+        Biome::from_id(self.biomes[index])
+            .unwrap_or_else(|| Biome::plains())
+    }
+
+    pub fn fill_biome(&mut self, biome: &Biome) {
+        self.biomes = vec![biome.id; BIOME_CELLS];
     }
 
     pub fn get_sections_bitmask(&self) -> u16 {
@@ -94,7 +181,78 @@ impl ChunkData {
             }
         }
         level_tag.insert_compound_tag_vec("Sections", section_tags);
+        level_tag.insert_compound_tag_vec("TileEntities", self.block_entities.iter()
+            .map(|(pos, entity)| entity.to_nbt(coords.global(pos.0, pos.1, pos.2)))
+            .collect());
+        level_tag.insert_bool("Modified", self.modified);
+        level_tag.insert_i32_vec("Biomes", 
+            self.biomes.iter().map(|id| *id as i32).collect());
         chunk_tag.insert_compound_tag("Level", level_tag);
         chunk_tag
     }
+
+    /// Whether a saved chunk was ever changed in game, vanilla chunks
+    /// count if a player spent any time near them
+    pub fn was_modified(tag: &CompoundTag) -> bool {
+        tag.get_compound_tag("Level")
+            .map(|level| {
+                level.get_bool("Modified").unwrap_or(false)
+                    || level.get_i64("InhabitedTime").unwrap_or(0) > 0
+            })
+            .unwrap_or(true)
+    }
+
+    /// Reads the TileEntities of a saved chunk, after its blocks were set.
+    /// All of them are kept, even for blocks without a modeled block entity
+    pub fn load_block_entities(&mut self, tags: &[&CompoundTag]) {
+        for tag in tags {
+            let pos = (tag.get_i32("x"), tag.get_i32("y"), tag.get_i32("z"));
+            if let (Ok(x), Ok(y), Ok(z)) = pos {
+                if y < 0 || y as usize >= CHUNK_HEIGHT {
+                    continue;
+                }
+                if let Some(entity) = BlockEntity::from_nbt(tag) {
+                    self.block_entities.insert(((x & 15) as usize, y as usize, (z & 15) as usize),
+                        entity);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use crate::blocks::BlockType;
+    use super::*;
+
+    fn block(name: &str) -> &'static Block {
+        let btype = BlockType::from_name(name).unwrap();
+        btype.with_props(&btype.default_state).unwrap()
+    }
+
+    #[test]
+    fn unmodeled_block_entities_stay_with_their_block() {
+        let mut chunk = ChunkData::new();
+        let hopper = block("minecraft:hopper");
+        chunk.set_block(1, 10, 2, hopper);
+        let mut tag = CompoundTag::new();
+        tag.insert_str("id", "minecraft:hopper");
+        tag.insert_i32("x", 17);
+        tag.insert_i32("y", 10);
+        tag.insert_i32("z", 2);
+        tag.insert_i32("TransferCooldown", 3);
+        chunk.load_block_entities(&[&tag]);
+        assert!(chunk.get_block_entity(1, 10, 2).is_some());
+
+        // Another state of the same block keeps it
+        let mut props = hopper.props.clone();
+        props.insert("enabled".into(), "false".into());
+        chunk.set_block(1, 10, 2, hopper.btype.with_props(&props).unwrap());
+        let saved = chunk.get_block_entity(1, 10, 2).unwrap().to_nbt(vector!(17, 10, 2));
+        assert_eq!(saved.get_i32("TransferCooldown").unwrap(), 3);
+
+        chunk.set_block(1, 10, 2, block("minecraft:stone"));
+        assert!(chunk.get_block_entity(1, 10, 2).is_none());
+    }
 }
@@ -1,12 +1,18 @@
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+
+use super::ChunkPackets;
+use nbt::Value as Nbt;
 
-use super::ChunkData;
 use crate::blocks::Block;
 
 #[derive(Clone)]
 pub enum ChunkEvent {
     ChunkLoaded {
-        chunk: Arc<RwLock<ChunkData>>,
+        packets: ChunkPackets,
+    },
+    /// Too much changed at once to send it block by block
+    ChunkResent {
+        packets: ChunkPackets,
     },
     BlockChanged {
         x: usize,
@@ -14,4 +20,16 @@ pub enum ChunkEvent {
         z: usize,
         new: &'static Block,
     },
+    /// Several blocks changed in the same 16x16x16 section
+    SectionChanged {
+        section: usize,
+        blocks: Arc<Vec<(usize, usize, usize, &'static Block)>>,
+    },
+    BlockEntityChanged {
+        x: usize,
+        y: usize,
+        z: usize,
+        action: u8,
+        nbt: Arc<Nbt>,
+    },
 }
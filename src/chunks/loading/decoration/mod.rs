@@ -0,0 +1,152 @@
+mod ores;
+mod trees;
+mod plants;
+
+use nalgebra::Vector3;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::biomes::Biome;
+use crate::blocks::Block;
+use crate::chunks::{ChunkData, ChunkCoords};
+use trees::TreeKind;
+
+const MAX_HEIGHT: i32 = 256;
+const DECORATION_SALT: u64 = 0x5DEECE66D;
+
+/// Lets features find the ground in chunks that aren't generated yet
+pub trait SurfaceSampler {
+    /// Height and block of the top of a column, None if it's underwater
+    fn surface_at(&self, x: i32, z: i32) -> Option<(usize, &'static Block, &'static Biome)>;
+}
+
+/// Adds ores, trees and plants to freshly generated chunks.
+/// Features can start in a neighbour chunk and reach into this one, so
+/// the features of the 8 neighbours are built again and only the blocks
+/// that land in this chunk are kept. Everything comes from the seed and
+/// the chunk coords, so both chunks agree on where things go
+pub struct Decorator {
+    seed: i64,
+}
+
+impl Decorator {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed,
+        }
+    }
+
+    pub fn decorate(&self, chunk: &mut ChunkData, coords: ChunkCoords,
+        surface: &impl SurfaceSampler)
+    {
+        let mut writer = ChunkWriter {
+            chunk,
+            coords,
+        };
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let origin = ChunkCoords(coords.0 + dx, coords.1 + dz);
+                let mut rng = self.chunk_rng(origin);
+                ores::place_ores(&mut writer, origin, &mut rng);
+                place_trees(&mut writer, origin, &mut rng, surface);
+            }
+        }
+        let mut rng = self.chunk_rng(coords);
+        plants::place_plants(&mut writer, &mut rng);
+    }
+
+    fn chunk_rng(&self, coords: ChunkCoords) -> ChaCha8Rng {
+        let seed = (self.seed as u64 ^ DECORATION_SALT)
+            ^ (coords.0 as u64).wrapping_mul(341873128712)
+            ^ (coords.1 as u64).wrapping_mul(132897987541);
+        ChaCha8Rng::seed_from_u64(seed)
+    }
+}
+
+/// What grows in each biome
+struct BiomeFeatures {
+    trees: &'static [TreeKind],
+    trees_per_chunk: f32,
+    grass: usize,
+    flowers: &'static [&'static str],
+    flower_count: usize,
+}
+
+const PLAIN_FLOWERS: &[&str] = &[
+    "minecraft:dandelion", "minecraft:poppy", "minecraft:azure_bluet",
+    "minecraft:oxeye_daisy", "minecraft:cornflower",
+];
+const FOREST_FLOWERS: &[&str] = &[
+    "minecraft:dandelion", "minecraft:poppy", "minecraft:lily_of_the_valley",
+    "minecraft:lilac", "minecraft:rose_bush", "minecraft:peony",
+];
+
+fn biome_features(biome: &Biome) -> BiomeFeatures {
+    use TreeKind::*;
+    let (trees, trees_per_chunk, grass, flowers, flower_count): (&'static [TreeKind], _, _, _, _) =
+        match biome.name.as_str() {
+            "minecraft:plains" => (&[Oak], 0.1, 24, PLAIN_FLOWERS, 3),
+            "minecraft:forest" => (&[Oak, Oak, Oak, Birch], 10.0, 6, FOREST_FLOWERS, 4),
+            "minecraft:birch_forest" => (&[Birch], 10.0, 6, FOREST_FLOWERS, 2),
+            "minecraft:taiga" => (&[Spruce], 10.0, 8, &[], 0),
+            "minecraft:snowy_taiga" => (&[Spruce], 8.0, 2, &[], 0),
+            "minecraft:snowy_tundra" => (&[Spruce], 0.1, 0, &[], 0),
+            "minecraft:mountains" | "minecraft:snowy_mountains" => (&[Spruce, Oak], 0.5, 2, &[], 0),
+            "minecraft:savanna" => (&[Oak], 1.0, 20, PLAIN_FLOWERS, 1),
+            _ => (&[], 0.0, 0, &[], 0),
+        };
+    BiomeFeatures {
+        trees,
+        trees_per_chunk,
+        grass,
+        flowers,
+        flower_count,
+    }
+}
+
+fn place_trees(writer: &mut ChunkWriter, origin: ChunkCoords, rng: &mut ChaCha8Rng,
+    surface: &impl SurfaceSampler)
+{
+    let center = origin.global(8, 0, 8);
+    let features = match surface.surface_at(center.x, center.z) {
+        Some((_, _, biome)) => biome_features(biome),
+        None => return,
+    };
+    let extra = if rng.gen::<f32>() < features.trees_per_chunk.fract() { 1 } else { 0 };
+    let count = features.trees_per_chunk as usize + extra;
+    for _ in 0..count {
+        let pos = origin.global(rng.gen_range(0..16), 0, rng.gen_range(0..16));
+        let kind = features.trees[rng.gen_range(0..features.trees.len())];
+        let tree = trees::Tree::new(kind, rng);
+        if let Some((height, block, _)) = surface.surface_at(pos.x, pos.z) {
+            if block.btype.name == "minecraft:grass_block" {
+                tree.place(writer, Vector3::new(pos.x, height as i32 + 1, pos.z));
+            }
+        }
+    }
+}
+
+/// Writes to a chunk using global coords, ignoring blocks outside of it
+pub struct ChunkWriter<'a> {
+    chunk: &'a mut ChunkData,
+    coords: ChunkCoords,
+}
+
+impl<'a> ChunkWriter<'a> {
+    pub fn get(&self, pos: Vector3<i32>) -> Option<&'static Block> {
+        self.local(pos).map(|(x, y, z)| self.chunk.get_block(x, y, z))
+    }
+
+    pub fn set(&mut self, pos: Vector3<i32>, block: &'static Block) {
+        if let Some((x, y, z)) = self.local(pos) {
+            self.chunk.set_block(x, y, z, block);
+        }
+    }
+
+    fn local(&self, pos: Vector3<i32>) -> Option<(usize, usize, usize)> {
+        if ChunkCoords::from_block(&pos) != self.coords || pos.y < 0 || pos.y >= MAX_HEIGHT {
+            return None;
+        }
+        Some(self.coords.relative(&pos))
+    }
+}
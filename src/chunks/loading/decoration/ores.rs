@@ -0,0 +1,42 @@
+use block_macro::block_id;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::blocks::Block;
+use crate::chunks::ChunkCoords;
+use super::ChunkWriter;
+
+struct Ore {
+    block: u16,
+    /// Blocks in a vein, at most 16 so veins never reach past a neighbour chunk
+    size: usize,
+    veins: usize,
+    max_y: usize,
+}
+
+const ORES: &[Ore] = &[
+    Ore { block: block_id!(coal_ore), size: 16, veins: 20, max_y: 128 },
+    Ore { block: block_id!(iron_ore), size: 8, veins: 20, max_y: 64 },
+    Ore { block: block_id!(gold_ore), size: 8, veins: 2, max_y: 32 },
+    Ore { block: block_id!(redstone_ore), size: 7, veins: 8, max_y: 16 },
+    Ore { block: block_id!(diamond_ore), size: 7, veins: 1, max_y: 16 },
+    Ore { block: block_id!(lapis_ore), size: 6, veins: 1, max_y: 32 },
+];
+
+/// Random walks through stone starting in the origin chunk
+pub fn place_ores(writer: &mut ChunkWriter, origin: ChunkCoords, rng: &mut ChaCha8Rng) {
+    for ore in ORES {
+        let block = Block::from_state_id(ore.block).unwrap();
+        for _ in 0..ore.veins {
+            let mut pos = origin.global(
+                rng.gen_range(0..16), rng.gen_range(1..ore.max_y), rng.gen_range(0..16));
+            for _ in 0..ore.size {
+                if writer.get(pos).map(|b| b.id == block_id!(stone)).unwrap_or(false) {
+                    writer.set(pos, block);
+                }
+                let axis = rng.gen_range(0..3);
+                pos[axis] += if rng.gen() { 1 } else { -1 };
+            }
+        }
+    }
+}
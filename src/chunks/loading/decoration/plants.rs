@@ -0,0 +1,58 @@
+use nalgebra::{Vector3, vector};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::blocks::{Block, BlockType, can_survive_on};
+use super::{ChunkWriter, biome_features};
+
+const TALL_GRASS_CHANCE: f64 = 0.1;
+
+/// Grass and flowers only take one column, so they are placed in their
+/// own chunk after the trees, on top of whatever ended up there
+pub fn place_plants(writer: &mut ChunkWriter, rng: &mut ChaCha8Rng) {
+    let top = writer.chunk.get_top_block(8, 8).unwrap_or(0);
+    let features = biome_features(writer.chunk.get_biome(8, top, 8));
+    for _ in 0..features.grass {
+        let name = if rng.gen::<f64>() < TALL_GRASS_CHANCE {
+            "minecraft:tall_grass"
+        } else {
+            "minecraft:grass"
+        };
+        place_plant(writer, name, rng);
+    }
+    for _ in 0..features.flower_count {
+        let name = features.flowers[rng.gen_range(0..features.flowers.len())];
+        place_plant(writer, name, rng);
+    }
+}
+
+fn place_plant(writer: &mut ChunkWriter, name: &str, rng: &mut ChaCha8Rng) {
+    let (x, z) = (rng.gen_range(0..16), rng.gen_range(0..16));
+    let top = match writer.chunk.get_top_block(x, z) {
+        Some(top) => top,
+        None => return,
+    };
+    let ground = writer.coords.global(x, top, z);
+    let above = ground + vector!(0, 1, 0);
+    if !writer.get(ground).map(can_survive_on).unwrap_or(false) || !is_air(writer, above) {
+        return;
+    }
+    let btype = BlockType::from_name(name).unwrap();
+    if btype.is_two_blocks_tall() {
+        let upper_pos = above + vector!(0, 1, 0);
+        if !is_air(writer, upper_pos) {
+            return;
+        }
+        let (lower, upper) = btype.two_block_halves(&btype.default_state);
+        writer.set(above, lower);
+        writer.set(upper_pos, upper);
+    } else {
+        writer.set(above, btype.with_props(&btype.default_state).unwrap());
+    }
+}
+
+fn is_air(writer: &ChunkWriter, pos: Vector3<i32>) -> bool {
+    writer.get(pos)
+        .map(|block| block.id == Block::air().id)
+        .unwrap_or(false)
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use block_macro::block_id;
+use nalgebra::{Vector3, vector};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::blocks::{Block, BlockType};
+use super::ChunkWriter;
+
+const MAX_LEAF_DISTANCE: i32 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeKind {
+    Oak,
+    Birch,
+    Spruce,
+}
+
+impl TreeKind {
+    fn wood(&self) -> &'static str {
+        match self {
+            Self::Oak => "oak",
+            Self::Birch => "birch",
+            Self::Spruce => "spruce",
+        }
+    }
+
+    fn log(&self) -> &'static Block {
+        let btype = BlockType::from_name(&format!("minecraft:{}_log", self.wood())).unwrap();
+        let mut props = btype.default_state.clone();
+        props.insert("axis".into(), "y".into());
+        btype.with_props(&props).unwrap()
+    }
+
+    fn leaves(&self, distance: i32) -> &'static Block {
+        let btype = BlockType::from_name(&format!("minecraft:{}_leaves", self.wood())).unwrap();
+        let mut props: BTreeMap<String, String> = btype.default_state.clone();
+        props.insert("distance".into(), distance.to_string());
+        props.insert("persistent".into(), "false".into());
+        btype.with_props(&props).unwrap()
+    }
+}
+
+/// Shape of a tree relative to the bottom of its trunk, picked before
+/// placing so it doesn't depend on what's already in the chunk
+pub struct Tree {
+    kind: TreeKind,
+    height: i32,
+    leaves: Vec<Vector3<i32>>,
+}
+
+impl Tree {
+    pub fn new(kind: TreeKind, rng: &mut ChaCha8Rng) -> Self {
+        let height = match kind {
+            TreeKind::Oak => rng.gen_range(4..7),
+            TreeKind::Birch => rng.gen_range(5..8),
+            TreeKind::Spruce => rng.gen_range(6..10),
+        };
+        let leaves = match kind {
+            TreeKind::Oak | TreeKind::Birch => blob_leaves(height, rng),
+            TreeKind::Spruce => spruce_leaves(height, rng),
+        };
+        Self {
+            kind,
+            height,
+            leaves,
+        }
+    }
+
+    /// Leaves only go where nothing solid is, logs can also replace leaves
+    pub fn place(&self, writer: &mut ChunkWriter, base: Vector3<i32>) {
+        for leaf in &self.leaves {
+            let pos = base + leaf;
+            if let Some(current) = writer.get(pos) {
+                if current.material.replaceable {
+                    writer.set(pos, self.kind.leaves(self.leaf_distance(leaf)));
+                }
+            }
+        }
+        let log = self.kind.log();
+        for y in 0..self.height {
+            let pos = base + vector!(0, y, 0);
+            if let Some(current) = writer.get(pos) {
+                if current.material.replaceable || current.material.name == "minecraft:leaves" {
+                    writer.set(pos, log);
+                }
+            }
+        }
+        let below = base - vector!(0, 1, 0);
+        if let Some(current) = writer.get(below) {
+            if current.btype.name == "minecraft:grass_block" {
+                writer.set(below, Block::from_state_id(block_id!(dirt)).unwrap());
+            }
+        }
+    }
+
+    /// Steps to the closest log, which is what vanilla keeps in the distance prop
+    fn leaf_distance(&self, leaf: &Vector3<i32>) -> i32 {
+        let trunk_y = leaf.y.clamp(0, self.height - 1);
+        let distance = leaf.x.abs() + leaf.z.abs() + (leaf.y - trunk_y).abs();
+        distance.clamp(1, MAX_LEAF_DISTANCE)
+    }
+}
+
+/// Oak and birch foliage, two wide layers and two narrow ones at the top,
+/// with some of the corners missing
+fn blob_leaves(height: i32, rng: &mut ChaCha8Rng) -> Vec<Vector3<i32>> {
+    let mut leaves = vec![];
+    for y in height - 3..=height {
+        let radius: i32 = if y >= height - 1 { 1 } else { 2 };
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                let corner = x.abs() == radius && z.abs() == radius;
+                let skip = rng.gen::<bool>();
+                if corner && (y == height || skip) {
+                    continue;
+                }
+                if x == 0 && z == 0 && y < height {
+                    continue;
+                }
+                leaves.push(vector!(x, y, z));
+            }
+        }
+    }
+    leaves
+}
+
+/// Spruce foliage, a cone of layers that get wider and narrower going down
+fn spruce_leaves(height: i32, rng: &mut ChaCha8Rng) -> Vec<Vector3<i32>> {
+    let mut leaves = vec![];
+    let bottom = rng.gen_range(1..3);
+    let max_radius: i32 = rng.gen_range(2..4);
+    let mut radius: i32 = 0;
+    for y in (bottom..=height).rev() {
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                let corner = radius > 0 && x.abs() == radius && z.abs() == radius;
+                if corner || (x == 0 && z == 0 && y < height) {
+                    continue;
+                }
+                leaves.push(vector!(x, y, z));
+            }
+        }
+        radius = if radius >= max_radius { 1 } else { radius + 1 };
+    }
+    leaves
+}
@@ -1,35 +1,38 @@
 use std::collections::BTreeMap;
 use std::iter::repeat_with;
 
+use std::sync::Arc;
+
 use anvil_nbt::{CompoundTag, Tag};
-use anvil_region::position::RegionChunkPosition;
-use anvil_region::position::RegionPosition;
-use anvil_region::provider::RegionProvider;
 use async_trait::async_trait;
-use anvil_region::provider::FolderRegionProvider;
 use tokio::task;
 use crate::blocks::{Block, BlockType};
 
 use crate::chunks::{
     ChunkData, ChunkCoords,
     data::Palette, data::Section,
+    regions::RegionManager,
 };
 use super::ChunkLoader;
 
 const SECTIONS_PER_CHUNK: usize = 16;
 
-pub struct AnvilChunkLoader;
+pub struct AnvilChunkLoader {
+    regions: Arc<RegionManager>,
+}
 
 impl AnvilChunkLoader {
-    pub fn new() -> Self {
-        Self
+    pub fn new(regions: Arc<RegionManager>) -> Self {
+        Self {
+            regions,
+        }
     }
 }
 
 #[async_trait]
 impl ChunkLoader for AnvilChunkLoader {
     async fn load_chunk(&self, coords: ChunkCoords) -> Option<ChunkData> {
-        let chunk = read_chunk(coords).await?;
+        let chunk = read_chunk(self.regions.clone(), coords).await?;
         let section_tags = chunk
             .get_compound_tag("Level").unwrap()
             .get_compound_tag_vec("Sections").unwrap();
@@ -52,20 +55,27 @@ impl ChunkLoader for AnvilChunkLoader {
                 sections[y] = Some(section);
             }
         }
-        Some(ChunkData::from_sections(sections))
+        let mut data = ChunkData::from_sections(sections);
+        data.modified = ChunkData::was_modified(&chunk);
+        if let Ok(biomes) = chunk.get_compound_tag("Level").unwrap()
+            .get_i32_vec("Biomes") 
+        {
+            data.set_biome_map(biomes.iter().map(|id| *id as u16).collect());
+        }
+        if let Ok(tags) = chunk.get_compound_tag("Level").unwrap()
+            .get_compound_tag_vec("TileEntities")
+        {
+            data.load_block_entities(&tags);
+        }
+        Some(data)
     }
 }
 
-async fn read_chunk(coords: ChunkCoords) -> Option<CompoundTag> {
-    let ChunkCoords(chunk_x, chunk_z) = coords;
-    let region_position = 
-        RegionPosition::from_chunk_position(chunk_x, chunk_z);
-    let chunk_position = 
-        RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
+async fn read_chunk(regions: Arc<RegionManager>, coords: ChunkCoords) 
+    -> Option<CompoundTag> 
+{
     task::spawn_blocking(move || {
-        let provider = FolderRegionProvider::new("world/region");
-        let mut region = provider.get_region(region_position).ok()?;
-        region.read_chunk(chunk_position).ok()
+        regions.read_chunk(coords)
     }).await.ok()?
 }
 
@@ -1,23 +1,79 @@
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use block_macro::block_id;
 use super::ChunkLoader;
 use super::{ChunkData, ChunkCoords};
-use crate::blocks::Block;
+use crate::biomes::Biome;
+use crate::blocks::{Block, BlockType};
 
-pub struct FlatGenerator;
+const MAX_HEIGHT: usize = 256;
+const VOID_PRESET: &str = "minecraft:air;minecraft:the_void";
+
+/// Same layers everywhere, described with a vanilla superflat preset
+pub struct FlatGenerator {
+    layers: Vec<&'static Block>,
+    biome: Option<&'static Biome>,
+}
+
+impl FlatGenerator {
+    /// Parses presets like `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`,
+    /// or `void` for an empty world
+    pub fn from_preset(preset: &str) -> Result<Self> {
+        let preset = if preset == "void" { VOID_PRESET } else { preset };
+        let mut parts = preset.split(';');
+        let mut layers = vec![];
+        for layer in parts.next().unwrap_or("").split(',') {
+            let layer = layer.trim();
+            if layer.is_empty() {
+                continue;
+            }
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => (count.trim().parse::<usize>()
+                    .map_err(|_| anyhow!("Invalid layer count in {}", layer))?, name.trim()),
+                None => (1, layer),
+            };
+            let btype = BlockType::from_name(&with_namespace(name))
+                .ok_or_else(|| anyhow!("Unknown block in flat preset: {}", name))?;
+            let block = btype.with_props(&btype.default_state)?;
+            // Checked before extending so huge counts can't allocate
+            let height = layers.len().saturating_add(count);
+            if height > MAX_HEIGHT {
+                return Err(anyhow!("Flat preset has at least {} layers, the limit is {}",
+                    height, MAX_HEIGHT));
+            }
+            layers.extend(std::iter::repeat(block).take(count));
+        }
+        let biome = match parts.next().map(str::trim) {
+            Some(name) if !name.is_empty() => Some(Biome::from_name(&with_namespace(name))
+                .ok_or_else(|| anyhow!("Unknown biome in flat preset: {}", name))?),
+            _ => None,
+        };
+        Ok(Self {
+            layers,
+            biome,
+        })
+    }
+}
 
 #[async_trait]
 impl ChunkLoader for FlatGenerator {
     async fn load_chunk(&self, _coords: ChunkCoords) -> Option<ChunkData> {
         let mut chunk = ChunkData::new();
-        let bedrock = Block::from_state_id(block_id!(bedrock)).unwrap();
-        let dirt = Block::from_state_id(block_id!(dirt)).unwrap();
-        let grass = Block::from_state_id(block_id!(grass_block)).unwrap();
-        set_layer(&mut chunk, 0, bedrock);
-        set_layer(&mut chunk, 1, dirt);
-        set_layer(&mut chunk, 2, grass);
+        for (y, block) in self.layers.iter().enumerate() {
+            if block.id != Block::air().id {
+                set_layer(&mut chunk, y, block);
+            }
+        }
+        if let Some(biome) = self.biome {
+            chunk.fill_biome(biome);
+        }
         Some(chunk)
     }
+
+    fn surface_height(&self, _x: i32, _z: i32) -> Option<i32> {
+        self.layers.iter()
+            .rposition(|block| block.id != Block::air().id)
+            .map(|top| top as i32 + 1)
+    }
 }
 
 fn set_layer(chunk: &mut ChunkData, y: usize, block: &'static Block) {
@@ -27,3 +83,46 @@ fn set_layer(chunk: &mut ChunkData, y: usize, block: &'static Block) {
         }
     }
 }
+
+fn with_namespace(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_presets() {
+        let flat = FlatGenerator::from_preset(
+            "minecraft:bedrock, 2*dirt,minecraft:grass_block;minecraft:desert").unwrap();
+        let names: Vec<&str> = flat.layers.iter()
+            .map(|block| block.btype.name.as_str())
+            .collect();
+        assert_eq!(names, ["minecraft:bedrock", "minecraft:dirt", "minecraft:dirt",
+            "minecraft:grass_block"]);
+        assert_eq!(flat.biome.unwrap().name, "minecraft:desert");
+        assert_eq!(flat.surface_height(0, 0), Some(4));
+    }
+
+    #[test]
+    fn void_has_no_surface() {
+        let void = FlatGenerator::from_preset("void").unwrap();
+        assert_eq!(void.surface_height(0, 0), None);
+    }
+
+    #[test]
+    fn rejects_bad_presets() {
+        assert!(FlatGenerator::from_preset("minecraft:not_a_block").is_err());
+        assert!(FlatGenerator::from_preset("x*minecraft:stone").is_err());
+        assert!(FlatGenerator::from_preset("minecraft:stone;minecraft:not_a_biome").is_err());
+        assert!(FlatGenerator::from_preset("257*minecraft:stone").is_err());
+        assert!(FlatGenerator::from_preset("200*minecraft:stone,100*minecraft:dirt").is_err());
+        assert!(FlatGenerator::from_preset("18446744073709551615*minecraft:stone").is_err());
+        assert!(FlatGenerator::from_preset("256*minecraft:stone").is_ok());
+    }
+}
@@ -1,5 +1,8 @@
 mod file;
 mod flat_generator;
+mod noise;
+mod decoration;
+mod noise_generator;
 
 use async_trait::async_trait;
 use super::ChunkData;
@@ -7,8 +10,15 @@ use super::ChunkCoords;
 
 pub use file::AnvilChunkLoader;
 pub use flat_generator::FlatGenerator;
+pub use noise_generator::NoiseGenerator;
 
 #[async_trait]
 pub trait ChunkLoader: Send + Sync {
     async fn load_chunk(&self, coords: ChunkCoords) -> Option<ChunkData>;
+
+    /// Lowest y a player can stand at in a column, for generators that
+    /// know it without making the chunk
+    fn surface_height(&self, _x: i32, _z: i32) -> Option<i32> {
+        None
+    }
 }
@@ -0,0 +1,135 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// Ken Perlin's improved noise, with a permutation table built from a seed
+pub struct PerlinNoise {
+    permutation: [u8; 512],
+    offset: (f64, f64, f64),
+}
+
+impl PerlinNoise {
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(rng);
+        let mut permutation = [0; 512];
+        for (i, value) in values.iter().cycle().take(512).enumerate() {
+            permutation[i] = *value;
+        }
+        Self {
+            permutation,
+            offset: (
+                rng.gen::<f64>() * 256.0,
+                rng.gen::<f64>() * 256.0,
+                rng.gen::<f64>() * 256.0),
+        }
+    }
+
+    /// Returns a value roughly between -1 and 1
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset.0, y + self.offset.1, z + self.offset.2);
+        let (floor_x, floor_y, floor_z) = (x.floor(), y.floor(), z.floor());
+        let (x, y, z) = (x - floor_x, y - floor_y, z - floor_z);
+        let xi = (floor_x as i64 & 255) as usize;
+        let yi = (floor_y as i64 & 255) as usize;
+        let zi = (floor_z as i64 & 255) as usize;
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let p = &self.permutation;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+        lerp(w,
+            lerp(v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(u, grad(p[ab], x, y - 1.0, z), grad(p[bb], x - 1.0, y - 1.0, z))),
+            lerp(v,
+                lerp(u, grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))))
+    }
+}
+
+/// Several layers of Perlin noise, each with double the frequency
+/// and half the amplitude of the previous one
+pub struct OctaveNoise {
+    octaves: Vec<PerlinNoise>,
+    scale: f64,
+}
+
+impl OctaveNoise {
+    pub fn new<R: Rng>(rng: &mut R, octaves: usize, scale: f64) -> Self {
+        Self {
+            octaves: (0..octaves).map(|_| PerlinNoise::new(rng)).collect(),
+            scale,
+        }
+    }
+
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut result = 0.0;
+        let mut frequency = 1.0 / self.scale;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        for octave in &self.octaves {
+            result += octave.sample(x * frequency, y * frequency, z * frequency)
+                * amplitude;
+            total_amplitude += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+        result / total_amplitude
+    }
+
+    pub fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, 0.0, z)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::{OctaveNoise, PerlinNoise};
+
+    #[test]
+    fn same_seed_same_noise_test() {
+        let a = PerlinNoise::new(&mut ChaCha8Rng::seed_from_u64(42));
+        let b = PerlinNoise::new(&mut ChaCha8Rng::seed_from_u64(42));
+        for i in 0..100 {
+            let (x, y, z) = (i as f64 * 0.37, i as f64 * 1.91, i as f64 * -0.73);
+            assert_eq!(a.sample(x, y, z), b.sample(x, y, z));
+        }
+    }
+
+    #[test]
+    fn octave_noise_range_test() {
+        let noise = OctaveNoise::new(&mut ChaCha8Rng::seed_from_u64(7), 4, 32.0);
+        for i in 0..1000 {
+            let value = noise.sample(i as f64 * 3.1, i as f64 * 0.5, i as f64 * -2.3);
+            assert!((-1.5..=1.5).contains(&value));
+        }
+    }
+}
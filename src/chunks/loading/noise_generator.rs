@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use block_macro::block_id;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use tokio::task;
+
+use crate::biomes::Biome;
+use crate::blocks::{Block, BlockType};
+use super::ChunkLoader;
+use super::{ChunkData, ChunkCoords};
+use super::noise::OctaveNoise;
+use super::decoration::{Decorator, SurfaceSampler};
+
+const SEA_LEVEL: usize = 63;
+const LAVA_LEVEL: usize = 10;
+const MAX_HEIGHT: usize = 256;
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 8;
+const CELLS_X: usize = 16 / CELL_WIDTH;
+const CELLS_Y: usize = MAX_HEIGHT / CELL_HEIGHT;
+const BEDROCK_LAYERS: usize = 5;
+const BIOME_CELL: usize = 4;
+/// Caves stay this far below the surface, so features placed from
+/// neighbour chunks always find the ground they expect
+const CAVE_SURFACE_MARGIN: usize = 5;
+
+/// Terrain made from seeded noise, with biomes, caves and oceans
+pub struct NoiseGenerator {
+    terrain: Arc<Terrain>,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            terrain: Arc::new(Terrain::new(seed)),
+        }
+    }
+}
+
+#[async_trait]
+impl ChunkLoader for NoiseGenerator {
+    async fn load_chunk(&self, coords: ChunkCoords) -> Option<ChunkData> {
+        let terrain = self.terrain.clone();
+        task::spawn_blocking(move || terrain.generate(coords)).await.ok()
+    }
+
+    /// Oceans have no surface to stand on
+    fn surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        self.terrain.surface_at(x, z).map(|(top, ..)| top as i32 + 1)
+    }
+}
+
+/// What a column looks like before any block is placed
+struct Column {
+    height: f64,
+    roughness: f64,
+    biome: &'static Biome,
+}
+
+struct Terrain {
+    seed: i64,
+    height: OctaveNoise,
+    roughness: OctaveNoise,
+    density: OctaveNoise,
+    temperature: OctaveNoise,
+    humidity: OctaveNoise,
+    surface_depth: OctaveNoise,
+    cave_a: OctaveNoise,
+    cave_b: OctaveNoise,
+    caverns: OctaveNoise,
+    decorator: Decorator,
+}
+
+impl Terrain {
+    fn new(seed: i64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed as u64);
+        Self {
+            seed,
+            height: OctaveNoise::new(&mut rng, 6, 512.0),
+            roughness: OctaveNoise::new(&mut rng, 4, 256.0),
+            density: OctaveNoise::new(&mut rng, 4, 64.0),
+            temperature: OctaveNoise::new(&mut rng, 4, 1024.0),
+            humidity: OctaveNoise::new(&mut rng, 4, 1024.0),
+            surface_depth: OctaveNoise::new(&mut rng, 2, 16.0),
+            cave_a: OctaveNoise::new(&mut rng, 2, 48.0),
+            cave_b: OctaveNoise::new(&mut rng, 2, 48.0),
+            caverns: OctaveNoise::new(&mut rng, 3, 96.0),
+            decorator: Decorator::new(seed),
+        }
+    }
+
+    fn generate(&self, coords: ChunkCoords) -> ChunkData {
+        let mut chunk = ChunkData::new();
+        let mut columns = Vec::with_capacity(16 * 16);
+        for x in 0..16 {
+            for z in 0..16 {
+                let pos = coords.global(x, 0, z);
+                columns.push(self.column(pos.x as f64, pos.z as f64));
+            }
+        }
+        self.fill_stone(&mut chunk, coords);
+        self.fill_biomes(&mut chunk, &columns);
+        for x in 0..16 {
+            for z in 0..16 {
+                let column = &columns[x * 16 + z];
+                self.apply_surface(&mut chunk, coords, x, z, column.biome);
+            }
+        }
+        self.carve_caves(&mut chunk, coords);
+        self.place_bedrock(&mut chunk, coords);
+        self.decorator.decorate(&mut chunk, coords, self);
+        chunk
+    }
+
+    fn column(&self, x: f64, z: f64) -> Column {
+        let continent = self.height.sample_2d(x, z);
+        let roughness = self.roughness.sample_2d(x, z).max(0.0);
+        let height = SEA_LEVEL as f64 + 4.0 + continent * 48.0
+            + roughness * 40.0;
+        let biome = self.pick_biome(x, z, height, roughness);
+        Column {
+            height,
+            roughness,
+            biome,
+        }
+    }
+
+    fn pick_biome(&self, x: f64, z: f64, height: f64, roughness: f64) -> &'static Biome {
+        let temperature = self.temperature.sample_2d(x, z);
+        let humidity = self.humidity.sample_2d(x, z);
+        let cold = temperature < -0.3;
+        let name = if height < SEA_LEVEL as f64 - 20.0 {
+            if cold { "minecraft:deep_frozen_ocean" } else { "minecraft:deep_ocean" }
+        } else if height < SEA_LEVEL as f64 - 3.0 {
+            if cold { "minecraft:frozen_ocean" } else { "minecraft:ocean" }
+        } else if height < SEA_LEVEL as f64 + 2.0 {
+            if cold { "minecraft:snowy_beach" } else { "minecraft:beach" }
+        } else if roughness > 0.3 && height > 100.0 {
+            if cold { "minecraft:snowy_mountains" } else { "minecraft:mountains" }
+        } else if cold {
+            if humidity > 0.0 { "minecraft:snowy_taiga" } else { "minecraft:snowy_tundra" }
+        } else if temperature < 0.0 {
+            if humidity > -0.1 { "minecraft:taiga" } else { "minecraft:plains" }
+        } else if temperature < 0.35 {
+            if humidity > 0.25 {
+                "minecraft:birch_forest"
+            } else if humidity > 0.0 {
+                "minecraft:forest"
+            } else {
+                "minecraft:plains"
+            }
+        } else if humidity < 0.0 {
+            "minecraft:desert"
+        } else {
+            "minecraft:savanna"
+        };
+        Biome::from_name(name).unwrap_or_else(Biome::plains)
+    }
+
+    /// Samples the 3D density on a coarse grid and interpolates between
+    /// the corners, like vanilla does, since sampling every block is slow
+    fn fill_stone(&self, chunk: &mut ChunkData, coords: ChunkCoords) {
+        let mut grid = vec![0.0; (CELLS_X + 1) * (CELLS_Y + 1) * (CELLS_X + 1)];
+        let index = |x: usize, y: usize, z: usize|
+            (x * (CELLS_X + 1) + z) * (CELLS_Y + 1) + y;
+        for cx in 0..=CELLS_X {
+            for cz in 0..=CELLS_X {
+                let pos = coords.global(cx * CELL_WIDTH, 0, cz * CELL_WIDTH);
+                let (x, z) = (pos.x as f64, pos.z as f64);
+                let column = self.column(x, z);
+                for cy in 0..=CELLS_Y {
+                    let y = (cy * CELL_HEIGHT) as f64;
+                    grid[index(cx, cy, cz)] = self.density_at(&column, x, y, z);
+                }
+            }
+        }
+        let stone = Block::from_state_id(block_id!(stone)).unwrap();
+        let water = Block::from_state_id(block_id!(water)).unwrap();
+        for x in 0..16 {
+            for z in 0..16 {
+                let (cx, fx) = (x / CELL_WIDTH, (x % CELL_WIDTH) as f64 / CELL_WIDTH as f64);
+                let (cz, fz) = (z / CELL_WIDTH, (z % CELL_WIDTH) as f64 / CELL_WIDTH as f64);
+                for y in 0..MAX_HEIGHT {
+                    let (cy, fy) = (y / CELL_HEIGHT, (y % CELL_HEIGHT) as f64 / CELL_HEIGHT as f64);
+                    let density = lerp3(fx, fy, fz,
+                        grid[index(cx, cy, cz)], grid[index(cx + 1, cy, cz)],
+                        grid[index(cx, cy + 1, cz)], grid[index(cx + 1, cy + 1, cz)],
+                        grid[index(cx, cy, cz + 1)], grid[index(cx + 1, cy, cz + 1)],
+                        grid[index(cx, cy + 1, cz + 1)], grid[index(cx + 1, cy + 1, cz + 1)]);
+                    if density > 0.0 {
+                        chunk.set_block(x, y, z, stone);
+                    } else if y <= SEA_LEVEL {
+                        chunk.set_block(x, y, z, water);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Positive means solid, the noise adds overhangs on rough terrain
+    fn density_at(&self, column: &Column, x: f64, y: f64, z: f64) -> f64 {
+        let amplitude = 6.0 + column.roughness * 30.0;
+        (column.height - y) + self.density.sample(x, y, z) * amplitude
+    }
+
+    fn fill_biomes(&self, chunk: &mut ChunkData, columns: &[Column]) {
+        let mut biomes = Vec::with_capacity(MAX_HEIGHT / BIOME_CELL * 16);
+        for _ in 0..MAX_HEIGHT / BIOME_CELL {
+            for z in 0..16 / BIOME_CELL {
+                for x in 0..16 / BIOME_CELL {
+                    let (x, z) = (x * BIOME_CELL + 2, z * BIOME_CELL + 2);
+                    biomes.push(columns[x * 16 + z].biome.id);
+                }
+            }
+        }
+        chunk.set_biome_map(biomes);
+    }
+
+    /// Replaces the top stone layers with the biome's surface blocks
+    fn apply_surface(&self, chunk: &mut ChunkData, coords: ChunkCoords,
+        x: usize, z: usize, biome: &'static Biome)
+    {
+        let pos = coords.global(x, 0, z);
+        let depth = 3 + (self.surface_depth.sample_2d(pos.x as f64, pos.z as f64)
+            * 2.0 + 1.0).max(0.0) as usize;
+        let top = match chunk.get_top_block(x, z) {
+            Some(top) => top,
+            None => return,
+        };
+        let (top_block, filler) = surface_blocks(biome, top);
+        let mut remaining = None;
+        for y in (0..=top).rev() {
+            let current = chunk.get_block(x, y, z);
+            if current.id != block_id!(stone) {
+                // Surfaces start over under overhangs
+                remaining = None;
+                continue;
+            }
+            match remaining {
+                None => {
+                    let block = if y < SEA_LEVEL || chunk.get_block(x, y + 1, z).id == block_id!(water) {
+                        filler
+                    } else {
+                        top_block
+                    };
+                    chunk.set_block(x, y, z, block);
+                    remaining = Some(depth);
+                },
+                Some(0) => continue,
+                Some(n) => {
+                    chunk.set_block(x, y, z, filler);
+                    remaining = Some(n - 1);
+                },
+            }
+        }
+        if biome.is_cold_at(top as i32) {
+            let top_block = chunk.get_block(x, top, z);
+            if top_block.id == block_id!(water) && top == SEA_LEVEL {
+                chunk.set_block(x, top, z, Block::from_state_id(block_id!(ice)).unwrap());
+            } else if top_block.opaque && top + 1 < MAX_HEIGHT {
+                chunk.set_block(x, top + 1, z, Block::from_state_id(block_id!(snow)).unwrap());
+                if top_block.id == block_id!(grass_block) {
+                    chunk.set_block(x, top, z, snowy_grass());
+                }
+            }
+        }
+    }
+
+    /// Spaghetti caves where two noises are close to zero, and
+    /// bigger caverns deep down. Caves under water are left alone
+    fn carve_caves(&self, chunk: &mut ChunkData, coords: ChunkCoords) {
+        let air = Block::air();
+        let lava = Block::from_state_id(block_id!(lava)).unwrap();
+        for x in 0..16 {
+            for z in 0..16 {
+                let top = match chunk.get_top_block(x, z) {
+                    Some(top) => top,
+                    None => continue,
+                };
+                for y in BEDROCK_LAYERS..top.saturating_sub(CAVE_SURFACE_MARGIN) {
+                    if !chunk.get_block(x, y, z).opaque ||
+                        chunk.get_block(x, y + 1, z).id == block_id!(water)
+                    {
+                        continue;
+                    }
+                    let pos = coords.global(x, y, z);
+                    let (gx, gy, gz) = (pos.x as f64, pos.y as f64 * 2.0, pos.z as f64);
+                    let a = self.cave_a.sample(gx, gy, gz);
+                    let b = self.cave_b.sample(gx, gy, gz);
+                    let tunnel = a * a + b * b < 0.0025;
+                    let cavern = y < 48 && self.caverns.sample(gx, gy, gz) > 0.45;
+                    if tunnel || cavern {
+                        let block = if y <= LAVA_LEVEL { lava } else { air };
+                        chunk.set_block(x, y, z, block);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Solid bottom layer with a few random ones above it
+    fn place_bedrock(&self, chunk: &mut ChunkData, coords: ChunkCoords) {
+        let bedrock = Block::from_state_id(block_id!(bedrock)).unwrap();
+        let mut rng = self.chunk_rng(coords);
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block(x, 0, z, bedrock);
+                for y in 1..BEDROCK_LAYERS {
+                    if rng.gen_range(0..BEDROCK_LAYERS) >= y {
+                        chunk.set_block(x, y, z, bedrock);
+                    }
+                }
+            }
+        }
+    }
+
+    fn chunk_rng(&self, coords: ChunkCoords) -> ChaCha8Rng {
+        let seed = (self.seed as u64)
+            ^ (coords.0 as u64).wrapping_mul(341873128712)
+            ^ (coords.1 as u64).wrapping_mul(132897987541);
+        ChaCha8Rng::seed_from_u64(seed)
+    }
+}
+
+impl SurfaceSampler for Terrain {
+    /// Does the same interpolation as fill_stone for a single column
+    fn surface_at(&self, x: i32, z: i32) -> Option<(usize, &'static Block, &'static Biome)> {
+        let width = CELL_WIDTH as i32;
+        let (cell_x, cell_z) = (x.div_euclid(width) * width, z.div_euclid(width) * width);
+        let fx = (x - cell_x) as f64 / CELL_WIDTH as f64;
+        let fz = (z - cell_z) as f64 / CELL_WIDTH as f64;
+        let corners: Vec<(f64, f64, Column)> = [(0, 0), (width, 0), (0, width), (width, width)]
+            .iter()
+            .map(|(dx, dz)| {
+                let (x, z) = ((cell_x + dx) as f64, (cell_z + dz) as f64);
+                (x, z, self.column(x, z))
+            })
+            .collect();
+        let density = |corner: usize, cy: usize| {
+            let (x, z, column) = &corners[corner];
+            self.density_at(column, *x, (cy * CELL_HEIGHT) as f64, *z)
+        };
+        let mut top = None;
+        for cy in (0..CELLS_Y).rev() {
+            let values = [
+                density(0, cy), density(1, cy), density(0, cy + 1), density(1, cy + 1),
+                density(2, cy), density(3, cy), density(2, cy + 1), density(3, cy + 1)];
+            if values.iter().all(|value| *value <= 0.0) {
+                continue;
+            }
+            top = (0..CELL_HEIGHT).rev()
+                .map(|dy| cy * CELL_HEIGHT + dy)
+                .find(|y| {
+                    let fy = (y % CELL_HEIGHT) as f64 / CELL_HEIGHT as f64;
+                    lerp3(fx, fy, fz, values[0], values[1], values[2], values[3],
+                        values[4], values[5], values[6], values[7]) > 0.0
+                });
+            if top.is_some() {
+                break;
+            }
+        }
+        let top = top?;
+        if top < SEA_LEVEL {
+            return None;
+        }
+        let biome = self.column(x as f64, z as f64).biome;
+        Some((top, surface_blocks(biome, top).0, biome))
+    }
+}
+
+fn surface_blocks(biome: &Biome, top: usize) -> (&'static Block, &'static Block) {
+    let block = |id| Block::from_state_id(id).unwrap();
+    let name = biome.name.as_str();
+    if name.ends_with("beach") || name == "minecraft:desert" {
+        (block(block_id!(sand)), block(block_id!(sand)))
+    } else if name.ends_with("ocean") {
+        if top < SEA_LEVEL - 12 {
+            (block(block_id!(gravel)), block(block_id!(gravel)))
+        } else {
+            (block(block_id!(sand)), block(block_id!(sand)))
+        }
+    } else if name.ends_with("mountains") && top > 110 {
+        (block(block_id!(stone)), block(block_id!(stone)))
+    } else {
+        (block(block_id!(grass_block)), block(block_id!(dirt)))
+    }
+}
+
+fn snowy_grass() -> &'static Block {
+    let mut props = BTreeMap::new();
+    props.insert("snowy".to_string(), "true".to_string());
+    BlockType::from_name("minecraft:grass_block").unwrap()
+        .with_props(&props).unwrap()
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lerp3(fx: f64, fy: f64, fz: f64, x0y0z0: f64, x1y0z0: f64, x0y1z0: f64,
+    x1y1z0: f64, x0y0z1: f64, x1y0z1: f64, x0y1z1: f64, x1y1z1: f64) -> f64
+{
+    lerp(fz,
+        lerp(fy, lerp(fx, x0y0z0, x1y0z0), lerp(fx, x0y1z0, x1y1z0)),
+        lerp(fy, lerp(fx, x0y0z1, x1y0z1), lerp(fx, x0y1z1, x1y1z1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use crate::chunks::ChunkCoords;
+    use super::Terrain;
+
+    fn chunk_hash(terrain: &Terrain, coords: ChunkCoords) -> u64 {
+        let chunk = terrain.generate(coords);
+        let mut hasher = DefaultHasher::new();
+        chunk.get_data().hash(&mut hasher);
+        chunk.get_biome_map().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn deterministic_generation_test() {
+        let a = Terrain::new(1234);
+        let b = Terrain::new(1234);
+        for coords in &[ChunkCoords(0, 0), ChunkCoords(-7, 3), ChunkCoords(40, -25)] {
+            assert_eq!(chunk_hash(&a, *coords), chunk_hash(&b, *coords));
+        }
+    }
+
+    #[test]
+    fn different_seeds_test() {
+        let a = Terrain::new(1);
+        let b = Terrain::new(2);
+        assert_ne!(chunk_hash(&a, ChunkCoords(0, 0)), chunk_hash(&b, ChunkCoords(0, 0)));
+    }
+}
@@ -4,29 +4,74 @@ mod coords;
 mod loading;
 mod data;
 mod saving;
+mod regions;
 mod view;
+mod packets;
+mod pregeneration;
 pub mod events;
 
 use std::io::Write;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::dimensions::Dimensions;
+use crate::level::LevelData;
 
 pub use data::ChunkData;
-pub use coords::ChunkCoords;
+pub use coords::{ChunkCoords, block_coords};
 pub use world::World;
 pub use view::WorldView;
+pub use packets::ChunkPackets;
+pub use loading::{ChunkLoader, FlatGenerator, NoiseGenerator};
 use legion::{systems::Builder, Resources};
-use loading::{FlatGenerator, AnvilChunkLoader};
+use loading::AnvilChunkLoader;
+use regions::RegionManager;
+use saving::ChunkSaver;
 
-pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+pub fn register(schedule: &mut Builder) {
     world::register(schedule);
-    resources.insert(World::new(vec![
-        Box::new(AnvilChunkLoader::new()),
-        Box::new(FlatGenerator),
-    ]));
+}
+
+/// Chunks saved in the given region folder, with a generator for
+/// the ones that aren't there yet
+pub fn new_world(region_folder: &str, generator: Box<dyn ChunkLoader>) -> World {
+    let regions = Arc::new(RegionManager::new(region_folder));
+    World::new(vec![
+        Box::new(AnvilChunkLoader::new(regions.clone())),
+        generator,
+    ], ChunkSaver::new(regions))
+}
+
+/// Starts pregenerating the area around spawn set in the config
+pub fn pregenerate_spawn(resources: &Resources, config: &Config) {
+    if config.pregenerate_radius == 0 {
+        return;
+    }
+    let spawn = resources.get::<LevelData>().unwrap().spawn_position();
+    let dimensions = resources.get::<Dimensions>().unwrap();
+    let overworld = dimensions.get(dimensions.overworld());
+    overworld.chunks.start_pregeneration(ChunkCoords::from_pos(&spawn),
+        config.pregenerate_radius, config.pregenerate_throttle);
+}
+
+/// Deletes the chunks nobody changed from every dimension's region files
+pub fn trim(resources: &Resources) {
+    let dimensions = resources.get::<Dimensions>().unwrap();
+    for dimension in dimensions.iter() {
+        print!("Trimming {}... ", dimension.name);
+        let _ = std::io::stdout().flush();
+        match dimension.chunks.trim_unmodified() {
+            Ok(removed) => println!("Removed {} chunks", removed),
+            Err(err) => println!("Error: {}", err),
+        }
+    }
 }
 
 pub async fn on_stop(resources: &mut Resources) {
     print!("Saving chunks...         ");
     let _ = std::io::stdout().flush();
-    resources.get_mut::<World>().unwrap().save_all();
+    for dimension in resources.get_mut::<Dimensions>().unwrap().iter_mut() {
+        dimension.chunks.save_all();
+    }
     println!("Done");
 }
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::net::ClientboundPacket;
+use super::{ChunkCoords, ChunkData};
+
+/// Chunk Data and Update Light packets for a chunk, encoded once and 
+/// sent as they are to everyone that sees it
+#[derive(Clone)]
+pub struct ChunkPackets {
+    pub data: Arc<[u8]>,
+    pub light: Arc<[u8]>,
+}
+
+impl ChunkPackets {
+    pub fn encode(coords: ChunkCoords, chunk: &ChunkData) -> Self {
+        let data = ClientboundPacket::ChunkData {
+            x: coords.0,
+            z: coords.1,
+            full: true,
+            bitmask: chunk.get_sections_bitmask(),
+            heightmap: chunk.heightmap.get_nbt(),
+            biomes: Some(chunk.get_biome_map()),
+            data: chunk.get_data(),
+            block_entities: chunk.get_block_entities_nbt(coords),
+        };
+        let mut sky_arrays = Vec::with_capacity(18);
+        for _ in 0..18 {
+            sky_arrays.push(vec![0xFF; 2048]);
+        }
+        let light = ClientboundPacket::UpdateLight {
+            x: coords.0,
+            z: coords.1,
+            trust_edges: true,
+            sky_mask: 0b0011_1111_1111_1111_1111,
+            block_mask: 0,
+            empty_sky_mask: 0,
+            empty_block_mask: 0b0011_1111_1111_1111_1111,
+            sky_light: sky_arrays,
+            block_light: vec![],
+        };
+        Self {
+            data: data.encode().into(),
+            light: light.encode().into(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{HashMap, VecDeque};
+use std::mem::take;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{ChunkCoords, ChunkData};
+use super::chunk::Chunk;
+use super::loading::ChunkLoader;
+use super::saving::ChunkSaver;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Generates and saves every chunk in a square around a center, a few at
+/// a time so it can run while players are online
+pub struct Pregeneration {
+    remaining: VecDeque<ChunkCoords>,
+    total: usize,
+    done: usize,
+    running: usize,
+    max_running: usize,
+    /// Filled by the load tasks, None if the chunk was already saved
+    finished: Arc<Mutex<Vec<(ChunkCoords, Option<ChunkData>)>>>,
+    last_report: Instant,
+}
+
+impl Pregeneration {
+    pub fn new(center: ChunkCoords, radius: i32, max_running: usize) -> Self {
+        let mut remaining: Vec<ChunkCoords> = (-radius..=radius)
+            .flat_map(|x| (-radius..=radius)
+                .map(move |z| ChunkCoords(center.0 + x, center.1 + z)))
+            .collect();
+        remaining.sort_by_key(|coords| 
+            (coords.0 - center.0).pow(2) + (coords.1 - center.1).pow(2));
+        Self {
+            total: remaining.len(),
+            remaining: remaining.into(),
+            done: 0,
+            running: 0,
+            max_running: max_running.max(1),
+            finished: Default::default(),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Saves what finished and starts loading more chunks,
+    /// returns true once everything is done
+    pub fn tick(&mut self, name: &str, loaded: &RwLock<HashMap<ChunkCoords, Chunk>>,
+        loaders: &Arc<Vec<Box<dyn ChunkLoader>>>, saver: &mut ChunkSaver) -> bool
+    {
+        let finished = take(&mut *self.finished.lock().unwrap());
+        for (coords, data) in finished {
+            self.running -= 1;
+            self.done += 1;
+            // Chunks players loaded meanwhile are saved with their changes
+            let in_memory = loaded.read().unwrap().contains_key(&coords);
+            if let (Some(data), false) = (data, in_memory) {
+                saver.save(coords, Arc::new(RwLock::new(data)));
+            }
+        }
+        while self.running < self.max_running {
+            let coords = match self.remaining.pop_front() {
+                Some(coords) => coords,
+                None => break,
+            };
+            if loaded.read().unwrap().contains_key(&coords) {
+                self.done += 1;
+                continue;
+            }
+            self.running += 1;
+            let loaders = loaders.clone();
+            let finished = self.finished.clone();
+            tokio::spawn(async move {
+                let data = generate(coords, &loaders).await;
+                finished.lock().unwrap().push((coords, data));
+            });
+        }
+        let complete = self.remaining.is_empty() && self.running == 0;
+        if complete || self.last_report.elapsed() > REPORT_INTERVAL {
+            println!("Pregenerating {}: {}/{} chunks ({}%)", name, self.done, self.total,
+                self.done * 100 / self.total.max(1));
+            self.last_report = Instant::now();
+        }
+        complete
+    }
+}
+
+/// Runs the loader chain, the first loader reads saved chunks 
+/// so anything it finds doesn't need saving again
+async fn generate(coords: ChunkCoords, loaders: &[Box<dyn ChunkLoader>]) 
+    -> Option<ChunkData> 
+{
+    for (index, loader) in loaders.iter().enumerate() {
+        if let Some(data) = loader.load_chunk(coords).await {
+            return if index == 0 { None } else { Some(data) };
+        }
+    }
+    None
+}
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+
+use anvil_nbt::CompoundTag;
+use anvil_region::error::ChunkWriteError;
+use anvil_region::position::{RegionChunkPosition, RegionPosition};
+use anvil_region::region::Region;
+use anyhow::{Result, anyhow};
+
+use super::ChunkCoords;
+
+const MAX_OPEN_REGIONS: usize = 32;
+
+type SharedRegion = Arc<Mutex<Region<File>>>;
+
+/// Keeps the most recently used region files open so that loading and
+/// saving don't have to reopen and reparse their headers for every chunk
+pub struct RegionManager {
+    folder: PathBuf,
+    open: Mutex<OpenRegions>,
+    pending: Mutex<HashMap<ChunkCoords, usize>>,
+    written: Condvar,
+}
+
+impl RegionManager {
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+            open: Mutex::new(OpenRegions::new(MAX_OPEN_REGIONS)),
+            pending: Mutex::new(HashMap::new()),
+            written: Condvar::new(),
+        }
+    }
+
+    /// Blocks, call it from a blocking thread
+    pub fn read_chunk(&self, coords: ChunkCoords) -> Option<CompoundTag> {
+        self.wait_pending(coords);
+        let ChunkCoords(chunk_x, chunk_z) = coords;
+        let region = self.get_region(
+            RegionPosition::from_chunk_position(chunk_x, chunk_z)).ok()?;
+        let mut region = region.lock().unwrap();
+        region.read_chunk(
+            RegionChunkPosition::from_chunk_position(chunk_x, chunk_z)).ok()
+    }
+
+    /// Marks a chunk as queued for writing, reads of it will wait until
+    /// the write is done so they never see stale data
+    pub fn mark_pending(&self, coords: ChunkCoords) {
+        *self.pending.lock().unwrap()
+            .entry(coords).or_insert(0) += 1;
+    }
+
+    /// Writes a batch of chunks holding each region only once, in the
+    /// order they're laid out in the file
+    pub fn write_chunks(&self, mut chunks: Vec<(ChunkCoords, CompoundTag)>) {
+        chunks.sort_unstable_by_key(|(ChunkCoords(x, z), _)|
+            (x >> 5, z >> 5, z & 31, x & 31));
+        let mut chunks = chunks.into_iter().peekable();
+        while let Some((coords, tag)) = chunks.next() {
+            let position = RegionPosition::from_chunk_position(coords.0, coords.1);
+            let mut batch = vec![(coords, tag)];
+            while let Some((next, _)) = chunks.peek() {
+                if RegionPosition::from_chunk_position(next.0, next.1) != position {
+                    break;
+                }
+                batch.push(chunks.next().unwrap());
+            }
+            let written: Vec<ChunkCoords> = batch.iter()
+                .map(|(coords, _)| *coords).collect();
+            match self.get_region(position) {
+                Ok(region) => {
+                    let mut region = region.lock().unwrap();
+                    for (coords, tag) in batch {
+                        if let Err(err) = write_chunk(&mut region, coords, tag) {
+                            eprintln!("Error saving chunk at ({}, {}): {}",
+                                coords.0, coords.1, err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error opening region ({}, {}): {}",
+                        position.x, position.z, err);
+                }
+            }
+            self.finish_pending(&written);
+        }
+    }
+
+    /// Rewrites every region file without the chunks `keep` rejects, since
+    /// region files have no way to remove a chunk in place. Blocks, returns
+    /// how many chunks were removed
+    pub fn trim<F>(&self, keep: F) -> Result<usize> 
+        where F: Fn(&CompoundTag) -> bool
+    {
+        if !self.folder.exists() {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.folder)? {
+            let name = entry?.file_name();
+            if let Some(position) = parse_region_name(&name.to_string_lossy()) {
+                removed += self.trim_region(position, &keep)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn trim_region<F>(&self, position: RegionPosition, keep: &F) -> Result<usize>
+        where F: Fn(&CompoundTag) -> bool
+    {
+        let shared = self.get_region(position)?;
+        let mut region = shared.lock().unwrap();
+        let mut kept = vec![];
+        let mut removed = 0;
+        for x in 0..32 {
+            for z in 0..32 {
+                if let Ok(tag) = region.read_chunk(RegionChunkPosition::new(x, z)) {
+                    if keep(&tag) {
+                        kept.push((ChunkCoords(x as i32, z as i32), tag));
+                    } else {
+                        removed += 1;
+                    }
+                }
+            }
+        }
+        if removed == 0 {
+            return Ok(0);
+        }
+        let path = self.region_path(position);
+        let temp_path = path.with_extension("mca.tmp");
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(true)
+            .open(&temp_path)?;
+        let mut trimmed = Region::load(position, file)?;
+        for (coords, tag) in kept {
+            write_chunk(&mut trimmed, coords, tag)?;
+        }
+        // Swapped inside the lock so whoever shares this region sees the new
+        // file, and before the rename so the old one is already closed. The
+        // new handle keeps pointing at the file once it's renamed
+        *region = trimmed;
+        if let Err(err) = fs::rename(&temp_path, &path) {
+            let file = OpenOptions::new().read(true).write(true).open(&path)?;
+            *region = Region::load(position, file)?;
+            return Err(err.into());
+        }
+        Ok(removed)
+    }
+
+    fn region_path(&self, position: RegionPosition) -> PathBuf {
+        self.folder.join(format!("r.{}.{}.mca", position.x, position.z))
+    }
+
+    fn get_region(&self, position: RegionPosition) -> io::Result<SharedRegion> {
+        let mut open = self.open.lock().unwrap();
+        if let Some(region) = open.get(position) {
+            return Ok(region);
+        }
+        fs::create_dir_all(&self.folder)?;
+        let path = self.region_path(position);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        let region = Arc::new(Mutex::new(Region::load(position, file)?));
+        open.insert(position, region.clone());
+        Ok(region)
+    }
+
+    fn wait_pending(&self, coords: ChunkCoords) {
+        let mut pending = self.pending.lock().unwrap();
+        while pending.contains_key(&coords) {
+            pending = self.written.wait(pending).unwrap();
+        }
+    }
+
+    /// Lets reads of chunks that were written, or failed to, go through
+    pub fn finish_pending(&self, written: &[ChunkCoords]) {
+        let mut pending = self.pending.lock().unwrap();
+        for coords in written {
+            if let Some(count) = pending.get_mut(coords) {
+                *count -= 1;
+                if *count == 0 {
+                    pending.remove(coords);
+                }
+            }
+        }
+        self.written.notify_all();
+    }
+}
+
+/// Position of a file named like r.-1.2.mca
+fn parse_region_name(name: &str) -> Option<RegionPosition> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    match parts.next() {
+        None => Some(RegionPosition::new(x, z)),
+        Some(_) => None,
+    }
+}
+
+fn write_chunk(region: &mut Region<File>, coords: ChunkCoords, tag: CompoundTag)
+    -> Result<()>
+{
+    let position = RegionChunkPosition::from_chunk_position(coords.0, coords.1);
+    region.write_chunk(position, tag).map_err(|err| match err {
+        ChunkWriteError::LengthExceedsMaximum { length }
+            => anyhow!(format!("Too large ({} bytes)", length)),
+        ChunkWriteError::IOError { io_error } => io_error.into(),
+    })
+}
+
+struct OpenRegions {
+    regions: BTreeMap<RegionPosition, SharedRegion>,
+    recently_used: VecDeque<RegionPosition>,
+    capacity: usize,
+}
+
+impl OpenRegions {
+    fn new(capacity: usize) -> Self {
+        Self {
+            regions: BTreeMap::new(),
+            recently_used: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&mut self, position: RegionPosition) -> Option<SharedRegion> {
+        let region = self.regions.get(&position)?.clone();
+        self.touch(position);
+        Some(region)
+    }
+
+    fn insert(&mut self, position: RegionPosition, region: SharedRegion) {
+        self.regions.insert(position, region);
+        self.touch(position);
+        while self.regions.len() > self.capacity {
+            if let Some(oldest) = self.recently_used.pop_back() {
+                self.regions.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, position: RegionPosition) {
+        self.recently_used.retain(|used| *used != position);
+        self.recently_used.push_front(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(keep: bool) -> CompoundTag {
+        let mut tag = CompoundTag::new();
+        tag.insert_bool("keep", keep);
+        tag
+    }
+
+    #[test]
+    fn region_names() {
+        let position = parse_region_name("r.-1.2.mca").unwrap();
+        assert_eq!((position.x, position.z), (-1, 2));
+        assert!(parse_region_name("r.0.0.mca.tmp").is_none());
+        assert!(parse_region_name("r.0.mca").is_none());
+        assert!(parse_region_name("r.0.0.0.mca").is_none());
+        assert!(parse_region_name("r.a.0.mca").is_none());
+        assert!(parse_region_name("level.dat").is_none());
+    }
+
+    #[test]
+    fn trimming_removes_rejected_chunks() {
+        let folder = std::env::temp_dir()
+            .join(format!("glow-trim-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        let regions = RegionManager::new(&folder);
+        regions.write_chunks(vec![
+            (ChunkCoords(0, 0), chunk(true)),
+            (ChunkCoords(1, 0), chunk(false)),
+            (ChunkCoords(-1, 5), chunk(false)),
+            (ChunkCoords(-2, 5), chunk(true)),
+        ]);
+        let removed = regions.trim(|tag| tag.get_bool("keep").unwrap_or(false)).unwrap();
+        assert_eq!(removed, 2);
+        // Both the shared handle and a fresh one see the trimmed files
+        for regions in [regions, RegionManager::new(&folder)].iter() {
+            assert!(regions.read_chunk(ChunkCoords(0, 0)).is_some());
+            assert!(regions.read_chunk(ChunkCoords(1, 0)).is_none());
+            assert!(regions.read_chunk(ChunkCoords(-1, 5)).is_none());
+            assert!(regions.read_chunk(ChunkCoords(-2, 5)).is_some());
+        }
+        assert!(!folder.join("r.0.0.mca.tmp").exists());
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -1,36 +1,39 @@
-use std::fs;
 use std::mem::replace;
-use std::sync::{Arc, RwLock, mpsc::{Receiver, Sender, channel}};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, PoisonError, RwLock, mpsc::{Receiver, Sender, channel}};
 use std::thread::{self, JoinHandle};
 
-use anyhow::anyhow;
-use anvil_region::error::ChunkWriteError;
-use anvil_region::position::{RegionChunkPosition, RegionPosition};
-use anvil_region::provider::{FolderRegionProvider, RegionProvider};
-
 use super::{ChunkCoords, ChunkData};
+use super::regions::RegionManager;
 
 pub struct ChunkSaver {
     sender: Sender<Job>,
     worker: JoinHandle<()>,
+    regions: Arc<RegionManager>,
 }
 
 impl ChunkSaver {
-    pub fn new() -> Self {
+    pub fn new(regions: Arc<RegionManager>) -> Self {
         let (sender, recv) = channel();
         Self {
             sender,
-            worker: spawn_worker(recv),
+            worker: spawn_worker(recv, regions.clone()),
+            regions,
         }
     }
 
     pub fn save(&mut self, coords: ChunkCoords, data: Arc<RwLock<ChunkData>>) {
+        self.regions.mark_pending(coords);
         if let Err(err) = self.sender.send(Job(coords, data)) {
             self.replace_worker();
             self.sender.send(err.0).unwrap();
         }
     }
 
+    pub fn regions(&self) -> Arc<RegionManager> {
+        self.regions.clone()
+    }
+
     pub fn wait_completion(&mut self) {
         let old_worker = self.replace_worker();
         let _ = old_worker.join();
@@ -39,47 +42,45 @@ impl ChunkSaver {
     fn replace_worker(&mut self) -> JoinHandle<()> {
         let (new_send, new_recv) = channel();
         self.sender = new_send;
-        let new_worker = spawn_worker(new_recv);
+        let new_worker = spawn_worker(new_recv, self.regions.clone());
         replace(&mut self.worker, new_worker)
     }
 }
 
-fn spawn_worker(recv: Receiver<Job>) -> JoinHandle<()> {
+fn spawn_worker(recv: Receiver<Job>, regions: Arc<RegionManager>) 
+    -> JoinHandle<()> 
+{
     thread::Builder::new()
         .name("chunk saving".into())
         .spawn(move || {
-            worker(recv)
+            worker(recv, &regions)
         }).unwrap()
 }
 
-fn worker(recv: Receiver<Job>) {
-    fs::create_dir_all("world/region").unwrap();
-    let provider = FolderRegionProvider::new("world/region");
-
+fn worker(recv: Receiver<Job>, regions: &RegionManager) {
     while let Ok(job) = recv.recv() {
-        let Job(coords, chunk) = job;
-        if let Ok(chunk) = chunk.read() {
-            let ChunkCoords(chunk_x, chunk_z) = coords;
-            let region_position = 
-                RegionPosition::from_chunk_position(chunk_x, chunk_z);
-            let region_chunk_position = 
-                RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
-            let mut region = provider.get_region(region_position).unwrap();
-
-            let chunk_data = chunk.get_save_data(coords);
-
-            if let Err(err) = region.write_chunk(region_chunk_position, 
-                chunk_data)
-            {
-                let err = match err {
-                    ChunkWriteError::LengthExceedsMaximum { length } 
-                    => anyhow!(format!("Too large ({} bytes)", length)),
-                    ChunkWriteError::IOError { io_error } => io_error.into(),
-                };
-                eprintln!("Error saving chunk at ({}, {}): {}", 
-                    coords.0, coords.1, err);
+        let batch: Vec<Job> = Some(job).into_iter()
+            .chain(recv.try_iter())
+            .collect();
+        let mut chunks = vec![];
+        let mut failed = vec![];
+        for Job(coords, chunk) in batch {
+            // Chunks that can't be saved still stop being pending,
+            // otherwise reading them would block forever
+            let data = panic::catch_unwind(AssertUnwindSafe(|| {
+                let chunk = chunk.read().unwrap_or_else(PoisonError::into_inner);
+                chunk.get_save_data(coords)
+            }));
+            match data {
+                Ok(data) => chunks.push((coords, data)),
+                Err(_) => {
+                    eprintln!("Error saving chunk at ({}, {})", coords.0, coords.1);
+                    failed.push(coords);
+                }
             }
-        };
+        }
+        regions.finish_pending(&failed);
+        regions.write_chunks(chunks);
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::biomes::Biome;
 use crate::blocks::Block;
 use nalgebra::{Vector3, vector};
 
@@ -38,4 +39,18 @@ impl<'a> WorldView<'a> {
     pub fn displace(&mut self, dir: Vector3<i32>) {
         self.center += dir
     }
+
+    pub fn get_center(&self) -> Vector3<i32> {
+        self.center
+    }
+
+    pub fn biome(&self) -> &'static Biome {
+        self.world.get_biome(&self.center)
+    }
+
+    /// True if nothing opaque is above the center block
+    pub fn sees_sky(&self) -> bool {
+        (1..CHUNK_HEIGHT as i32 - self.center.y)
+            .all(|dy| !self.get(0, dy, 0).opaque)
+    }
 }
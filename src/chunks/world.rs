@@ -1,4 +1,6 @@
+use crate::biomes::Biome;
 use crate::blocks::Block;
+use crate::block_entities::BlockEntity;
 use super::WorldView;
 use super::ChunkData;
 use super::data::CHUNK_HEIGHT;
@@ -7,6 +9,8 @@ use super::chunk::Chunk;
 use super::coords::ChunkCoords;
 use super::events::ChunkEvent;
 use super::saving::ChunkSaver;
+use super::pregeneration::Pregeneration;
+use anyhow::Result;
 use legion::system;
 use legion::systems::Builder;
 use nalgebra::Vector3;
@@ -14,64 +18,80 @@ use nalgebra::vector;
 use std::mem::take;
 use std::sync::Mutex;
 use std::time::Duration;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use super::loading::ChunkLoader;
+use crate::autosave::Autosave;
+use crate::level::{LevelData, Weather};
+use crate::dimensions::Dimensions;
 use std::sync::{Arc, RwLock};
 use rayon::prelude::*;
 
 const CHUNK_UNLOAD_TIME: Duration = Duration::from_secs(10);
 const MAX_UNLOADS_PER_TICK: usize = 2;
+const MAX_AUTOSAVES_PER_TICK: usize = 16;
 
 pub fn register(schedule: &mut Builder) {
     schedule.add_system(update_changed_system());
+    schedule.add_system(autosave_chunks_system());
     schedule.add_thread_local(unload_chunks_system());
     schedule.add_thread_local(random_tick_system());
+    schedule.add_thread_local(tick_block_entities_system());
+    schedule.add_thread_local(send_block_changes_system());
+    schedule.add_thread_local(pregenerate_system());
 }
 
 #[system]
-fn update_changed(#[resource] world: &mut World) {
-    let last_changes = take(&mut world.changed)
-        .into_inner().unwrap();
-    let to_update = last_changes.iter()
-        .map(|pos| adjacent_coords(pos))
-        .flatten();
-    for pos in to_update {
-        let view = world.get_view(pos);
-        let block = world.get_block(&pos);
-        block.update(&view);
+fn update_changed(#[resource] dimensions: &mut Dimensions) {
+    for dimension in dimensions.iter_mut() {
+        dimension.chunks.update_changed();
     }
 }
 
 #[system]
-fn random_tick(#[resource] world: &mut World) {
-    let chunks: Vec<(ChunkCoords, Chunk)> = world.chunks.read().unwrap()
-        .iter().map(|(coords, chunk)| (*coords, chunk.clone()))
-        .collect();
-    chunks.par_iter()
-        .for_each(|(coords, chunk)| {
-            let center = coords.global(0, 0, 0);
-            let view = WorldView::new(world, center);
-            chunk.random_tick(&view);
-        });
+fn random_tick(#[resource] dimensions: &Dimensions, #[resource] level: &LevelData,
+    #[resource] weather: &Weather) 
+{
+    let ticks_per_section = level.game_rules.get_int("randomTickSpeed").max(0);
+    for dimension in dimensions.iter() {
+        dimension.chunks.random_tick(ticks_per_section, weather);
+    }
 }
 
 #[system]
-fn unload_chunks(#[resource] world: &mut World) {
-    let mut removed = vec![];
-    for (coords, chunk) in world.chunks.read().unwrap().iter() {
-        if chunk.time_unobserved() > CHUNK_UNLOAD_TIME {
-            removed.push(*coords);
-            if removed.len() == MAX_UNLOADS_PER_TICK {
-                break;
-            }
-        }
+fn tick_block_entities(#[resource] dimensions: &Dimensions) {
+    for dimension in dimensions.iter() {
+        dimension.chunks.tick_block_entities();
+    }
+}
+
+#[system]
+fn send_block_changes(#[resource] dimensions: &Dimensions) {
+    for dimension in dimensions.iter() {
+        dimension.chunks.flush_block_changes();
+    }
+}
+
+#[system]
+fn pregenerate(#[resource] dimensions: &Dimensions) {
+    for dimension in dimensions.iter() {
+        dimension.chunks.pregenerate(&dimension.name);
     }
-    let mut chunks = world.chunks.write().unwrap();
-    let mut saver = world.saver.lock().unwrap();
-    for coords in removed {
-        if let Some(chunk) = chunks.remove(&coords) {
-            chunk.save(coords, &mut saver);
+}
+
+#[system]
+fn autosave_chunks(#[resource] dimensions: &Dimensions, #[resource] autosave: &Autosave) {
+    for dimension in dimensions.iter() {
+        if autosave.is_saving() {
+            dimension.chunks.queue_dirty_chunks();
         }
+        dimension.chunks.save_queued(MAX_AUTOSAVES_PER_TICK);
+    }
+}
+
+#[system]
+fn unload_chunks(#[resource] dimensions: &mut Dimensions) {
+    for dimension in dimensions.iter_mut() {
+        dimension.chunks.unload_unobserved();
     }
 }
 
@@ -81,16 +101,95 @@ pub struct World {
     >>,
     chunk_loaders: Arc<Vec<Box<dyn ChunkLoader>>>,
     saver: Mutex<ChunkSaver>,
+    save_queue: Mutex<HashSet<ChunkCoords>>,
     changed: Mutex<Vec<Vector3<i32>>>,
+    changed_chunks: Mutex<HashSet<ChunkCoords>>,
+    pregeneration: Mutex<Option<Pregeneration>>,
 }
 
 impl World {
-    pub fn new(chunk_sources: Vec<Box<dyn ChunkLoader>>) -> Self {
+    pub fn new(chunk_sources: Vec<Box<dyn ChunkLoader>>, saver: ChunkSaver) -> Self {
         Self {
             chunks: Arc::new(RwLock::new(HashMap::new())),
             chunk_loaders: Arc::new(chunk_sources),
-            saver: Mutex::new(ChunkSaver::new()),
+            saver: Mutex::new(saver),
+            save_queue: Mutex::new(HashSet::new()),
             changed: Mutex::new(vec![]),
+            changed_chunks: Mutex::new(HashSet::new()),
+            pregeneration: Mutex::new(None),
+        }
+    }
+
+    /// Where the generator puts the ground in a column
+    pub fn surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        self.chunk_loaders.iter()
+            .find_map(|loader| loader.surface_height(x, z))
+    }
+
+    fn update_changed(&mut self) {
+        let last_changes = take(&mut self.changed)
+            .into_inner().unwrap();
+        let to_update = last_changes.iter()
+            .map(|pos| adjacent_coords(pos))
+            .flatten();
+        for pos in to_update {
+            let view = self.get_view(pos);
+            let block = self.get_block(&pos);
+            block.update(&view);
+        }
+    }
+
+    /// Sends the block changes of this tick to the players, 
+    /// batched per chunk section
+    fn flush_block_changes(&self) {
+        let changed = take(&mut *self.changed_chunks.lock().unwrap());
+        for coords in changed {
+            let chunk = self.chunks.read().unwrap()
+                .get(&coords).cloned();
+            if let Some(chunk) = chunk {
+                chunk.flush_changes();
+            }
+        }
+    }
+
+    fn random_tick(&self, ticks_per_section: i32, weather: &Weather) {
+        let chunks: Vec<(ChunkCoords, Chunk)> = self.chunks.read().unwrap()
+            .iter().map(|(coords, chunk)| (*coords, chunk.clone()))
+            .collect();
+        chunks.par_iter()
+            .for_each(|(coords, chunk)| {
+                let center = coords.global(0, 0, 0);
+                let view = WorldView::new(self, center);
+                chunk.random_tick(&view, ticks_per_section, weather);
+            });
+    }
+
+    fn tick_block_entities(&self) {
+        let chunks: Vec<(ChunkCoords, Chunk)> = self.chunks.read().unwrap()
+            .iter().map(|(coords, chunk)| (*coords, chunk.clone()))
+            .collect();
+        for (coords, chunk) in chunks {
+            let view = WorldView::new(self, coords.global(0, 0, 0));
+            chunk.tick_block_entities(&view);
+        }
+    }
+
+    fn unload_unobserved(&mut self) {
+        let mut removed = vec![];
+        for (coords, chunk) in self.chunks.read().unwrap().iter() {
+            if chunk.time_unobserved() > CHUNK_UNLOAD_TIME {
+                removed.push(*coords);
+                if removed.len() == MAX_UNLOADS_PER_TICK {
+                    break;
+                }
+            }
+        }
+        let mut chunks = self.chunks.write().unwrap();
+        let mut saver = self.saver.lock().unwrap();
+        for coords in removed {
+            if let Some(chunk) = chunks.remove(&coords) {
+                chunk.save(coords, &mut saver);
+            }
         }
     }
 
@@ -104,13 +203,13 @@ impl World {
                 chunk.subscribe(id, callback);
             },
             None => {
-                let chunk = Chunk::new();
+                let chunk = Chunk::new(coords);
                 chunk.subscribe(id, callback);
                 self.chunks.write().unwrap()
-                    .insert(coords, chunk);
+                    .insert(coords, chunk.clone());
                 let sources = self.chunk_loaders.clone();
                 let world = self.chunks.clone();
-                tokio::spawn(async move {
+                let task = tokio::spawn(async move {
                     if let Some(data) = load_chunk(coords, &*sources).await {
                         if let Some(chunk) = world.write().unwrap().get_mut(&coords) {
                             chunk.load(data);
@@ -119,6 +218,7 @@ impl World {
                         eprintln!("No chunk source could load chunk at {:?}", coords);
                     }
                 });
+                chunk.set_loading(task);
             }
         }
     }
@@ -128,9 +228,33 @@ impl World {
             .get(&coords).cloned();
         if let Some(chunk) = chunk {
             chunk.unsubscribe(id);
+            if !chunk.is_observed() {
+                self.cancel_load(coords);
+            }
+        }
+    }
+
+    /// Forgets a chunk that left everyone's view before it finished loading
+    fn cancel_load(&self, coords: ChunkCoords) {
+        let mut chunks = self.chunks.write().unwrap();
+        let pending = chunks.get(&coords)
+            .map(|chunk| !chunk.is_loaded() && !chunk.is_observed())
+            .unwrap_or(false);
+        if pending {
+            if let Some(chunk) = chunks.remove(&coords) {
+                chunk.cancel_loading();
+            }
         }
     }
 
+    /// False for chunks nobody asked for and ones still being loaded
+    pub fn is_loaded(&self, coords: ChunkCoords) -> bool {
+        self.chunks.read().unwrap()
+            .get(&coords)
+            .map(|chunk| chunk.is_loaded())
+            .unwrap_or(false)
+    }
+
     pub fn get_block(&self, pos: &Vector3<i32>) -> &'static Block {
         if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
             return Block::air();
@@ -146,6 +270,20 @@ impl World {
         }
     }
 
+    pub fn get_biome(&self, pos: &Vector3<i32>) -> &'static Biome {
+        let coords = ChunkCoords::from_block(pos);
+        let chunk = self.chunks.read().unwrap()
+            .get(&coords).cloned();
+        match chunk {
+            Some(chunk) => {
+                let y = pos.y.max(0).min(CHUNK_HEIGHT as i32 - 1);
+                let (x, _, z) = coords.relative(pos);
+                chunk.get_biome(x, y as usize, z)
+            }
+            None => Biome::plains(),
+        }
+    }
+
     pub fn set_block(&self, pos: &Vector3<i32>, block: &'static Block) {
         if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
             return;
@@ -156,15 +294,106 @@ impl World {
         if let Some(chunk) = chunk {
             self.changed.lock().unwrap()
                 .push(pos.clone());
+            self.changed_chunks.lock().unwrap()
+                .insert(coords);
             let (x, y, z) = coords.relative(pos);
             chunk.set_block(x, y, z, block);
         }
     }
 
+    pub fn get_block_entity(&self, pos: &Vector3<i32>) -> Option<BlockEntity> {
+        if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
+            return None;
+        }
+        let coords = ChunkCoords::from_block(pos);
+        let chunk = self.chunks.read().unwrap()
+            .get(&coords).cloned()?;
+        let (x, y, z) = coords.relative(pos);
+        chunk.get_block_entity(x, y, z)
+    }
+
+    /// Replaces the data of the block entity at a position, ignored if 
+    /// the block there doesn't have one
+    pub fn set_block_entity(&self, pos: &Vector3<i32>, entity: BlockEntity) {
+        if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
+            return;
+        }
+        let coords = ChunkCoords::from_block(pos);
+        let chunk = self.chunks.read().unwrap()
+            .get(&coords).cloned();
+        if let Some(chunk) = chunk {
+            self.changed_chunks.lock().unwrap()
+                .insert(coords);
+            let (x, y, z) = coords.relative(pos);
+            chunk.set_block_entity(x, y, z, entity);
+        }
+    }
+
+    /// Generates and saves the chunks up to `radius` chunks away from `center`
+    /// in the background, at most `max_running` at once
+    pub fn start_pregeneration(&self, center: ChunkCoords, radius: i32, max_running: usize) {
+        *self.pregeneration.lock().unwrap() = 
+            Some(Pregeneration::new(center, radius, max_running));
+    }
+
+    fn pregenerate(&self, name: &str) {
+        let mut pregeneration = self.pregeneration.lock().unwrap();
+        if let Some(job) = pregeneration.as_mut() {
+            let mut saver = self.saver.lock().unwrap();
+            if job.tick(name, &self.chunks, &self.chunk_loaders, &mut saver) {
+                *pregeneration = None;
+            }
+        }
+    }
+
+    /// Removes the chunks that were never changed in game from the region 
+    /// files, they are generated again if needed. Blocks until it's done
+    pub fn trim_unmodified(&self) -> Result<usize> {
+        let regions = self.saver.lock().unwrap().regions();
+        regions.trim(ChunkData::was_modified)
+    }
+
     pub fn get_view(&self, center: Vector3<i32>) -> WorldView {
         WorldView::new(self, center)
     }
 
+    /// Queues every modified chunk to be saved over the next ticks
+    pub fn queue_dirty_chunks(&self) {
+        let chunks = self.chunks.read().unwrap();
+        let mut queue = self.save_queue.lock().unwrap();
+        for (coords, chunk) in chunks.iter() {
+            if chunk.is_dirty() {
+                queue.insert(*coords);
+            }
+        }
+    }
+
+    fn save_queued(&self, max: usize) {
+        let mut queue = self.save_queue.lock().unwrap();
+        if queue.is_empty() {
+            return;
+        }
+        let chunks = self.chunks.read().unwrap();
+        let mut saver = self.saver.lock().unwrap();
+        let mut saved = 0;
+        let mut done = vec![];
+        for coords in queue.iter() {
+            done.push(*coords);
+            if let Some(chunk) = chunks.get(coords) {
+                if chunk.is_dirty() {
+                    chunk.save(*coords, &mut saver);
+                    saved += 1;
+                    if saved == max {
+                        break;
+                    }
+                }
+            }
+        }
+        for coords in done {
+            queue.remove(&coords);
+        }
+    }
+
     pub fn save_all(&mut self) {
         let mut saver = self.saver.lock().unwrap();
         let chunks = self.chunks.write().unwrap();
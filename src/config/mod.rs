@@ -1,17 +1,44 @@
 use tokio::fs::File;
-use yaml_rust::YamlLoader;
+use yaml_rust::{Yaml, YamlLoader};
 use std::io::ErrorKind::NotFound;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use anyhow::Result;
 use thiserror::Error;
 
+use crate::dimensions::{BorderSettings, DimensionType, WorldSettings, DEFAULT_BORDER_DIAMETER};
+
 const CONFIG_PATH: &str = "config.yml";
 const DEFAULT_CONFIG: &str = include_str!("default.yml");
+const DEFAULT_AUTOSAVE_INTERVAL: u64 = 300;
+const DEFAULT_PREGENERATE_THROTTLE: usize = 4;
+pub const DEFAULT_FLAT_PRESET: &str = "minecraft:bedrock,minecraft:dirt,minecraft:grass_block;minecraft:plains";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelType {
+    Flat,
+    Default,
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub port: u16,
     pub motd: String,
+    /// Seconds between autosaves, 0 disables them
+    pub autosave_interval: u64,
+    /// Generator used for chunks that aren't saved yet
+    pub level_type: LevelType,
+    /// Layers used by flat worlds, in the vanilla preset syntax
+    pub flat_preset: String,
+    /// Worlds the server runs, new players spawn in the first one
+    pub worlds: Vec<WorldSettings>,
+    /// World border for dimensions that don't have one saved yet
+    pub border: BorderSettings,
+    /// Don't load chunks that are fully outside the world border
+    pub border_blocks_chunks: bool,
+    /// Chunks around spawn generated in the background on start, 0 disables it
+    pub pregenerate_radius: i32,
+    /// Chunks being pregenerated at the same time
+    pub pregenerate_throttle: usize,
 }
 
 impl Config {
@@ -37,10 +64,82 @@ impl Config {
                 .ok_or(MissingField("port"))?,
             motd: yaml["motd"].as_str().map(|motd| String::from(motd))
                 .ok_or(MissingField("motd"))?,
+            autosave_interval: yaml["autosave_interval"].as_i64()
+                .map(|interval| interval.max(0) as u64)
+                .unwrap_or(DEFAULT_AUTOSAVE_INTERVAL),
+            level_type: match yaml["level_type"].as_str() {
+                None | Some("flat") => LevelType::Flat,
+                Some("default") => LevelType::Default,
+                Some(_) => return Err(InvalidField("level_type").into()),
+            },
+            flat_preset: yaml["flat_preset"].as_str()
+                .unwrap_or(DEFAULT_FLAT_PRESET).into(),
+            worlds: worlds(yaml)?,
+            border: border_settings(yaml),
+            border_blocks_chunks: yaml["border_blocks_chunks"].as_bool()
+                .unwrap_or(false),
+            pregenerate_radius: yaml["pregenerate_radius"].as_i64()
+                .map(|radius| radius.max(0) as i32)
+                .unwrap_or(0),
+            pregenerate_throttle: yaml["pregenerate_throttle"].as_i64()
+                .map(|throttle| throttle.max(1) as usize)
+                .unwrap_or(DEFAULT_PREGENERATE_THROTTLE),
         })
     }
 }
 
+/// Configs made before worlds could be listed get the vanilla dimensions
+fn worlds(yaml: &Yaml) -> Result<Vec<WorldSettings>> {
+    let list = match yaml["worlds"].as_vec() {
+        Some(list) => list,
+        None => return Ok(vec![
+            WorldSettings::vanilla(DimensionType::Overworld),
+            WorldSettings::vanilla(DimensionType::Nether),
+            WorldSettings::vanilla(DimensionType::End),
+        ]),
+    };
+    let mut worlds: Vec<WorldSettings> = vec![];
+    for world in list {
+        let settings = WorldSettings {
+            name: world["name"].as_str().ok_or(MissingField("name"))?.into(),
+            kind: world["type"].as_str().ok_or(MissingField("type"))
+                .map(DimensionType::from_config)?
+                .ok_or(InvalidField("type"))?,
+            region_folder: world["region_folder"].as_str()
+                .ok_or(MissingField("region_folder"))?.into(),
+            flat_preset: world["flat_preset"].as_str().map(String::from),
+        };
+        let shared = worlds.iter().any(|other| other.name == settings.name
+            || other.region_folder == settings.region_folder);
+        if shared {
+            return Err(InvalidField("worlds").into());
+        }
+        worlds.push(settings);
+    }
+    if worlds.is_empty() {
+        return Err(InvalidField("worlds").into());
+    }
+    Ok(worlds)
+}
+
+fn border_settings(yaml: &Yaml) -> BorderSettings {
+    // Whole numbers are read as integers
+    let number = |key: &str| yaml[key].as_f64()
+        .or_else(|| yaml[key].as_i64().map(|value| value as f64));
+    let mut border = BorderSettings::new(number("border_diameter")
+        .unwrap_or(DEFAULT_BORDER_DIAMETER));
+    border.center_x = number("border_center_x").unwrap_or(border.center_x);
+    border.center_z = number("border_center_z").unwrap_or(border.center_z);
+    border.warning_blocks = yaml["border_warning_blocks"].as_i64()
+        .map_or(border.warning_blocks, |blocks| blocks as i32);
+    border.warning_time = yaml["border_warning_time"].as_i64()
+        .map_or(border.warning_time, |seconds| seconds as i32);
+    border.damage_per_block = number("border_damage_per_block")
+        .unwrap_or(border.damage_per_block);
+    border.safe_zone = number("border_safe_zone").unwrap_or(border.safe_zone);
+    border
+}
+
 async fn create_default_file() -> Result<()> {
     let mut file = File::create(CONFIG_PATH).await?;
     file.write_all(DEFAULT_CONFIG.as_bytes()).await?;
@@ -48,5 +147,34 @@ async fn create_default_file() -> Result<()> {
 }
 
 #[derive(Error, Debug)]
-#[error("missing or invalid field: {0}")]
-struct MissingField(&'static str);
\ No newline at end of file
+#[error("missing field: {0}")]
+struct MissingField(&'static str);
+
+#[derive(Error, Debug)]
+#[error("invalid value for field: {0}")]
+struct InvalidField(&'static str);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_worlds() {
+        let config = Config::from_str(DEFAULT_CONFIG).unwrap();
+        let names: Vec<_> = config.worlds.iter().map(|world| world.name.as_str()).collect();
+        assert_eq!(names, ["minecraft:overworld", "minecraft:the_nether", "minecraft:the_end"]);
+        assert_eq!(config.worlds[1].kind, DimensionType::Nether);
+        assert_eq!(config.worlds[2].region_folder, "world/DIM1/region");
+    }
+
+    #[test]
+    fn worlds_need_their_own_folder() {
+        let source = "port: 25565\nmotd: test\nworlds:\n\
+            - {name: a, type: overworld, region_folder: world/region}\n\
+            - {name: b, type: overworld, region_folder: world/region}\n";
+        assert!(Config::from_str(source).is_err());
+        let source = "port: 25565\nmotd: test\nworlds:\n\
+            - {name: a, type: moon, region_folder: world/region}\n";
+        assert!(Config::from_str(source).is_err());
+    }
+}
\ No newline at end of file
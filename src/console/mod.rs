@@ -0,0 +1,145 @@
+use std::io::{self, BufRead};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+use anyhow::{Result, anyhow};
+use legion::*;
+use systems::Builder;
+use world::SubWorld;
+
+use crate::autosave::Autosave;
+use crate::dimensions::{Dimensions, WorldBorder};
+use crate::entities::Name;
+use crate::level::WorldTime;
+use crate::players::{GameMode, GameModeChangeQueue};
+
+/// Lines typed into the server's terminal, read on their own thread
+pub struct Console {
+    receiver: Mutex<Receiver<String>>,
+}
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    resources.insert(Console {
+        receiver: Mutex::new(receiver),
+    });
+    schedule.add_system(run_commands_system());
+}
+
+#[system]
+#[read_component(Name)]
+fn run_commands(world: &SubWorld, #[resource] console: &Console,
+    #[resource] game_modes: &GameModeChangeQueue, #[resource] dimensions: &mut Dimensions,
+    #[resource] autosave: &Autosave, #[resource] time: &mut WorldTime)
+{
+    for line in console.receiver.lock().unwrap().try_iter() {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let result = match args[..] {
+            [] => continue,
+            ["save-all"] => {
+                autosave.save_all();
+                println!("Saving the world");
+                Ok(())
+            },
+            ["time", ref args @ ..] => change_time(time, args),
+            ["gamemode", mode, player] => set_game_mode(world, game_modes, mode, player),
+            // Like vanilla, the console changes the overworld's border
+            ["worldborder", ref args @ ..] => {
+                let overworld = dimensions.overworld();
+                change_border(&mut dimensions.get_mut(overworld).border, args)
+            },
+            [command, ..] => Err(anyhow!("Unknown command {}", command)),
+        };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+fn set_game_mode(world: &SubWorld, queue: &GameModeChangeQueue, mode: &str,
+    player: &str) -> Result<()>
+{
+    let mode = GameMode::from_name(mode)
+        .ok_or_else(|| anyhow!("Unknown game mode {}", mode))?;
+    let entity = find_player(world, player)?;
+    queue.send(entity, mode);
+    println!("Set {}'s game mode to {}", player, mode.name());
+    Ok(())
+}
+
+fn change_time(time: &mut WorldTime, args: &[&str]) -> Result<()> {
+    let ticks = |arg: &str| match arg {
+        "day" => Ok(1000),
+        "noon" => Ok(6000),
+        "night" => Ok(13000),
+        "midnight" => Ok(18000),
+        _ => arg.parse::<i64>().map_err(|_| anyhow!("Invalid time {}", arg)),
+    };
+    match args {
+        ["set", value] => time.set_time(ticks(value)?),
+        ["add", value] => time.add_time(ticks(value)?),
+        ["query", "daytime"] => (),
+        ["query", "gametime"] => {
+            println!("The game time is {}", time.world_age());
+            return Ok(());
+        },
+        ["query", "day"] => {
+            println!("The day is {}", time.day());
+            return Ok(());
+        },
+        _ => return Err(anyhow!("Usage: time set|add <time> or time query daytime|gametime|day")),
+    }
+    println!("The time is {}", time.time_of_day());
+    Ok(())
+}
+
+fn change_border(border: &mut WorldBorder, args: &[&str]) -> Result<()> {
+    let number = |arg: &str| arg.parse::<f64>()
+        .map_err(|_| anyhow!("Invalid number {}", arg));
+    let seconds = |arg: Option<&&str>| match arg {
+        Some(arg) => arg.parse::<u64>().map_err(|_| anyhow!("Invalid time {}", arg)),
+        None => Ok(0),
+    };
+    match args {
+        ["get"] => (),
+        ["set", diameter, time @ ..] if time.len() <= 1 => {
+            border.lerp_diameter(number(diameter)?, seconds(time.first())? * 1000);
+        },
+        ["add", diameter, time @ ..] if time.len() <= 1 => {
+            let diameter = border.settings().size_lerp_target + number(diameter)?;
+            border.lerp_diameter(diameter, seconds(time.first())? * 1000);
+        },
+        ["center", x, z] => border.set_center(number(x)?, number(z)?),
+        ["warning", "distance", blocks] => {
+            border.set_warning_blocks(blocks.parse()
+                .map_err(|_| anyhow!("Invalid distance {}", blocks))?);
+        },
+        ["warning", "time", time] => border.set_warning_time(seconds(Some(time))? as i32),
+        ["damage", "amount", amount] => {
+            border.set_damage(number(amount)?, border.settings().safe_zone);
+        },
+        ["damage", "buffer", blocks] => {
+            border.set_damage(border.settings().damage_per_block, number(blocks)?);
+        },
+        _ => return Err(anyhow!("Usage: worldborder get|set|add|center|warning|damage")),
+    }
+    let settings = border.settings();
+    println!("The world border is {} blocks wide, centered on {} {}",
+        settings.size_lerp_target, settings.center_x, settings.center_z);
+    Ok(())
+}
+
+fn find_player(world: &SubWorld, name: &str) -> Result<Entity> {
+    <(Entity, &Name)>::query().iter(world)
+        .find(|(_, player)| player.0 == name)
+        .map(|(entity, _)| *entity)
+        .ok_or_else(|| anyhow!("{} isn't online", name))
+}
@@ -0,0 +1,281 @@
+use std::mem::take;
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::chunks::ChunkCoords;
+use crate::net::ClientboundPacket;
+
+const MILLIS_PER_TICK: u64 = 50;
+pub const DEFAULT_BORDER_DIAMETER: f64 = 59999968.0;
+
+/// What gets saved in level.dat for dimensions other than the overworld,
+/// named like the Border* fields vanilla uses for the overworld
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all="PascalCase")]
+pub struct BorderSettings {
+    pub center_x: f64,
+    pub center_z: f64,
+    pub size: f64,
+    pub size_lerp_target: f64,
+    pub size_lerp_time: u64,
+    pub warning_blocks: i32,
+    pub warning_time: i32,
+    pub damage_per_block: f64,
+    pub safe_zone: f64,
+}
+
+impl BorderSettings {
+    pub fn new(diameter: f64) -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            size: diameter,
+            size_lerp_target: diameter,
+            size_lerp_time: 0,
+            warning_blocks: 5,
+            warning_time: 15,
+            damage_per_block: 0.2,
+            safe_zone: 5.0,
+        }
+    }
+}
+
+/// Overworld border in the top level keys of level.dat, where vanilla
+/// keeps it. Vanilla stores the warnings and lerp time as other types
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelBorder {
+    #[serde(rename="BorderCenterX")]
+    center_x: f64,
+    #[serde(rename="BorderCenterZ")]
+    center_z: f64,
+    #[serde(rename="BorderSize")]
+    size: f64,
+    #[serde(rename="BorderSizeLerpTarget")]
+    size_lerp_target: f64,
+    #[serde(rename="BorderSizeLerpTime")]
+    size_lerp_time: i64,
+    #[serde(rename="BorderWarningBlocks")]
+    warning_blocks: f64,
+    #[serde(rename="BorderWarningTime")]
+    warning_time: f64,
+    #[serde(rename="BorderDamagePerBlock")]
+    damage_per_block: f64,
+    #[serde(rename="BorderSafeZone")]
+    safe_zone: f64,
+}
+
+impl From<&BorderSettings> for LevelBorder {
+    fn from(settings: &BorderSettings) -> Self {
+        Self {
+            center_x: settings.center_x,
+            center_z: settings.center_z,
+            size: settings.size,
+            size_lerp_target: settings.size_lerp_target,
+            size_lerp_time: settings.size_lerp_time as i64,
+            warning_blocks: settings.warning_blocks as f64,
+            warning_time: settings.warning_time as f64,
+            damage_per_block: settings.damage_per_block,
+            safe_zone: settings.safe_zone,
+        }
+    }
+}
+
+impl From<&LevelBorder> for BorderSettings {
+    fn from(border: &LevelBorder) -> Self {
+        Self {
+            center_x: border.center_x,
+            center_z: border.center_z,
+            size: border.size,
+            size_lerp_target: border.size_lerp_target,
+            size_lerp_time: border.size_lerp_time.max(0) as u64,
+            warning_blocks: border.warning_blocks as i32,
+            warning_time: border.warning_time as i32,
+            damage_per_block: border.damage_per_block,
+            safe_zone: border.safe_zone,
+        }
+    }
+}
+
+/// Square border around a dimension, the diameter can move
+/// towards a new value over some time
+pub struct WorldBorder {
+    center: (f64, f64),
+    old_diameter: f64,
+    diameter: f64,
+    lerp_time: u64,
+    lerp_remaining: u64,
+    warning_blocks: i32,
+    warning_time: i32,
+    /// Not applied to anyone until players have health
+    damage_per_block: f64,
+    safe_zone: f64,
+    blocks_chunk_loading: bool,
+    queued: Vec<ClientboundPacket>,
+    packets: Vec<ClientboundPacket>,
+}
+
+impl WorldBorder {
+    pub fn new(settings: &BorderSettings, blocks_chunk_loading: bool) -> Self {
+        Self {
+            center: (settings.center_x, settings.center_z),
+            old_diameter: settings.size,
+            diameter: settings.size_lerp_target,
+            lerp_time: settings.size_lerp_time,
+            lerp_remaining: settings.size_lerp_time,
+            warning_blocks: settings.warning_blocks,
+            warning_time: settings.warning_time,
+            damage_per_block: settings.damage_per_block,
+            safe_zone: settings.safe_zone,
+            blocks_chunk_loading,
+            queued: vec![],
+            packets: vec![],
+        }
+    }
+
+    pub fn settings(&self) -> BorderSettings {
+        BorderSettings {
+            center_x: self.center.0,
+            center_z: self.center.1,
+            size: self.current_diameter(),
+            size_lerp_target: self.diameter,
+            size_lerp_time: self.lerp_remaining,
+            warning_blocks: self.warning_blocks,
+            warning_time: self.warning_time,
+            damage_per_block: self.damage_per_block,
+            safe_zone: self.safe_zone,
+        }
+    }
+
+    pub fn current_diameter(&self) -> f64 {
+        if self.lerp_remaining == 0 {
+            return self.diameter;
+        }
+        let progress = self.lerp_remaining as f64 / self.lerp_time as f64;
+        self.diameter + (self.old_diameter - self.diameter) * progress
+    }
+
+    pub fn set_center(&mut self, x: f64, z: f64) {
+        self.center = (x, z);
+        self.queued.push(ClientboundPacket::WorldBorderCenter(x, z));
+    }
+
+    pub fn set_diameter(&mut self, diameter: f64) {
+        self.old_diameter = diameter;
+        self.diameter = diameter;
+        self.lerp_remaining = 0;
+        self.queued.push(ClientboundPacket::WorldBorderSize(diameter));
+    }
+
+    /// Moves from the current diameter to the new one in the given milliseconds
+    pub fn lerp_diameter(&mut self, diameter: f64, millis: u64) {
+        if millis == 0 {
+            return self.set_diameter(diameter);
+        }
+        self.old_diameter = self.current_diameter();
+        self.diameter = diameter;
+        self.lerp_time = millis;
+        self.lerp_remaining = millis;
+        self.queued.push(ClientboundPacket::WorldBorderLerpSize {
+            old_diameter: self.old_diameter,
+            new_diameter: diameter,
+            speed: millis,
+        });
+    }
+
+    pub fn set_warning_blocks(&mut self, blocks: i32) {
+        self.warning_blocks = blocks;
+        self.queued.push(ClientboundPacket::WorldBorderWarningBlocks(blocks));
+    }
+
+    pub fn set_warning_time(&mut self, seconds: i32) {
+        self.warning_time = seconds;
+        self.queued.push(ClientboundPacket::WorldBorderWarningTime(seconds));
+    }
+
+    pub fn set_damage(&mut self, per_block: f64, safe_zone: f64) {
+        self.damage_per_block = per_block;
+        self.safe_zone = safe_zone;
+    }
+
+    /// Vanilla rule, a block is inside if any part of it is
+    pub fn contains_block(&self, pos: &Vector3<i32>) -> bool {
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        pos.x as f64 + 1.0 > min_x && (pos.x as f64) < max_x
+            && pos.z as f64 + 1.0 > min_z && (pos.z as f64) < max_z
+    }
+
+    pub fn contains_chunk(&self, coords: ChunkCoords) -> bool {
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        let (x, z) = (coords.0 as f64 * 16.0, coords.1 as f64 * 16.0);
+        x + 16.0 > min_x && x < max_x && z + 16.0 > min_z && z < max_z
+    }
+
+    /// Chunks fully outside the border are never loaded if the config says so
+    pub fn allows_chunk(&self, coords: ChunkCoords) -> bool {
+        !self.blocks_chunk_loading || self.contains_chunk(coords)
+    }
+
+    /// Everything a player needs when they first see this border
+    pub fn init_packet(&self) -> ClientboundPacket {
+        ClientboundPacket::WorldBorderInit {
+            x: self.center.0,
+            z: self.center.1,
+            old_diameter: self.current_diameter(),
+            new_diameter: self.diameter,
+            speed: self.lerp_remaining,
+            warning_time: self.warning_time,
+            warning_blocks: self.warning_blocks,
+        }
+    }
+
+    /// Changes made since the last tick
+    pub fn get_packets(&self) -> &[ClientboundPacket] {
+        &self.packets
+    }
+
+    pub fn tick(&mut self) {
+        self.packets = take(&mut self.queued);
+        self.lerp_remaining = self.lerp_remaining.saturating_sub(MILLIS_PER_TICK);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let radius = self.current_diameter() / 2.0;
+        let (x, z) = self.center;
+        (x - radius, z - radius, x + radius, z + radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use super::*;
+
+    #[test]
+    fn lerp_and_bounds() {
+        let mut border = WorldBorder::new(&BorderSettings::new(32.0), true);
+        assert!(border.contains_block(&vector!(-16, 0, 15)));
+        assert!(!border.contains_block(&vector!(16, 0, 0)));
+        assert!(border.allows_chunk(ChunkCoords(-1, 0)));
+        assert!(!border.allows_chunk(ChunkCoords(1, 0)));
+        border.lerp_diameter(64.0, 1000);
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert_eq!(border.current_diameter(), 48.0);
+        assert_eq!(border.settings().size_lerp_time, 500);
+    }
+
+    #[test]
+    fn level_border_round_trip() {
+        let mut settings = BorderSettings::new(100.0);
+        settings.center_x = -8.5;
+        settings.size_lerp_time = 2000;
+        settings.warning_blocks = 7;
+        let back = BorderSettings::from(&LevelBorder::from(&settings));
+        assert_eq!(back.center_x, -8.5);
+        assert_eq!(back.size_lerp_time, 2000);
+        assert_eq!(back.warning_blocks, 7);
+        assert_eq!(back.size, 100.0);
+    }
+}
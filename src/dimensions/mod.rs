@@ -0,0 +1,235 @@
+mod border;
+
+use anyhow::Result;
+use legion::*;
+use legion::systems::Builder;
+
+use crate::buckets::EntityTracker;
+use crate::chunks::{self, ChunkLoader, FlatGenerator, NoiseGenerator, World as ChunkWorld};
+use crate::config::{Config, LevelType, DEFAULT_FLAT_PRESET};
+use crate::level::LevelData;
+use crate::net::{DIM_NBT, DIM_NETHER_NBT, DIM_END_NBT, PlayerConnection};
+
+pub use border::{BorderSettings, LevelBorder, WorldBorder, DEFAULT_BORDER_DIAMETER};
+
+// There are no nether and end generators yet, so they get flat worlds
+// unless the config gives them a preset
+const NETHER_PRESET: &str = "minecraft:bedrock,60*minecraft:netherrack;minecraft:nether_wastes";
+const END_PRESET: &str = "minecraft:air;minecraft:the_end";
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources, config: &Config) -> Result<()> {
+    let level = resources.get::<LevelData>().unwrap();
+    let mut list = vec![];
+    for (index, world) in config.worlds.iter().enumerate() {
+        let (generator, is_flat): (Box<dyn ChunkLoader>, _) = match (&world.flat_preset, world.kind) {
+            (Some(preset), kind) => (Box::new(FlatGenerator::from_preset(preset)?),
+                kind == DimensionType::Overworld),
+            (None, DimensionType::Overworld) => match config.level_type {
+                LevelType::Flat => (Box::new(FlatGenerator::from_preset(&config.flat_preset)?), true),
+                LevelType::Default => (Box::new(NoiseGenerator::new(level.seed())), false),
+            },
+            (None, kind) => (Box::new(FlatGenerator::from_preset(kind.default_preset())?), false),
+        };
+        let top_level = match index {
+            0 => level.border.as_ref().map(BorderSettings::from),
+            _ => None,
+        };
+        let settings = top_level
+            .or_else(|| level.world_borders.get(&world.name).cloned())
+            .unwrap_or_else(|| config.border.clone());
+        let border = WorldBorder::new(&settings, config.border_blocks_chunks);
+        list.push(Dimension::new(world, generator, is_flat, border));
+    }
+    drop(level);
+    resources.insert(Dimensions { list });
+    schedule
+        .add_system(tick_borders_system())
+        .add_system(broadcast_borders_system());
+    Ok(())
+}
+
+#[system]
+fn tick_borders(#[resource] dimensions: &mut Dimensions) {
+    for dimension in dimensions.iter_mut() {
+        dimension.border.tick();
+    }
+}
+
+#[system(for_each)]
+fn broadcast_borders(conn: &PlayerConnection, dimension: &DimensionId,
+    #[resource] dimensions: &Dimensions)
+{
+    for packet in dimensions.get(*dimension).border.get_packets() {
+        conn.send(packet.clone());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DimensionType {
+    Overworld,
+    Nether,
+    End,
+}
+
+impl DimensionType {
+    /// Reads the type names used in config.yml
+    pub fn from_config(name: &str) -> Option<Self> {
+        match name {
+            "overworld" => Some(Self::Overworld),
+            "nether" => Some(Self::Nether),
+            "end" => Some(Self::End),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Overworld => "minecraft:overworld",
+            Self::Nether => "minecraft:the_nether",
+            Self::End => "minecraft:the_end",
+        }
+    }
+
+    /// Dimension type sent in the Join Game and Respawn packets
+    pub fn nbt(&self) -> &'static [u8] {
+        match self {
+            Self::Overworld => DIM_NBT,
+            Self::Nether => DIM_NETHER_NBT,
+            Self::End => DIM_END_NBT,
+        }
+    }
+
+    /// Same folders vanilla uses, so worlds can be copied back and forth
+    fn region_folder(&self) -> &'static str {
+        match self {
+            Self::Overworld => "world/region",
+            Self::Nether => "world/DIM-1/region",
+            Self::End => "world/DIM1/region",
+        }
+    }
+
+    fn default_preset(&self) -> &'static str {
+        match self {
+            Self::Overworld => DEFAULT_FLAT_PRESET,
+            Self::Nether => NETHER_PRESET,
+            Self::End => END_PRESET,
+        }
+    }
+
+    /// Horizontal distances are this many times bigger in the overworld
+    pub fn coordinate_scale(&self) -> f64 {
+        match self {
+            Self::Nether => 8.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn has_weather(&self) -> bool {
+        *self == Self::Overworld
+    }
+}
+
+/// A world listed in config.yml
+#[derive(Clone, Debug)]
+pub struct WorldSettings {
+    /// Sent to clients and stored in player data
+    pub name: String,
+    pub kind: DimensionType,
+    pub region_folder: String,
+    /// Flat layers for this world, otherwise the overworld types use
+    /// the configured level type and the others a flat default
+    pub flat_preset: Option<String>,
+}
+
+impl WorldSettings {
+    /// A vanilla dimension in its vanilla folder
+    pub fn vanilla(kind: DimensionType) -> Self {
+        Self {
+            name: kind.name().into(),
+            kind,
+            region_folder: kind.region_folder().into(),
+            flat_preset: None,
+        }
+    }
+}
+
+/// Component with the dimension an entity is in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DimensionId(pub usize);
+
+/// A world with its own chunks and entities
+pub struct Dimension {
+    pub name: String,
+    pub kind: DimensionType,
+    pub is_flat: bool,
+    pub chunks: ChunkWorld,
+    pub tracker: EntityTracker,
+    pub border: WorldBorder,
+}
+
+impl Dimension {
+    pub fn new(settings: &WorldSettings, generator: Box<dyn ChunkLoader>, is_flat: bool,
+        border: WorldBorder) -> Self 
+    {
+        Self {
+            name: settings.name.clone(),
+            kind: settings.kind,
+            is_flat,
+            chunks: chunks::new_world(&settings.region_folder, generator),
+            tracker: EntityTracker::new(),
+            border,
+        }
+    }
+}
+
+pub struct Dimensions {
+    list: Vec<Dimension>,
+}
+
+impl Dimensions {
+    pub fn get(&self, id: DimensionId) -> &Dimension {
+        &self.list[id.0]
+    }
+
+    pub fn get_mut(&mut self, id: DimensionId) -> &mut Dimension {
+        &mut self.list[id.0]
+    }
+
+    /// Where new players spawn, the first world in the config
+    pub fn overworld(&self) -> DimensionId {
+        DimensionId(0)
+    }
+
+    pub fn find(&self, name: &str) -> Option<DimensionId> {
+        self.list.iter()
+            .position(|dimension| dimension.name == name)
+            .map(DimensionId)
+    }
+
+    pub fn find_kind(&self, kind: DimensionType) -> Option<DimensionId> {
+        self.list.iter()
+            .position(|dimension| dimension.kind == kind)
+            .map(DimensionId)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dimension> {
+        self.list.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Dimension> {
+        self.list.iter_mut()
+    }
+
+    /// The spawn world's border goes where vanilla looks for the overworld's
+    pub fn store_borders(&self, level: &mut LevelData) {
+        for (index, dimension) in self.list.iter().enumerate() {
+            let settings = dimension.border.settings();
+            if index == self.overworld().0 {
+                level.border = Some(LevelBorder::from(&settings));
+                level.world_borders.remove(&dimension.name);
+            } else {
+                level.world_borders.insert(dimension.name.clone(), settings);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use legion::*;
+use legion::systems::CommandBuffer;
+use nalgebra::{Vector3, vector};
+use uuid::Uuid;
+use world::SubWorld;
+
+use crate::dimensions::{DimensionId, Dimensions};
+use crate::items::ItemStack;
+use super::{EntityId, EntityIdGenerator, Position, Rotation};
+
+/// Ticks before an item on the ground disappears, 5 minutes like vanilla
+const DESPAWN_AGE: u32 = 6000;
+/// Ticks before a dropped item can be picked up
+const PICKUP_DELAY: u32 = 10;
+/// Longer delay for thrown items so they don't come right back
+const THROWN_PICKUP_DELAY: u32 = 40;
+
+/// Item lying on the ground
+#[derive(Clone, Debug)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    pub age: u32,
+    pub pickup_delay: u32,
+}
+
+/// Drops an item in the world, it shows up for players after the commands run
+pub fn spawn_item(cmd: &mut CommandBuffer, ids: &EntityIdGenerator, dimension: DimensionId,
+    pos: Vector3<f64>, stack: ItemStack)
+{
+    spawn(cmd, ids, dimension, pos, stack, PICKUP_DELAY);
+}
+
+fn spawn(cmd: &mut CommandBuffer, ids: &EntityIdGenerator, dimension: DimensionId,
+    pos: Vector3<f64>, stack: ItemStack, pickup_delay: u32)
+{
+    let id = ids.get_new();
+    let entity = cmd.push((
+        id,
+        Uuid::new_v4(),
+        Position(pos),
+        dimension,
+        DroppedItem { stack, age: 0, pickup_delay },
+    ));
+    cmd.exec_mut(move |_, resources| {
+        let dimensions = resources.get::<Dimensions>().unwrap();
+        dimensions.get(dimension).tracker.add(id.0, entity, &pos);
+    });
+}
+
+/// Drops an item a player threw, a bit in front of them since items
+/// don't fly yet
+pub fn throw_item(cmd: &mut CommandBuffer, ids: &EntityIdGenerator, dimension: DimensionId,
+    position: &Position, rotation: &Rotation, stack: ItemStack)
+{
+    let yaw = rotation.0.to_radians() as f64;
+    let pos = position.0 + vector!(-yaw.sin(), 0.25, yaw.cos());
+    spawn(cmd, ids, dimension, pos, stack, THROWN_PICKUP_DELAY);
+}
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(Position)]
+#[read_component(DimensionId)]
+#[write_component(DroppedItem)]
+pub fn despawn_items(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] dimensions: &Dimensions)
+{
+    let mut query = <(Entity, &EntityId, &Position, &DimensionId, &mut DroppedItem)>::query();
+    for (entity, id, pos, dimension, item) in query.iter_mut(world) {
+        item.age += 1;
+        item.pickup_delay = item.pickup_delay.saturating_sub(1);
+        if item.age >= DESPAWN_AGE {
+            dimensions.get(*dimension).tracker.remove(id.0, &pos.0);
+            cmd.remove(*entity);
+        }
+    }
+}
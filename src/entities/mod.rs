@@ -1,12 +1,16 @@
 mod components;
 mod entity_id;
+mod items;
 
 use legion::*;
 use systems::Builder;
 
 pub use components::{Position, Rotation, Name};
 pub use entity_id::{EntityId, EntityIdGenerator};
+pub use items::{DroppedItem, spawn_item, throw_item};
+use items::despawn_items_system;
 
 pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+    schedule.add_system(despawn_items_system());
     resources.insert(EntityIdGenerator::new());
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use nbt::Value as Nbt;
+use serde::{Serialize, Deserialize};
+
+use super::serialization::ItemStackPlayerData;
+use crate::items::{ItemStack, ItemType};
+
+pub const ENDER_CHEST_SLOTS: usize = 27;
+
+/// Storage of each player, the same behind every ender chest
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "Vec<ItemStackPlayerData>")]
+#[serde(into = "Vec<ItemStackPlayerData>")]
+pub struct EnderChest {
+    pub items: Vec<Option<ItemStack>>,
+}
+
+impl Default for EnderChest {
+    fn default() -> Self {
+        Self {
+            items: vec![None; ENDER_CHEST_SLOTS],
+        }
+    }
+}
+
+impl EnderChest {
+    /// Item tags by slot, see Inventory::item_tags
+    pub fn item_tags(&self) -> HashMap<i8, Nbt> {
+        self.items.iter().enumerate()
+            .filter_map(|(slot, stack)| Some((slot as i8, stack.as_ref()?.nbt.clone()?)))
+            .collect()
+    }
+
+    pub fn set_item_tags(&mut self, tags: HashMap<i8, Nbt>) {
+        for (slot, tag) in tags {
+            if let Some(Some(stack)) = self.items.get_mut(slot as usize) {
+                stack.nbt = Some(tag);
+            }
+        }
+    }
+}
+
+impl TryFrom<Vec<ItemStackPlayerData>> for EnderChest {
+    type Error = anyhow::Error;
+
+    fn try_from(items: Vec<ItemStackPlayerData>) -> Result<Self, Self::Error> {
+        let mut chest = Self::default();
+        for stack in items {
+            let slot = stack.slot as usize;
+            if slot < ENDER_CHEST_SLOTS {
+                chest.items[slot] = Some(ItemStack {
+                    item: ItemType::from_str(stack.item.as_str())?,
+                    count: stack.count as u8,
+                    nbt: None,
+                });
+            }
+        }
+        Ok(chest)
+    }
+}
+
+impl From<EnderChest> for Vec<ItemStackPlayerData> {
+    fn from(chest: EnderChest) -> Self {
+        chest.items.into_iter().enumerate()
+            .filter_map(|(slot, stack)| {
+                let stack = stack?;
+                Some(ItemStackPlayerData {
+                    slot: slot as i8,
+                    item: stack.item.to_str().into(),
+                    count: stack.count as i8,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,9 +1,19 @@
 mod serialization;
+mod ender_chest;
 
 use crate::items::ItemStack;
+use crate::recipes::find_crafting;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use serialization::ItemStackPlayerData;
+pub use serialization::{read_item_tags, write_item_tags};
+pub use ender_chest::EnderChest;
+
+/// Main inventory and hotbar, the part that shows up in every window
+pub const STORAGE_SLOTS: usize = 36;
+/// Slots of the inventory window, from the crafting result to the offhand
+pub const WINDOW_SLOTS: usize = 46;
+const CRAFTING_GRID: usize = 4;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(try_from = "Vec<ItemStackPlayerData>")]
@@ -11,6 +21,10 @@ use serialization::ItemStackPlayerData;
 pub struct Inventory {
     held_slot: SlotIndex,
     slots: HashMap<SlotIndex, ItemStack>,
+    /// Stack being moved around in a window, not saved
+    pub cursor: Option<ItemStack>,
+    /// 2x2 grid of the inventory window, emptied when it's closed
+    crafting: Vec<Option<ItemStack>>,
 }
 
 impl Inventory {
@@ -18,6 +32,8 @@ impl Inventory {
         Self {
             held_slot: SlotIndex::from_hotbar(0),
             slots: HashMap::new(),
+            cursor: None,
+            crafting: vec![None; CRAFTING_GRID],
         }
     }
 
@@ -31,32 +47,136 @@ impl Inventory {
         }
     }
 
+    pub fn get_slot(&self, index: SlotIndex) -> Option<&ItemStack> {
+        self.slots.get(&index)
+    }
+
     pub fn set_held_slot(&mut self, slot: SlotIndex) {
         self.held_slot = slot;
     }
 
+    /// Hotbar slot in hand, 0 to 8
+    pub fn held_hotbar_slot(&self) -> u8 {
+        self.held_slot.0
+    }
+
     pub fn get_held(&self) -> Option<&ItemStack> {
         self.slots.get(&self.held_slot)
     }
 
+    /// Takes up to count of the held items, like when placing or dropping them
+    pub fn take_held(&mut self, count: u8) -> Option<ItemStack> {
+        let stack = self.slots.get_mut(&self.held_slot)?;
+        let taken = ItemStack { count: count.min(stack.count), ..stack.clone() };
+        stack.count -= taken.count;
+        if stack.count == 0 {
+            self.slots.remove(&self.held_slot);
+        }
+        Some(taken)
+    }
+
+    /// Main inventory and then hotbar, in the order they are at the 
+    /// bottom of container windows
+    pub fn get_storage(&self) -> Vec<Option<ItemStack>> {
+        storage_indices()
+            .map(|index| self.slots.get(&index).cloned())
+            .collect()
+    }
+
+    pub fn set_storage(&mut self, items: Vec<Option<ItemStack>>) {
+        for (index, stack) in storage_indices().zip(items) {
+            self.set_slot(index, stack);
+        }
+    }
+
+    /// Puts a stack wherever it fits, returns what didn't
+    pub fn add_stack(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+        let indices: Vec<SlotIndex> = (0..STORAGE_SLOTS as u8).map(SlotIndex).collect();
+        for index in &indices {
+            if let Some(current) = self.slots.get_mut(index) {
+                if current.is_same_kind(&stack) {
+                    let moved = stack.count.min(current.max_stack_size().saturating_sub(current.count));
+                    current.count += moved;
+                    stack.count -= moved;
+                    if stack.count == 0 {
+                        return None;
+                    }
+                }
+            }
+        }
+        for index in indices {
+            if !self.slots.contains_key(&index) {
+                self.slots.insert(index, stack);
+                return None;
+            }
+        }
+        Some(stack)
+    }
+
     pub fn get_window(&self) -> Vec<Option<ItemStack>> {
-        let mut window = vec![None; 46];
+        let mut window = vec![None; WINDOW_SLOTS];
         for (slot, item) in &self.slots {
-            window[slot.to_network() as usize] = Some(item.clone());
+            if let Some(index) = slot.to_network() {
+                window[index as usize] = Some(item.clone());
+            }
         }
+        window[1..=CRAFTING_GRID].clone_from_slice(&self.crafting);
+        window[0] = find_crafting(&self.crafting, 2)
+            .map(|recipe| recipe.result().clone());
         window
     }
+
+    /// Takes back the slots of the inventory window after a click
+    pub fn set_window(&mut self, window: Vec<Option<ItemStack>>) {
+        for (index, stack) in window.into_iter().enumerate() {
+            match SlotIndex::from_network(index as u8) {
+                Some(slot) => self.set_slot(slot, stack),
+                None if (1..=CRAFTING_GRID).contains(&index) => {
+                    self.crafting[index - 1] = stack;
+                },
+                None => (),
+            }
+        }
+    }
+
+    /// What was left in the crafting grid or the cursor when the
+    /// inventory is closed goes back into it, and what doesn't fit is returned
+    pub fn clear_crafting(&mut self) -> Vec<ItemStack> {
+        let leftover: Vec<ItemStack> = self.crafting.iter_mut()
+            .chain(std::iter::once(&mut self.cursor))
+            .filter_map(|slot| slot.take())
+            .collect();
+        leftover.into_iter()
+            .filter_map(|stack| self.add_stack(stack))
+            .collect()
+    }
 }
 
+fn storage_indices() -> impl Iterator<Item = SlotIndex> {
+    (9..STORAGE_SLOTS as u8).chain(0..9).map(SlotIndex)
+}
+
+/// Slot as saved in player data, 0 to 8 is the hotbar, 9 to 35 the main 
+/// inventory, 100 to 103 armor from the feet up and -106 the offhand
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct SlotIndex(u8);
 
 impl SlotIndex {
-    pub fn from_network(index: u8) -> Self {
-        if index >= 36 && index <= 44 {
-            Self(index - 36) 
-        } else {
-            Self(index)
+    pub const OFFHAND: Self = Self(-106i8 as u8);
+
+    /// Armor slot, 0 for the head to 3 for the feet
+    pub fn armor(piece: u8) -> Self {
+        Self(103 - piece)
+    }
+
+    /// Slot of the inventory window, None for the crafting slots
+    pub fn from_network(index: u8) -> Option<Self> {
+        match index {
+            5..=8 => Some(Self::armor(index - 5)),
+            9..=35 => Some(Self(index)),
+            36..=44 => Some(Self(index - 36)),
+            45 => Some(Self::OFFHAND),
+            _ => None,
         }
     }
 
@@ -72,11 +192,13 @@ impl SlotIndex {
         self.0 as i8
     }
 
-    pub fn to_network(&self) -> u16 {
-        if self.0 < 9 {
-            (self.0 + 36) as u16
-        } else {
-            self.0 as u16
+    pub fn to_network(&self) -> Option<u16> {
+        match self.0 {
+            0..=8 => Some(self.0 as u16 + 36),
+            9..=35 => Some(self.0 as u16),
+            100..=103 => Some(8 - (self.0 as u16 - 100)),
+            _ if *self == Self::OFFHAND => Some(45),
+            _ => None,
         }
     }
 }
@@ -1,8 +1,11 @@
 use std::convert::TryFrom;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
+use anvil_nbt::CompoundTag;
+use nbt::Value as Nbt;
 use super::{Inventory, ItemStack, SlotIndex};
 use crate::items::ItemType;
+use crate::serialization::{compound_to_value, value_to_compound};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all="PascalCase")] 
@@ -31,8 +34,8 @@ impl TryFrom<Vec<ItemStackPlayerData>> for Inventory {
                 });
         }
         Ok(Self {
-            held_slot: SlotIndex::from_hotbar(0),
             slots,
+            ..Self::new()
         })
     }
 }
@@ -48,3 +51,72 @@ impl From<Inventory> for Vec<ItemStackPlayerData> {
         ).collect()
     }
 }
+
+/// Serde changes the types of NBT values, so item tags are left out of
+/// it and moved with anvil_nbt by slot instead
+impl Inventory {
+    pub fn item_tags(&self) -> HashMap<i8, Nbt> {
+        self.slots.iter()
+            .filter_map(|(slot, stack)| Some((slot.to_file(), stack.nbt.clone()?)))
+            .collect()
+    }
+
+    pub fn set_item_tags(&mut self, tags: HashMap<i8, Nbt>) {
+        for (slot, tag) in tags {
+            if let Some(stack) = self.slots.get_mut(&SlotIndex::from_file(slot)) {
+                stack.nbt = Some(tag);
+            }
+        }
+    }
+}
+
+/// Tags of the items in a list like Inventory, by slot
+pub fn read_item_tags(compound: &CompoundTag, list: &str) -> HashMap<i8, Nbt> {
+    compound.get_compound_tag_vec(list).unwrap_or_default().into_iter()
+        .filter_map(|item| {
+            let slot = item.get_i8("Slot").ok()?;
+            let tag = item.get_compound_tag("tag").ok()?;
+            Some((slot, compound_to_value(tag)))
+        })
+        .collect()
+}
+
+pub fn write_item_tags(compound: &mut CompoundTag, list: &str, tags: &HashMap<i8, Nbt>) {
+    let items = match compound.get_compound_tag_vec(list) {
+        Ok(items) => items.into_iter().cloned().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    let items = items.into_iter()
+        .map(|mut item| {
+            let tag = item.get_i8("Slot").ok()
+                .and_then(|slot| tags.get(&slot))
+                .and_then(value_to_compound);
+            if let Some(tag) = tag {
+                item.insert_compound_tag("tag", tag);
+            }
+            item
+        })
+        .collect();
+    compound.insert_compound_tag_vec(list, items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_tags_keep_types() {
+        let mut tag = nbt::Map::new();
+        tag.insert("Damage".into(), Nbt::Int(3));
+        tag.insert("Id".into(), Nbt::IntArray(vec![1, -300, 70000, 2]));
+        tag.insert("Lore".into(), Nbt::List(vec![]));
+        let tags = vec![(4, Nbt::Compound(tag.clone()))].into_iter().collect();
+        let mut item = CompoundTag::new();
+        item.insert_i8("Slot", 4);
+        let mut compound = CompoundTag::new();
+        compound.insert_compound_tag_vec("Inventory", vec![item]);
+        write_item_tags(&mut compound, "Inventory", &tags);
+        let back = read_item_tags(&compound, "Inventory");
+        assert_eq!(back.get(&4), Some(&Nbt::Compound(tag)));
+    }
+}
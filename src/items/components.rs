@@ -0,0 +1,211 @@
+use nbt::{Map, Value as Nbt};
+use super::ItemStack;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enchantment {
+    pub id: String,
+    pub level: i16,
+}
+
+/// Typed access to the common parts of an item's `tag`
+impl ItemStack {
+    fn tag(&self) -> Option<&Map<String, Nbt>> {
+        match &self.nbt {
+            Some(Nbt::Compound(tag)) => Some(tag),
+            _ => None,
+        }
+    }
+
+    fn tag_mut(&mut self) -> &mut Map<String, Nbt> {
+        if !matches!(self.nbt, Some(Nbt::Compound(_))) {
+            self.nbt = Some(Nbt::Compound(Map::new()));
+        }
+        match &mut self.nbt {
+            Some(Nbt::Compound(tag)) => tag,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets or removes a key, dropping the tag once it's empty so the
+    /// stack merges with plain ones again
+    fn set_tag_value(&mut self, key: &str, value: Option<Nbt>) {
+        match value {
+            Some(value) => {
+                self.tag_mut().insert(key.into(), value);
+            }
+            None => {
+                self.tag_mut().remove(key);
+                if self.tag().map_or(false, Map::is_empty) {
+                    self.nbt = None;
+                }
+            }
+        }
+    }
+
+    fn display(&self) -> Option<&Map<String, Nbt>> {
+        match self.tag()?.get("display")? {
+            Nbt::Compound(display) => Some(display),
+            _ => None,
+        }
+    }
+
+    fn set_display_value(&mut self, key: &str, value: Option<Nbt>) {
+        let mut display = self.display().cloned().unwrap_or_default();
+        match value {
+            Some(value) => display.insert(key.into(), value),
+            None => display.remove(key),
+        };
+        let display = (!display.is_empty()).then(|| Nbt::Compound(display));
+        self.set_tag_value("display", display);
+    }
+
+    /// Custom name as a JSON text component
+    pub fn display_name(&self) -> Option<&str> {
+        match self.display()?.get("Name")? {
+            Nbt::String(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn set_display_name(&mut self, name: Option<String>) {
+        self.set_display_value("Name", name.map(Nbt::String));
+    }
+
+    /// Lore lines as JSON text components
+    pub fn lore(&self) -> Vec<&str> {
+        match self.display().and_then(|display| display.get("Lore")) {
+            Some(Nbt::List(lines)) => lines.iter()
+                .filter_map(|line| match line {
+                    Nbt::String(line) => Some(line.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn set_lore(&mut self, lines: Vec<String>) {
+        let lore = (!lines.is_empty())
+            .then(|| Nbt::List(lines.into_iter().map(Nbt::String).collect()));
+        self.set_display_value("Lore", lore);
+    }
+
+    /// Enchantments on tools and armor, or the stored ones on books
+    pub fn enchantments(&self) -> Vec<Enchantment> {
+        let list = self.tag().and_then(|tag| {
+            tag.get("Enchantments").or_else(|| tag.get("StoredEnchantments"))
+        });
+        match list {
+            Some(Nbt::List(list)) => list.iter()
+                .filter_map(|enchantment| match enchantment {
+                    Nbt::Compound(enchantment) => Some(Enchantment {
+                        id: match enchantment.get("id")? {
+                            Nbt::String(id) => id.clone(),
+                            _ => return None,
+                        },
+                        level: match enchantment.get("lvl")? {
+                            Nbt::Short(level) => *level,
+                            _ => return None,
+                        },
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Level of one enchantment, 0 if the item doesn't have it
+    pub fn enchantment_level(&self, id: &str) -> i16 {
+        self.enchantments().iter()
+            .find(|enchantment| enchantment.id == id)
+            .map_or(0, |enchantment| enchantment.level)
+    }
+
+    pub fn set_enchantments(&mut self, enchantments: Vec<Enchantment>) {
+        let list = (!enchantments.is_empty()).then(|| Nbt::List(
+            enchantments.into_iter().map(|enchantment| {
+                let mut tag = Map::new();
+                tag.insert("id".into(), Nbt::String(enchantment.id));
+                tag.insert("lvl".into(), Nbt::Short(enchantment.level));
+                Nbt::Compound(tag)
+            }).collect()
+        ));
+        self.set_tag_value("Enchantments", list);
+    }
+
+    /// Durability used up so far
+    pub fn damage(&self) -> i32 {
+        match self.tag().and_then(|tag| tag.get("Damage")) {
+            Some(Nbt::Int(damage)) => *damage,
+            _ => 0,
+        }
+    }
+
+    pub fn set_damage(&mut self, damage: i32) {
+        self.set_tag_value("Damage", (damage != 0).then(|| Nbt::Int(damage)));
+    }
+
+    /// Block entity data applied when the item is placed
+    pub fn block_entity_tag(&self) -> Option<&Nbt> {
+        self.tag()?.get("BlockEntityTag")
+    }
+
+    pub fn set_block_entity_tag(&mut self, tag: Option<Nbt>) {
+        self.set_tag_value("BlockEntityTag", tag);
+    }
+
+    pub fn custom_model_data(&self) -> Option<i32> {
+        match self.tag()?.get("CustomModelData")? {
+            Nbt::Int(data) => Some(*data),
+            _ => None,
+        }
+    }
+
+    pub fn set_custom_model_data(&mut self, data: Option<i32>) {
+        self.set_tag_value("CustomModelData", data.map(Nbt::Int));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::items::ItemType;
+    use super::*;
+
+    fn stack() -> ItemStack {
+        ItemStack {
+            item: ItemType::from_str("minecraft:diamond_sword").unwrap(),
+            count: 1,
+            nbt: None,
+        }
+    }
+
+    #[test]
+    fn display_and_enchantments() {
+        let mut sword = stack();
+        sword.set_display_name(Some(r#"{"text":"Sting"}"#.into()));
+        sword.set_lore(vec![r#"{"text":"Glows blue"}"#.into()]);
+        sword.set_enchantments(vec![Enchantment {
+            id: "minecraft:sharpness".into(),
+            level: 5,
+        }]);
+        assert_eq!(sword.display_name(), Some(r#"{"text":"Sting"}"#));
+        assert_eq!(sword.lore(), vec![r#"{"text":"Glows blue"}"#]);
+        assert_eq!(sword.enchantments()[0].level, 5);
+
+        sword.set_display_name(None);
+        sword.set_lore(Vec::new());
+        sword.set_enchantments(Vec::new());
+        assert!(sword.nbt.is_none());
+    }
+
+    #[test]
+    fn damage_merges_when_repaired() {
+        let mut sword = stack();
+        sword.set_damage(12);
+        assert_eq!(sword.damage(), 12);
+        assert!(!sword.is_same_kind(&stack()));
+        sword.set_damage(0);
+        assert!(sword.is_same_kind(&stack()));
+    }
+}
@@ -19,6 +19,8 @@ lazy_static! {
 pub struct ItemType {
     id: u16,
     block: Option<u16>,
+    max_stack_size: u8,
+    equipment_slot: Option<String>,
 }
 
 impl ItemType {
@@ -41,6 +43,29 @@ impl ItemType {
         self.id
     }
 
+    pub fn max_stack_size(&self) -> u8 {
+        self.max_stack_size
+    }
+
+    /// Where the item can be worn, head, chest, legs, feet or offhand
+    pub fn equipment_slot(&self) -> Option<&str> {
+        if let Some(slot) = &self.equipment_slot {
+            return Some(slot);
+        }
+        let name = self.to_str();
+        if name == "minecraft:elytra" {
+            Some("chest")
+        } else if name == "minecraft:shield" {
+            Some("offhand")
+        } else if name == "minecraft:carved_pumpkin" || name.ends_with("_head") 
+            || name.ends_with("_skull") 
+        {
+            Some("head")
+        } else {
+            None
+        }
+    }
+
     pub fn get_block(&self) -> Option<&BlockType> {
         self.block.map(|block| BlockType::from_id(block).unwrap())
     }
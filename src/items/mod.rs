@@ -1,7 +1,10 @@
 mod item_type;
+mod components;
+mod tools;
 
 use nbt::Value as Nbt;
 pub use item_type::ItemType;
+pub use tools::ToolKind;
 
 #[derive(Clone, Debug)]
 pub struct ItemStack {
@@ -9,3 +12,14 @@ pub struct ItemStack {
     pub count: u8,
     pub nbt: Option<Nbt>,
 }
+
+impl ItemStack {
+    /// Same item and data, so they can be merged into one stack
+    pub fn is_same_kind(&self, other: &ItemStack) -> bool {
+        self.item.to_numeric() == other.item.to_numeric() && self.nbt == other.nbt
+    }
+
+    pub fn max_stack_size(&self) -> u8 {
+        self.item.max_stack_size()
+    }
+}
@@ -0,0 +1,71 @@
+use serde::Deserialize;
+
+use super::ItemType;
+
+/// What a tool is good at, blocks name the one that digs them faster
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum ToolKind {
+    Pickaxe,
+    Axe,
+    Shovel,
+    Hoe,
+    Sword,
+    Shears,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tool {
+    pub kind: ToolKind,
+    /// Harvest level, 0 for wood and gold up to 4 for netherite
+    pub level: u8,
+    /// Dig speed on the blocks the tool is made for
+    pub speed: f32,
+}
+
+impl ItemType {
+    pub fn tool(&self) -> Option<Tool> {
+        let name = self.to_str().strip_prefix("minecraft:")?;
+        if name == "shears" {
+            return Some(Tool { kind: ToolKind::Shears, level: 0, speed: 1.0 });
+        }
+        let (tier, kind) = name.split_at(name.find('_')?);
+        let kind = match kind {
+            "_pickaxe" => ToolKind::Pickaxe,
+            "_axe" => ToolKind::Axe,
+            "_shovel" => ToolKind::Shovel,
+            "_hoe" => ToolKind::Hoe,
+            "_sword" => ToolKind::Sword,
+            _ => return None,
+        };
+        let (level, speed) = match tier {
+            "wooden" => (0, 2.0),
+            "stone" => (1, 4.0),
+            "iron" => (2, 6.0),
+            "diamond" => (3, 8.0),
+            "netherite" => (4, 9.0),
+            "golden" => (0, 12.0),
+            _ => return None,
+        };
+        Some(Tool { kind, level, speed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str) -> Option<Tool> {
+        ItemType::from_str(name).unwrap().tool()
+    }
+
+    #[test]
+    fn tiers_and_kinds() {
+        let pickaxe = tool("minecraft:diamond_pickaxe").unwrap();
+        assert_eq!((pickaxe.kind, pickaxe.level), (ToolKind::Pickaxe, 3));
+        assert_eq!(tool("minecraft:golden_axe").unwrap().speed, 12.0);
+        assert_eq!(tool("minecraft:shears").unwrap().kind, ToolKind::Shears);
+        assert_eq!(tool("minecraft:iron_bars"), None);
+        assert_eq!(tool("minecraft:stick"), None);
+    }
+}
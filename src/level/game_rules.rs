@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    ("doDaylightCycle", "true"),
+    ("doWeatherCycle", "true"),
+    ("randomTickSpeed", "3"),
+    ("spawnRadius", "10"),
+    ("keepInventory", "false"),
+    ("doTileDrops", "true"),
+];
+
+/// Game rules are stored as strings in level.dat, even the numeric ones
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct GameRules(HashMap<String, String>);
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self(DEFAULT_RULES.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect())
+    }
+}
+
+impl GameRules {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name)
+            .map(|value| value.as_str())
+            .or_else(|| default_value(name))
+    }
+
+    pub fn get_bool(&self, name: &str) -> bool {
+        self.get(name) == Some("true")
+    }
+
+    pub fn get_int(&self, name: &str) -> i32 {
+        self.get(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn set(&mut self, name: &str, value: impl ToString) {
+        self.0.insert(name.into(), value.to_string());
+    }
+}
+
+fn default_value(name: &str) -> Option<&'static str> {
+    DEFAULT_RULES.iter()
+        .find(|(rule, _)| *rule == name)
+        .map(|(_, value)| *value)
+}
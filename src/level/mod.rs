@@ -0,0 +1,260 @@
+mod game_rules;
+mod time;
+mod weather;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind::NotFound;
+use std::io::Write;
+use std::path::Path;
+
+use anvil_nbt::{CompoundTag, decode, encode};
+use anyhow::{Result, anyhow};
+use legion::*;
+use nalgebra::{Vector3, vector};
+use serde::{Deserialize, Serialize};
+use systems::Builder;
+
+use crate::autosave::Autosave;
+use crate::chunks::World as ChunkWorld;
+use crate::dimensions::{BorderSettings, Dimensions, LevelBorder};
+use crate::players::GameMode;
+use crate::serialization::overlay_compound;
+use crate::util::{read_file, write_file};
+
+pub use game_rules::GameRules;
+pub use time::WorldTime;
+pub use weather::Weather;
+use time::{tick_time_system, broadcast_time_system};
+use weather::{tick_weather_system, broadcast_weather_system};
+
+const LEVEL_PATH: &str = "world/level.dat";
+const DEFAULT_LEVEL_NAME: &str = "world";
+/// Used when no column close to the origin has dry ground
+const DEFAULT_SPAWN: Vector3<i32> = vector!(0, 3, 0);
+/// How far from the origin new worlds look for a spawn, in blocks
+const SPAWN_SEARCH_RADIUS: i32 = 256;
+/// Distance between the columns checked while looking for a spawn
+const SPAWN_SEARCH_STEP: i32 = 16;
+const NBT_VERSION: i32 = 19133;
+const DATA_VERSION: i32 = 2586;
+
+#[system]
+fn autosave_level(#[resource] level: &mut LevelData, #[resource] time: &WorldTime,
+    #[resource] weather: &Weather, #[resource] autosave: &Autosave,
+    #[resource] dimensions: &Dimensions) 
+{
+    if autosave.is_saving() {
+        time.store(level);
+        weather.store(level);
+        dimensions.store_borders(level);
+        let level = level.clone();
+        tokio::spawn(async move {
+            level.save().await
+                .unwrap_or_else(|err| {
+                    eprintln!("Error saving level.dat: {}", err);
+                });
+        });
+    }
+}
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources, level: LevelData) {
+    schedule
+        .add_system(tick_time_system())
+        .add_system(broadcast_time_system())
+        .add_system(tick_weather_system())
+        .add_system(broadcast_weather_system())
+        .add_thread_local(autosave_level_system());
+    resources.insert(WorldTime::from_level(&level));
+    resources.insert(Weather::from_level(&level));
+    resources.insert(level);
+}
+
+pub async fn on_stop(resources: &mut Resources) {
+    print!("Saving level...          ");
+    let _ = std::io::stdout().flush();
+    let mut level = resources.get::<LevelData>().unwrap().clone();
+    resources.get::<WorldTime>().unwrap().store(&mut level);
+    resources.get::<Weather>().unwrap().store(&mut level);
+    resources.get::<Dimensions>().unwrap().store_borders(&mut level);
+    level.save().await
+        .unwrap_or_else(|err| {
+            eprintln!("Error saving level.dat: {}", err);
+        });
+    println!("Done");
+}
+
+/// Puts the spawn of a new world on the ground the overworld generator
+/// makes, needs the dimensions to be registered
+pub async fn place_spawn(resources: &Resources) -> Result<()> {
+    let level = {
+        let mut level = resources.get_mut::<LevelData>().unwrap();
+        if !level.spawn_pending {
+            return Ok(());
+        }
+        let dimensions = resources.get::<Dimensions>().unwrap();
+        let chunks = &dimensions.get(dimensions.overworld()).chunks;
+        if let Some(spawn) = find_spawn(chunks) {
+            level.spawn_x = spawn.x;
+            level.spawn_y = spawn.y;
+            level.spawn_z = spawn.z;
+        }
+        level.spawn_pending = false;
+        level.clone()
+    };
+    level.save().await
+}
+
+/// Closest checked column to the origin that has dry ground
+fn find_spawn(chunks: &ChunkWorld) -> Option<Vector3<i32>> {
+    (0..=SPAWN_SEARCH_RADIUS / SPAWN_SEARCH_STEP)
+        .flat_map(|ring| (-ring..=ring)
+            .flat_map(move |dx| (-ring..=ring).map(move |dz| (dx, dz)))
+            .filter(move |(dx, dz)| dx.abs().max(dz.abs()) == ring))
+        .map(|(dx, dz)| (dx * SPAWN_SEARCH_STEP, dz * SPAWN_SEARCH_STEP))
+        .find_map(|(x, z)| chunks.surface_height(x, z).map(|y| vector!(x, y, z)))
+}
+
+#[derive(Serialize, Deserialize)]
+struct LevelFile {
+    #[serde(rename="Data")]
+    data: LevelData,
+}
+
+/// World metadata stored in level.dat, in the same format vanilla uses
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all="PascalCase")]
+pub struct LevelData {
+    pub level_name: String,
+    pub spawn_x: i32,
+    pub spawn_y: i32,
+    pub spawn_z: i32,
+    pub time: i64,
+    pub day_time: i64,
+    #[serde(rename="raining", default)]
+    pub raining: bool,
+    #[serde(rename="rainTime", default)]
+    pub rain_time: i32,
+    #[serde(rename="thundering", default)]
+    pub thundering: bool,
+    #[serde(rename="thunderTime", default)]
+    pub thunder_time: i32,
+    #[serde(rename="clearWeatherTime", default)]
+    pub clear_weather_time: i32,
+    #[serde(default)]
+    pub world_gen_settings: WorldGenSettings,
+    #[serde(default)]
+    pub game_rules: GameRules,
+    /// Overworld border, missing in worlds that never had one saved
+    #[serde(flatten)]
+    pub border: Option<LevelBorder>,
+    /// Border of the other dimensions, by dimension name
+    #[serde(default)]
+    pub world_borders: HashMap<String, BorderSettings>,
+    /// Game mode of players joining for the first time
+    #[serde(default)]
+    pub game_type: GameMode,
+    #[serde(rename="version", default="nbt_version")]
+    version: i32,
+    #[serde(default="data_version")]
+    data_version: i32,
+    /// Set for new worlds until the spawn is moved to the surface
+    #[serde(skip)]
+    spawn_pending: bool,
+    /// Data tag as it was read, so saving keeps the fields not listed here
+    #[serde(skip)]
+    original: Option<CompoundTag>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WorldGenSettings {
+    pub seed: i64,
+}
+
+impl LevelData {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            level_name: DEFAULT_LEVEL_NAME.into(),
+            spawn_x: DEFAULT_SPAWN.x,
+            spawn_y: DEFAULT_SPAWN.y,
+            spawn_z: DEFAULT_SPAWN.z,
+            time: 0,
+            day_time: 0,
+            raining: false,
+            rain_time: 0,
+            thundering: false,
+            thunder_time: 0,
+            clear_weather_time: 0,
+            world_gen_settings: WorldGenSettings { seed },
+            game_rules: GameRules::default(),
+            border: None,
+            world_borders: HashMap::new(),
+            game_type: GameMode::default(),
+            version: NBT_VERSION,
+            data_version: DATA_VERSION,
+            spawn_pending: true,
+            original: None,
+        }
+    }
+
+    /// Reads world/level.dat, creating it if it doesn't exist
+    pub async fn load() -> Result<Self> {
+        match read_file(LEVEL_PATH).await {
+            Ok(file) => {
+                let level: LevelFile = nbt::from_gzip_reader(file.as_slice())?;
+                let root = decode::read_gzip_compound_tag(&mut file.as_slice())
+                    .map_err(|err| anyhow!("Invalid level data: {:?}", err))?;
+                let original = root.get_compound_tag("Data").ok().cloned();
+                Ok(Self { original, ..level.data })
+            },
+            Err(e) if is_not_found(&e) => {
+                let level = Self::new(rand::random());
+                level.save().await?;
+                Ok(level)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let mut raw = vec![];
+        let file = LevelFile {
+            data: self.clone(),
+        };
+        nbt::to_writer(&mut raw, &file, None)?;
+        let root = decode::read_compound_tag(&mut raw.as_slice())
+            .map_err(|err| anyhow!("Invalid level data: {:?}", err))?;
+        let ours = root.get_compound_tag("Data")
+            .map_err(|err| anyhow!("Invalid level data: {:?}", err))?.clone();
+        let base = self.original.clone().unwrap_or_else(CompoundTag::new);
+        let mut root = CompoundTag::new();
+        root.insert_compound_tag("Data", overlay_compound(base, ours));
+        let mut data = vec![];
+        encode::write_gzip_compound_tag(&mut data, root)?;
+        fs::create_dir_all(Path::new(LEVEL_PATH).parent().unwrap())?;
+        write_file(LEVEL_PATH, data.as_slice()).await?;
+        Ok(())
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.world_gen_settings.seed
+    }
+
+    /// Position new players appear at, centered on the spawn block
+    pub fn spawn_position(&self) -> Vector3<f64> {
+        vector!(
+            self.spawn_x as f64 + 0.5,
+            self.spawn_y as f64,
+            self.spawn_z as f64 + 0.5)
+    }
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<std::io::Error>()
+        .map(|e| e.kind() == NotFound)
+        .unwrap_or(false)
+}
+
+const fn nbt_version() -> i32 { NBT_VERSION }
+
+const fn data_version() -> i32 { DATA_VERSION }
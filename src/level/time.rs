@@ -0,0 +1,93 @@
+use legion::*;
+
+use crate::net::{ClientboundPacket, PlayerConnection};
+use super::LevelData;
+
+const TICKS_PER_DAY: i64 = 24000;
+const BROADCAST_INTERVAL: i64 = 20;
+
+#[system]
+pub fn tick_time(#[resource] time: &mut WorldTime, #[resource] level: &LevelData) {
+    time.daylight_cycle = level.game_rules.get_bool("doDaylightCycle");
+    time.tick();
+}
+
+#[system(for_each)]
+pub fn broadcast_time(conn: &PlayerConnection, #[resource] time: &WorldTime) {
+    if time.should_broadcast {
+        conn.send(time.get_packet());
+    }
+}
+
+/// World age and time of day, advanced once per tick
+pub struct WorldTime {
+    world_age: i64,
+    /// Ticks since the first day started, only moved by the daylight cycle
+    day_time: i64,
+    daylight_cycle: bool,
+    changed: bool,
+    should_broadcast: bool,
+}
+
+impl WorldTime {
+    pub fn from_level(level: &LevelData) -> Self {
+        Self {
+            world_age: level.time,
+            day_time: level.day_time,
+            daylight_cycle: level.game_rules.get_bool("doDaylightCycle"),
+            changed: false,
+            should_broadcast: false,
+        }
+    }
+
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    /// Time within the current day, from 0 to 23999
+    pub fn time_of_day(&self) -> i64 {
+        self.day_time.rem_euclid(TICKS_PER_DAY)
+    }
+
+    /// Days passed since the first one
+    pub fn day(&self) -> i64 {
+        self.day_time.div_euclid(TICKS_PER_DAY)
+    }
+
+    pub fn set_time(&mut self, day_time: i64) {
+        self.day_time = day_time;
+        self.changed = true;
+    }
+
+    pub fn add_time(&mut self, ticks: i64) {
+        self.set_time(self.day_time + ticks);
+    }
+
+    pub fn get_packet(&self) -> ClientboundPacket {
+        // A negative time of day stops the sun from moving client side
+        let time_of_day = if self.daylight_cycle {
+            self.day_time
+        } else {
+            -self.day_time.max(1)
+        };
+        ClientboundPacket::TimeUpdate {
+            world_age: self.world_age,
+            time_of_day,
+        }
+    }
+
+    pub fn store(&self, level: &mut LevelData) {
+        level.time = self.world_age;
+        level.day_time = self.day_time;
+    }
+
+    fn tick(&mut self) {
+        self.world_age += 1;
+        if self.daylight_cycle {
+            self.day_time += 1;
+        }
+        self.should_broadcast = self.changed || 
+            self.world_age % BROADCAST_INTERVAL == 0;
+        self.changed = false;
+    }
+}
@@ -0,0 +1,209 @@
+use std::mem::take;
+use std::sync::Mutex;
+
+use legion::*;
+use nalgebra::Vector3;
+use rand::{Rng, thread_rng};
+use uuid::Uuid;
+
+use crate::dimensions::{DimensionId, Dimensions};
+use crate::entities::EntityIdGenerator;
+use crate::net::{ClientboundPacket, GameStateChange, PlayerConnection};
+use super::LevelData;
+
+const LIGHTNING_BOLT_TYPE: u32 = 41;
+const LEVEL_CHANGE_STEP: f32 = 0.01;
+
+#[system]
+pub fn tick_weather(#[resource] weather: &mut Weather, #[resource] level: &LevelData,
+    #[resource] entity_ids: &EntityIdGenerator)
+{
+    weather.packets.clear();
+    if level.game_rules.get_bool("doWeatherCycle") {
+        weather.advance_timers();
+    }
+    weather.update_levels();
+    let strikes = take(&mut *weather.strikes.lock().unwrap());
+    for pos in strikes {
+        weather.packets.push(ClientboundPacket::SpawnEntity {
+            entity_id: entity_ids.get_new().0,
+            uuid: Uuid::new_v4(),
+            entity_type: LIGHTNING_BOLT_TYPE,
+            x: pos.x as f64 + 0.5,
+            y: pos.y as f64,
+            z: pos.z as f64 + 0.5,
+            pitch: 0.0,
+            yaw: 0.0,
+            data: 0,
+        });
+    }
+}
+
+#[system(for_each)]
+pub fn broadcast_weather(conn: &PlayerConnection, dimension: &DimensionId,
+    #[resource] weather: &Weather, #[resource] dimensions: &Dimensions)
+{
+    if !dimensions.get(*dimension).kind.has_weather() {
+        return;
+    }
+    for packet in &weather.packets {
+        conn.send(packet.clone());
+    }
+}
+
+/// Rain and thunder state, with the same timers vanilla keeps in level.dat
+pub struct Weather {
+    raining: bool,
+    rain_time: i32,
+    thundering: bool,
+    thunder_time: i32,
+    clear_weather_time: i32,
+    rain_level: f32,
+    thunder_level: f32,
+    strikes: Mutex<Vec<Vector3<i32>>>,
+    packets: Vec<ClientboundPacket>,
+}
+
+impl Weather {
+    pub fn from_level(level: &LevelData) -> Self {
+        Self {
+            raining: level.raining,
+            rain_time: level.rain_time,
+            thundering: level.thundering,
+            thunder_time: level.thunder_time,
+            clear_weather_time: level.clear_weather_time,
+            rain_level: if level.raining { 1.0 } else { 0.0 },
+            thunder_level: if level.thundering { 1.0 } else { 0.0 },
+            strikes: Mutex::new(vec![]),
+            packets: vec![],
+        }
+    }
+
+    pub fn store(&self, level: &mut LevelData) {
+        level.raining = self.raining;
+        level.rain_time = self.rain_time;
+        level.thundering = self.thundering;
+        level.thunder_time = self.thunder_time;
+        level.clear_weather_time = self.clear_weather_time;
+    }
+
+    pub fn is_raining(&self) -> bool {
+        self.rain_level > 0.2
+    }
+
+    pub fn is_thundering(&self) -> bool {
+        self.is_raining() && self.thunder_level > 0.9
+    }
+
+    /// Clear weather for the given number of ticks
+    pub fn set_clear(&mut self, duration: i32) {
+        self.clear_weather_time = duration;
+        self.rain_time = 0;
+        self.thunder_time = 0;
+        self.raining = false;
+        self.thundering = false;
+    }
+
+    pub fn set_rain(&mut self, duration: i32, thunder: bool) {
+        self.clear_weather_time = 0;
+        self.rain_time = duration;
+        self.thunder_time = duration;
+        self.raining = true;
+        self.thundering = thunder;
+    }
+
+    /// Queues a lightning bolt, sent to the players on the next tick
+    pub fn strike_lightning(&self, pos: Vector3<i32>) {
+        self.strikes.lock().unwrap().push(pos);
+    }
+
+    /// Packets a joining player needs to see the current weather
+    pub fn get_state_packets(&self) -> Vec<ClientboundPacket> {
+        if self.rain_level > 0.0 {
+            vec![
+                ClientboundPacket::ChangeGameState {
+                    reason: GameStateChange::BeginRaining as u8,
+                    value: 0.0,
+                },
+                ClientboundPacket::ChangeGameState {
+                    reason: GameStateChange::RainLevel as u8,
+                    value: self.rain_level,
+                },
+                ClientboundPacket::ChangeGameState {
+                    reason: GameStateChange::ThunderLevel as u8,
+                    value: self.thunder_level,
+                },
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn advance_timers(&mut self) {
+        let mut rng = thread_rng();
+        if self.clear_weather_time > 0 {
+            self.clear_weather_time -= 1;
+            self.thunder_time = if self.thundering { 0 } else { 1 };
+            self.rain_time = if self.raining { 0 } else { 1 };
+            self.thundering = false;
+            self.raining = false;
+            return;
+        }
+        if self.thunder_time > 0 {
+            self.thunder_time -= 1;
+            if self.thunder_time == 0 {
+                self.thundering = !self.thundering;
+            }
+        } else if self.thundering {
+            self.thunder_time = rng.gen_range(3600..15600);
+        } else {
+            self.thunder_time = rng.gen_range(12000..180000);
+        }
+        if self.rain_time > 0 {
+            self.rain_time -= 1;
+            if self.rain_time == 0 {
+                self.raining = !self.raining;
+            }
+        } else if self.raining {
+            self.rain_time = rng.gen_range(12000..24000);
+        } else {
+            self.rain_time = rng.gen_range(12000..180000);
+        }
+    }
+
+    fn update_levels(&mut self) {
+        let was_raining = self.is_raining();
+        let old_rain = self.rain_level;
+        let old_thunder = self.thunder_level;
+        self.rain_level = approach(self.rain_level, self.raining);
+        self.thunder_level = approach(self.thunder_level, self.thundering);
+        if self.rain_level != old_rain {
+            self.packets.push(ClientboundPacket::ChangeGameState {
+                reason: GameStateChange::RainLevel as u8,
+                value: self.rain_level,
+            });
+        }
+        if self.thunder_level != old_thunder {
+            self.packets.push(ClientboundPacket::ChangeGameState {
+                reason: GameStateChange::ThunderLevel as u8,
+                value: self.thunder_level,
+            });
+        }
+        if was_raining != self.is_raining() {
+            let reason = if self.is_raining() {
+                GameStateChange::BeginRaining
+            } else {
+                GameStateChange::EndRaining
+            };
+            self.packets.push(ClientboundPacket::ChangeGameState {
+                reason: reason as u8,
+                value: 0.0,
+            });
+        }
+    }
+}
+
+fn approach(level: f32, active: bool) -> f32 {
+    let delta = if active { LEVEL_CHANGE_STEP } else { -LEVEL_CHANGE_STEP };
+    (level + delta).max(0.0).min(1.0)
+}
@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use nalgebra::Vector3;
+use nbt::{Map, Value as Nbt};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::block_entities::BlockEntity;
+use crate::blocks::Block;
+use crate::items::{ItemStack, ItemType};
+use crate::serialization::compound_to_value;
+
+const BLOCKS_JSON: &str = include_str!("blocks.json");
+
+lazy_static! {
+    static ref TABLES: HashMap<String, LootTable> =
+        serde_json::from_str(BLOCKS_JSON).unwrap();
+}
+
+/// What the drops depend on
+pub struct LootContext<'a> {
+    pub block: &'a Block,
+    pub block_entity: Option<&'a BlockEntity>,
+    /// Saved data of the block entity, what copy_nbt reads
+    pub block_entity_nbt: Option<Nbt>,
+    /// Item the block was broken with
+    pub tool: Option<&'a ItemStack>,
+}
+
+/// Loot table in the vanilla data pack format, only with the
+/// conditions and functions blocks use
+#[derive(Deserialize)]
+pub struct LootTable {
+    #[serde(default)]
+    pools: Vec<Pool>,
+}
+
+#[derive(Deserialize)]
+struct Pool {
+    rolls: Number,
+    entries: Vec<Entry>,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Entry {
+    #[serde(rename = "minecraft:item")]
+    Item {
+        name: String,
+        #[serde(default = "one")]
+        weight: u32,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+        #[serde(default)]
+        functions: Vec<Function>,
+    },
+    /// The first child whose conditions pass
+    #[serde(rename = "minecraft:alternatives")]
+    Alternatives {
+        children: Vec<Entry>,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    /// Items that depend on the block, like the contents of a shulker box
+    #[serde(rename = "minecraft:dynamic")]
+    Dynamic {
+        name: String,
+        #[serde(default = "one")]
+        weight: u32,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    #[serde(rename = "minecraft:empty")]
+    Empty {
+        #[serde(default = "one")]
+        weight: u32,
+    },
+}
+
+/// Entry that passed its conditions and can be picked
+enum Candidate<'a> {
+    Item(&'a str, &'a [Function]),
+    Dynamic(&'a str),
+    Empty,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Number {
+    Constant(f32),
+    Random(RandomNumber),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RandomNumber {
+    #[serde(rename = "minecraft:uniform")]
+    Uniform {
+        min: f32,
+        max: f32,
+    },
+    #[serde(rename = "minecraft:binomial")]
+    Binomial {
+        n: i32,
+        p: f32,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "condition")]
+enum Condition {
+    /// Always passes while there are no explosions
+    #[serde(rename = "minecraft:survives_explosion")]
+    SurvivesExplosion,
+    #[serde(rename = "minecraft:random_chance")]
+    RandomChance {
+        chance: f32,
+    },
+    /// Random chance that grows with the level of an enchantment
+    #[serde(rename = "minecraft:table_bonus")]
+    TableBonus {
+        enchantment: String,
+        chances: Vec<f32>,
+    },
+    #[serde(rename = "minecraft:match_tool")]
+    MatchTool {
+        predicate: ToolPredicate,
+    },
+    #[serde(rename = "minecraft:block_state_property")]
+    BlockStateProperty {
+        block: String,
+        properties: HashMap<String, String>,
+    },
+    #[serde(rename = "minecraft:inverted")]
+    Inverted {
+        term: Box<Condition>,
+    },
+    /// Passes if any of the terms does
+    #[serde(rename = "minecraft:alternative")]
+    Alternative {
+        terms: Vec<Condition>,
+    },
+}
+
+#[derive(Deserialize)]
+struct ToolPredicate {
+    item: Option<String>,
+    #[serde(default)]
+    enchantments: Vec<EnchantmentPredicate>,
+}
+
+#[derive(Deserialize)]
+struct EnchantmentPredicate {
+    enchantment: String,
+    #[serde(default)]
+    levels: Limit,
+}
+
+#[derive(Deserialize, Default)]
+struct Limit {
+    min: Option<i32>,
+    max: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    #[serde(flatten)]
+    kind: FunctionKind,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "function")]
+enum FunctionKind {
+    #[serde(rename = "minecraft:set_count")]
+    SetCount {
+        count: Number,
+    },
+    /// More drops with fortune, using one of the vanilla formulas
+    #[serde(rename = "minecraft:apply_bonus")]
+    ApplyBonus {
+        enchantment: String,
+        formula: String,
+        #[serde(default)]
+        parameters: BonusParameters,
+    },
+    #[serde(rename = "minecraft:limit_count")]
+    LimitCount {
+        limit: Limit,
+    },
+    /// Does nothing while there are no explosions
+    #[serde(rename = "minecraft:explosion_decay")]
+    ExplosionDecay,
+    /// Names the item like the block entity was named
+    #[serde(rename = "minecraft:copy_name")]
+    CopyName,
+    #[serde(rename = "minecraft:copy_nbt")]
+    CopyNbt {
+        ops: Vec<CopyOperation>,
+    },
+    /// Keeps block state properties in the item's BlockStateTag
+    #[serde(rename = "minecraft:copy_state")]
+    CopyState {
+        block: String,
+        properties: Vec<String>,
+    },
+    /// Fills BlockEntityTag.Items, keeping the slots
+    #[serde(rename = "minecraft:set_contents")]
+    SetContents {
+        entries: Vec<Entry>,
+    },
+}
+
+/// Copies a value from the block entity, paths are keys separated by dots
+#[derive(Deserialize)]
+struct CopyOperation {
+    source: String,
+    target: String,
+    op: CopyMode,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum CopyMode {
+    Replace,
+    /// Adds to the end of a list that's already there
+    Append,
+    /// Adds the keys to a compound that's already there
+    Merge,
+}
+
+#[derive(Deserialize, Default)]
+struct BonusParameters {
+    #[serde(rename = "bonusMultiplier", default)]
+    bonus_multiplier: i32,
+    #[serde(default)]
+    extra: i32,
+    #[serde(default)]
+    probability: f32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Items that drop when a block is broken
+pub fn block_drops(block: &Block, block_entity: Option<&BlockEntity>, pos: Vector3<i32>,
+    tool: Option<&ItemStack>) -> Vec<ItemStack>
+{
+    let name = block.btype.name.trim_start_matches("minecraft:");
+    let table = match TABLES.get(&format!("minecraft:blocks/{}", name)) {
+        Some(table) => table,
+        None => return Vec::new(),
+    };
+    let context = LootContext {
+        block,
+        block_entity,
+        block_entity_nbt: block_entity.map(|entity| compound_to_value(&entity.to_nbt(pos))),
+        tool,
+    };
+    table.roll(&context, &mut rand::thread_rng())
+}
+
+impl LootTable {
+    pub fn roll(&self, context: &LootContext, rng: &mut impl Rng) -> Vec<ItemStack> {
+        let mut drops = Vec::new();
+        for pool in &self.pools {
+            if !all(&pool.conditions, context, rng) {
+                continue;
+            }
+            for _ in 0..pool.rolls.roll(rng) {
+                pool.roll(context, rng, &mut drops);
+            }
+        }
+        drops
+    }
+}
+
+impl Pool {
+    fn roll(&self, context: &LootContext, rng: &mut impl Rng, drops: &mut Vec<ItemStack>) {
+        let mut candidates = Vec::new();
+        for entry in &self.entries {
+            entry.expand(context, rng, &mut candidates);
+        }
+        let total: u32 = candidates.iter().map(|(weight, _)| weight).sum();
+        if total == 0 {
+            return;
+        }
+        let mut picked = rng.gen_range(0..total);
+        let candidate = candidates.into_iter()
+            .find(|(weight, _)| {
+                let found = picked < *weight;
+                picked = picked.saturating_sub(*weight);
+                found
+            })
+            .map(|(_, candidate)| candidate);
+        if let Some(candidate) = candidate {
+            drops.extend(candidate.generate(&self.functions, context, rng).into_iter().flatten());
+        }
+    }
+}
+
+impl Entry {
+    /// Adds the entries that can be picked with their weights
+    fn expand<'a>(&'a self, context: &LootContext, rng: &mut impl Rng,
+        candidates: &mut Vec<(u32, Candidate<'a>)>) -> bool
+    {
+        match self {
+            Self::Item { name, weight, conditions, functions } => {
+                if !all(conditions, context, rng) {
+                    return false;
+                }
+                candidates.push((*weight, Candidate::Item(name, functions)));
+            },
+            Self::Alternatives { children, conditions } => {
+                if !all(conditions, context, rng) {
+                    return false;
+                }
+                return children.iter().any(|child| child.expand(context, rng, candidates));
+            },
+            Self::Dynamic { name, weight, conditions } => {
+                if !all(conditions, context, rng) {
+                    return false;
+                }
+                candidates.push((*weight, Candidate::Dynamic(name)));
+            },
+            Self::Empty { weight } => candidates.push((*weight, Candidate::Empty)),
+        }
+        true
+    }
+}
+
+impl Candidate<'_> {
+    /// The stacks it drops, dynamic contents keep their empty slots
+    fn generate(&self, pool_functions: &[Function], context: &LootContext,
+        rng: &mut impl Rng) -> Vec<Option<ItemStack>>
+    {
+        match self {
+            Self::Item(name, functions) => {
+                let item = match ItemType::from_str(name) {
+                    Ok(item) => item,
+                    Err(_) => return Vec::new(),
+                };
+                let mut loot = Loot {
+                    stack: ItemStack { item, count: 1, nbt: None },
+                    count: 1,
+                };
+                for function in functions.iter().chain(pool_functions) {
+                    function.apply(&mut loot, context, rng);
+                }
+                loot.into_stacks()
+            },
+            Self::Dynamic("minecraft:contents") => context.block_entity
+                .and_then(|entity| entity.items().cloned())
+                .unwrap_or_default(),
+            Self::Dynamic(_) | Self::Empty => Vec::new(),
+        }
+    }
+}
+
+/// Item being made by an entry, functions can take the count below zero
+/// before limiting it
+struct Loot {
+    stack: ItemStack,
+    count: i32,
+}
+
+impl Loot {
+    /// Splits the items in full stacks
+    fn into_stacks(self) -> Vec<Option<ItemStack>> {
+        let max = self.stack.max_stack_size().max(1) as i32;
+        let mut left = self.count;
+        let mut stacks = Vec::new();
+        while left > 0 {
+            let count = left.min(max);
+            stacks.push(Some(ItemStack { count: count as u8, ..self.stack.clone() }));
+            left -= count;
+        }
+        stacks
+    }
+}
+
+impl Number {
+    fn roll(&self, rng: &mut impl Rng) -> i32 {
+        match self {
+            Self::Constant(value) => *value as i32,
+            Self::Random(RandomNumber::Uniform { min, max }) => {
+                rng.gen_range(min.floor() as i32..=max.floor() as i32)
+            },
+            Self::Random(RandomNumber::Binomial { n, p }) => {
+                (0..*n).filter(|_| rng.gen::<f32>() < *p).count() as i32
+            },
+        }
+    }
+}
+
+impl Condition {
+    fn test(&self, context: &LootContext, rng: &mut impl Rng) -> bool {
+        match self {
+            Self::SurvivesExplosion => true,
+            Self::RandomChance { chance } => rng.gen::<f32>() < *chance,
+            Self::TableBonus { enchantment, chances } => {
+                let level = enchantment_level(context, enchantment) as usize;
+                let chance = chances.get(level).or_else(|| chances.last()).copied();
+                rng.gen::<f32>() < chance.unwrap_or(0.0)
+            },
+            Self::MatchTool { predicate } => predicate.test(context.tool),
+            Self::BlockStateProperty { block, properties } => {
+                context.block.btype.name == *block && properties.iter()
+                    .all(|(name, value)| context.block.props.get(name) == Some(value))
+            },
+            Self::Inverted { term } => !term.test(context, rng),
+            Self::Alternative { terms } => terms.iter().any(|term| term.test(context, rng)),
+        }
+    }
+}
+
+fn all(conditions: &[Condition], context: &LootContext, rng: &mut impl Rng) -> bool {
+    conditions.iter().all(|condition| condition.test(context, rng))
+}
+
+fn enchantment_level(context: &LootContext, enchantment: &str) -> i16 {
+    context.tool.map_or(0, |tool| tool.enchantment_level(enchantment))
+}
+
+impl ToolPredicate {
+    fn test(&self, tool: Option<&ItemStack>) -> bool {
+        let tool = match tool {
+            Some(tool) => tool,
+            None => return false,
+        };
+        if let Some(item) = &self.item {
+            if tool.item.to_str() != item {
+                return false;
+            }
+        }
+        self.enchantments.iter().all(|predicate| {
+            let level = tool.enchantment_level(&predicate.enchantment) as i32;
+            level > 0 && predicate.levels.contains(level)
+        })
+    }
+}
+
+impl Limit {
+    fn contains(&self, value: i32) -> bool {
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+
+    fn clamp(&self, value: i32) -> i32 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+impl Function {
+    fn apply(&self, loot: &mut Loot, context: &LootContext, rng: &mut impl Rng) {
+        if !all(&self.conditions, context, rng) {
+            return;
+        }
+        match &self.kind {
+            FunctionKind::SetCount { count } => loot.count = count.roll(rng),
+            FunctionKind::ApplyBonus { enchantment, formula, parameters } => {
+                let level = enchantment_level(context, enchantment) as i32;
+                loot.count = apply_bonus(loot.count, level, formula, parameters, rng);
+            },
+            FunctionKind::LimitCount { limit } => loot.count = limit.clamp(loot.count),
+            FunctionKind::ExplosionDecay => (),
+            FunctionKind::CopyName => {
+                let name = context.block_entity_nbt.as_ref()
+                    .and_then(|nbt| get_path(nbt, "CustomName"));
+                if let Some(Nbt::String(name)) = name {
+                    loot.stack.set_display_name(Some(name.clone()));
+                }
+            },
+            FunctionKind::CopyNbt { ops } => {
+                let source = match &context.block_entity_nbt {
+                    Some(source) => source,
+                    None => return,
+                };
+                for op in ops {
+                    if let Some(value) = get_path(source, &op.source) {
+                        set_path(&mut loot.stack, &op.target, value.clone(), op.op);
+                    }
+                }
+            },
+            FunctionKind::CopyState { block, properties } => {
+                if context.block.btype.name != *block {
+                    return;
+                }
+                for property in properties {
+                    if let Some(value) = context.block.props.get(property) {
+                        let path = format!("BlockStateTag.{}", property);
+                        set_path(&mut loot.stack, &path, Nbt::String(value.clone()),
+                            CopyMode::Replace);
+                    }
+                }
+            },
+            FunctionKind::SetContents { entries } => {
+                let mut candidates = Vec::new();
+                for entry in entries {
+                    entry.expand(context, rng, &mut candidates);
+                }
+                let contents: Vec<Option<ItemStack>> = candidates.iter()
+                    .flat_map(|(_, candidate)| candidate.generate(&[], context, rng))
+                    .collect();
+                let items: Vec<Nbt> = contents.iter().enumerate()
+                    .filter_map(|(slot, stack)| Some(item_nbt(slot, stack.as_ref()?)))
+                    .collect();
+                if !items.is_empty() {
+                    set_path(&mut loot.stack, "BlockEntityTag.Items", Nbt::List(items),
+                        CopyMode::Replace);
+                }
+            },
+        }
+    }
+}
+
+/// Value at a path like BlockEntityTag.Items
+fn get_path<'a>(value: &'a Nbt, path: &str) -> Option<&'a Nbt> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Nbt::Compound(map) => map.get(key),
+        _ => None,
+    })
+}
+
+/// Puts a value in the item's tag, creating the compounds on the way
+fn set_path(stack: &mut ItemStack, path: &str, value: Nbt, mode: CopyMode) {
+    fn put(target: &mut Nbt, path: &str, value: Nbt, mode: CopyMode) {
+        let map = match target {
+            Nbt::Compound(map) => map,
+            _ => return,
+        };
+        if let Some((key, rest)) = path.split_once('.') {
+            let child = map.entry(key.to_string())
+                .or_insert_with(|| Nbt::Compound(Map::new()));
+            return put(child, rest, value, mode);
+        }
+        let value = match (mode, map.remove(path), value) {
+            (CopyMode::Append, Some(Nbt::List(mut old)), Nbt::List(new)) => {
+                old.extend(new);
+                Nbt::List(old)
+            },
+            (CopyMode::Merge, Some(Nbt::Compound(mut old)), Nbt::Compound(new)) => {
+                old.extend(new);
+                Nbt::Compound(old)
+            },
+            (_, _, value) => value,
+        };
+        map.insert(path.into(), value);
+    }
+    let tag = stack.nbt.get_or_insert_with(|| Nbt::Compound(Map::new()));
+    put(tag, path, value, mode);
+}
+
+/// Entry of an Items list, like block entities save them
+fn item_nbt(slot: usize, stack: &ItemStack) -> Nbt {
+    let mut item = Map::new();
+    item.insert("Slot".into(), Nbt::Byte(slot as i8));
+    item.insert("id".into(), Nbt::String(stack.item.to_str().into()));
+    item.insert("Count".into(), Nbt::Byte(stack.count as i8));
+    if let Some(tag) = &stack.nbt {
+        item.insert("tag".into(), tag.clone());
+    }
+    Nbt::Compound(item)
+}
+
+fn apply_bonus(count: i32, level: i32, formula: &str, parameters: &BonusParameters,
+    rng: &mut impl Rng) -> i32
+{
+    if level <= 0 {
+        return count;
+    }
+    match formula {
+        // Multiplies the drops by a random amount, never less than one
+        "minecraft:ore_drops" => count * (rng.gen_range(0..level + 2) - 1).max(0) + count,
+        "minecraft:uniform_bonus_count" => {
+            count + rng.gen_range(0..=parameters.bonus_multiplier * level)
+        },
+        "minecraft:binomial_with_bonus_count" => {
+            count + (0..level + parameters.extra)
+                .filter(|_| rng.gen::<f32>() < parameters.probability)
+                .count() as i32
+        },
+        _ => count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    #[test]
+    fn tables_drop_real_items() {
+        fn check(entry: &Entry) {
+            match entry {
+                Entry::Item { name, .. } => assert!(ItemType::from_str(name).is_ok(), "{}", name),
+                Entry::Alternatives { children, .. } => children.iter().for_each(check),
+                Entry::Dynamic { .. } | Entry::Empty { .. } => (),
+            }
+        }
+        for table in TABLES.values() {
+            table.pools.iter().flat_map(|pool| &pool.entries).for_each(check);
+        }
+    }
+
+    #[test]
+    fn fortune_never_lowers_drops() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let parameters = BonusParameters::default();
+        for _ in 0..100 {
+            let count = apply_bonus(1, 3, "minecraft:ore_drops", &parameters, &mut rng);
+            assert!((1..=4).contains(&count));
+        }
+        assert_eq!(apply_bonus(2, 0, "minecraft:ore_drops", &parameters, &mut rng), 2);
+    }
+}
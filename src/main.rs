@@ -2,14 +2,24 @@ mod net;
 mod players;
 mod chunks;
 mod config;
+mod console;
 mod util;
 mod entities;
 mod buckets;
 mod inventory;
 mod items;
+mod loot;
+mod recipes;
 mod serialization;
 mod tags;
 mod blocks;
+mod autosave;
+mod level;
+mod biomes;
+mod dimensions;
+mod block_entities;
+mod portals;
+mod windows;
 
 use std::{
     sync::atomic::{AtomicBool, Ordering}, 
@@ -17,6 +27,7 @@ use std::{
 
 use anyhow::Result;
 use config::Config;
+use level::LevelData;
 use legion::*;
 
 use net::Server;
@@ -29,14 +40,26 @@ static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = Config::load().await?;
+    let level = LevelData::load().await?;
     let server = Server::start(&config);
     let mut resources = Resources::default();
     resources.insert(server);
     let mut schedule = Schedule::builder();
+    autosave::register(&mut schedule, &mut resources, &config);
+    level::register(&mut schedule, &mut resources, level);
     players::register_early(&mut schedule, &mut resources);
-    chunks::register(&mut schedule, &mut resources);
+    dimensions::register(&mut schedule, &mut resources, &config)?;
+    level::place_spawn(&resources).await?;
+    if std::env::args().any(|arg| arg == "--trim") {
+        chunks::trim(&resources);
+    }
+    chunks::register(&mut schedule);
+    chunks::pregenerate_spawn(&resources, &config);
     entities::register(&mut schedule, &mut resources);
-    buckets::register(&mut schedule, &mut resources);
+    buckets::register(&mut schedule);
+    portals::register(&mut schedule, &mut resources);
+    windows::register(&mut schedule, &mut resources);
+    console::register(&mut schedule, &mut resources);
     players::register_late(&mut schedule, &mut resources);
     let mut schedule = schedule.build();
     let mut world = World::default();
@@ -59,6 +82,7 @@ async fn main() -> Result<()> {
             println!("\nStopping...");
             players::on_stop(&mut world, &mut resources).await;
             chunks::on_stop(&mut resources).await;
+            level::on_stop(&mut resources).await;
             break Ok(());
         }
     }
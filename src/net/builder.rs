@@ -1,6 +1,7 @@
 use nalgebra::Vector3;
 use nbt::{Value, to_writer};
-use crate::serialization::push_varint;
+use crate::serialization::{push_varint, push_varlong};
+use crate::items::ItemStack;
 
 pub struct PacketBuilder {
     bytes: Vec<u8>,
@@ -18,6 +19,11 @@ impl PacketBuilder {
         self
     }
 
+    pub fn add_varlong(&mut self, value: u64) -> &mut Self {
+        push_varlong(value, &mut self.bytes);
+        self
+    }
+
     pub fn add_str(&mut self, value: &str) -> &mut Self {
         self.add_varint(value.len() as u32)
             .add_bytes(value.as_bytes())
@@ -33,6 +39,25 @@ impl PacketBuilder {
         self
     }
 
+    pub fn add_slot(&mut self, slot: &Option<ItemStack>) -> &mut Self {
+        self.add_bytes(&[slot.is_some() as u8]);
+        if let Some(stack) = slot {
+            self.add_varint(stack.item.to_numeric() as u32)
+                .add_bytes(&[stack.count]);
+            match &stack.nbt {
+                // Written by hand so int and byte arrays keep their tag types
+                Some(nbt) => {
+                    self.add_bytes(&[nbt.id(), 0, 0]);
+                    nbt.to_writer(&mut self.bytes).unwrap();
+                }
+                None => {
+                    self.add_bytes(&[0]);
+                }
+            }
+        }
+        self
+    }
+
     pub fn add_angle(&mut self, angle: f32) -> &mut Self {
         let angle = ((angle / 360.0) * 256.0).rem_euclid(256.0) as u8;
         self.add_bytes(&[angle]);
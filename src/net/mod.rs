@@ -8,4 +8,5 @@ mod server_info;
 
 pub use server::Server;
 pub use connection::PlayerConnection;
-pub use play::{ClientboundPacket, ServerboundPacket, PlayerInfo};
+pub use play::{ClickMode, ClientboundPacket, GameStateChange, Metadata, ServerboundPacket, PlayerInfo};
+pub use play::{DIM_CODEC, DIM_NBT, DIM_NETHER_NBT, DIM_END_NBT};
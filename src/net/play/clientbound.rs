@@ -1,9 +1,12 @@
+use std::sync::Arc;
+
 use nalgebra::Vector3;
 use nbt::Value as Nbt;
 use serde_json::Value as Json;
 use uuid::Uuid;
 
 use crate::items::ItemStack;
+use crate::recipes::Recipe;
 
 #[derive(Clone)]
 pub struct PlayerInfo {
@@ -21,6 +24,22 @@ pub struct PlayerInfoProperty {
     pub signature: Option<String>,
 }
 
+/// Reasons of the Change Game State packet
+#[derive(Clone, Copy)]
+pub enum GameStateChange {
+    EndRaining = 1,
+    BeginRaining = 2,
+    ChangeGameMode = 3,
+    RainLevel = 7,
+    ThunderLevel = 8,
+}
+
+/// Value of an entity metadata entry, only the types in use so far
+#[derive(Clone)]
+pub enum Metadata {
+    Slot(Option<ItemStack>),
+}
+
 #[derive(Clone)]
 pub enum ClientboundPacket {
     JoinGame {
@@ -31,6 +50,7 @@ pub enum ClientboundPacket {
         dimension: &'static[u8],
         current_world: String,
         view_distance: u8,
+        is_flat: bool,
     },
     PluginMessage {
         channel: String,
@@ -100,6 +120,22 @@ pub enum ClientboundPacket {
         yaw: f32,
     },
     DestroyEntities(Vec<u32>),
+    SpawnEntity {
+        entity_id: u32,
+        uuid: Uuid,
+        entity_type: u32,
+        x: f64,
+        y: f64,
+        z: f64,
+        pitch: f32,
+        yaw: f32,
+        data: i32,
+    },
+    /// Indexes depend on the entity type, like 7 for the item of an item entity
+    EntityMetadata {
+        id: u32,
+        metadata: Vec<(u8, Metadata)>,
+    },
     SpawnPlayer {
        entity_id: u32,
        uuid: Uuid,
@@ -113,15 +149,121 @@ pub enum ClientboundPacket {
         pos: Vector3<i32>,
         block_state: u32,
     },
+    MultiBlockChange {
+        /// Section coordinates, in sections instead of blocks
+        section: Vector3<i32>,
+        /// Position inside the section and new state of each block
+        blocks: Vec<((u8, u8, u8), u32)>,
+    },
+    BlockEntityData {
+        pos: Vector3<i32>,
+        /// What kind of block entity this is for the client
+        action: u8,
+        nbt: Nbt,
+    },
+    /// Answer to Player Digging, with the block the client should show
+    AcknowledgePlayerDigging {
+        pos: Vector3<i32>,
+        block_state: u32,
+        status: u8,
+        successful: bool,
+    },
+    /// Cracks on a block someone else is digging, stages go from 0 to 9
+    /// and anything else removes them
+    BlockBreakAnimation {
+        entity_id: u32,
+        pos: Vector3<i32>,
+        stage: i8,
+    },
+    /// Bar goes from 0 to 1 between levels
+    SetExperience {
+        bar: f32,
+        level: i32,
+        total: i32,
+    },
+    /// Item flying to whoever picked it up, count is how many were taken
+    CollectItem {
+        id: u32,
+        collector: u32,
+        count: u8,
+    },
+    OpenSignEditor(Vector3<i32>),
+    /// Hotbar slot in hand, 0 to 8
+    HeldItemChange(u8),
     WindowItems {
         window: u8,
         items: Vec<Option<ItemStack>>,
     },
+    /// Window -1 and slot -1 is the stack held by the cursor
+    SetSlot {
+        window: i8,
+        slot: i16,
+        item: Option<ItemStack>,
+    },
+    OpenWindow {
+        window: u8,
+        /// Id in the menu registry, like 2 for a 9x3 chest
+        kind: u32,
+        title: Json,
+    },
+    CloseWindow(u8),
+    /// Numbers a window shows, like the progress arrow of a furnace
+    WindowProperty {
+        window: u8,
+        property: i16,
+        value: i16,
+    },
+    /// Whether a click went the way the client predicted
+    WindowConfirmation {
+        window: u8,
+        action: u16,
+        accepted: bool,
+    },
     UnloadChunk(i32, i32),
+    TimeUpdate {
+        world_age: i64,
+        time_of_day: i64,
+    },
+    ChangeGameState {
+        reason: u8,
+        value: f32,
+    },
+    Respawn {
+        dimension: &'static [u8],
+        world_name: String,
+        gamemode: u8,
+        is_flat: bool,
+    },
     Disconnect {
         reason: Json,
     },
+    /// Every recipe the server knows, for the recipe book
+    DeclareRecipes(&'static [Recipe]),
+    /// Shows recipes in the recipe book, all of them since there's
+    /// no unlocking yet
+    UnlockRecipes(&'static [Recipe]),
     Tags {
         raw: &'static [u8],
-    }
+    },
+    WorldBorderInit {
+        x: f64,
+        z: f64,
+        old_diameter: f64,
+        new_diameter: f64,
+        /// Milliseconds until the new diameter is reached
+        speed: u64,
+        warning_time: i32,
+        warning_blocks: i32,
+    },
+    WorldBorderSize(f64),
+    WorldBorderLerpSize {
+        old_diameter: f64,
+        new_diameter: f64,
+        speed: u64,
+    },
+    WorldBorderCenter(f64, f64),
+    WorldBorderWarningTime(i32),
+    WorldBorderWarningBlocks(i32),
+    /// Already encoded packet, shared between every connection it's sent to
+    Raw(Arc<[u8]>),
 }
@@ -4,22 +4,25 @@ mod clientbound;
 mod serverbound;
 mod errors;
 
-pub use clientbound::{ClientboundPacket, PlayerInfo, PlayerInfoProperty};
-pub use serverbound::ServerboundPacket;
+pub use clientbound::{ClientboundPacket, GameStateChange, Metadata, PlayerInfo, PlayerInfoProperty};
+pub use serverbound::{ClickMode, ServerboundPacket};
 
 use tokio::net::TcpStream;
 use anyhow::Result;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, BufReader};
 use std::sync::mpsc::Sender;
 use tokio::sync::mpsc::UnboundedReceiver;
+use crate::recipes;
 use crate::tags::TAG_PACKET;
 
 use super::connection::GameConnection;
 use errors::UnknownPacket;
 
 const BRAND: &str = "Glow";
-const DIM_CODEC: &[u8] = include_bytes!("./dimension_codec.nbt");
-const DIM_NBT: &[u8] = include_bytes!("./dimension.nbt");
+pub const DIM_CODEC: &[u8] = include_bytes!("./dimension_codec.nbt");
+pub const DIM_NBT: &[u8] = include_bytes!("./dimension.nbt");
+pub const DIM_NETHER_NBT: &[u8] = include_bytes!("./dimension_nether.nbt");
+pub const DIM_END_NBT: &[u8] = include_bytes!("./dimension_end.nbt");
 
 pub async fn play(conn: TcpStream, game: GameConnection) -> Result<()> {
     let (game_recv, mut game_send) = game.into_split();
@@ -50,9 +53,13 @@ async fn client_to_game<R>(tcp: R, sender: &mut Sender<ServerboundPacket>)
     }
 }
 
-async fn game_to_client<W>(mut game: UnboundedReceiver<ClientboundPacket>, mut tcp: W) 
+async fn game_to_client<W>(mut game: UnboundedReceiver<ClientboundPacket>, mut tcp: W)
     -> Result<()> where W: AsyncWrite + Unpin
 {
+    // The game sends Join Game first, once it knows where the player is
+    if let Some(join) = game.recv().await {
+        join.send(&mut tcp).await?;
+    }
     send_initial_packets(&mut tcp).await?;
     while let Some(packet) = game.recv().await {
         packet.send(&mut tcp).await?;
@@ -63,21 +70,16 @@ async fn game_to_client<W>(mut game: UnboundedReceiver<ClientboundPacket>, mut t
 async fn send_initial_packets<W>(writer: &mut W) -> Result<()>
     where W: AsyncWrite + Unpin 
 {
-    ClientboundPacket::JoinGame {
-        entity_id: 999,
-        gamemode: 1,
-        world_names: vec!["overworld".into(), "nether".into(), "the_end".into()],
-        dimension_codec: DIM_CODEC,
-        dimension: DIM_NBT,
-        current_world: "overworld".into(),
-        view_distance: 6,
-    }.send(writer).await?;
     ClientboundPacket::PluginMessage {
         channel: "minecraft:brand".into(),
         content: BRAND.into(),
     }.send(writer).await?;
+    ClientboundPacket::DeclareRecipes(recipes::all())
+        .send(writer).await?;
     ClientboundPacket::Tags {
         raw: TAG_PACKET,
     }.send(writer).await?;
+    ClientboundPacket::UnlockRecipes(recipes::all())
+        .send(writer).await?;
     Ok(())
 }
@@ -4,11 +4,15 @@ use nalgebra::vector;
 use tokio::io::{AsyncRead, AsyncReadExt};
 use anyhow::{Result, anyhow};
 use crate::items::{ItemStack, ItemType};
+use nbt::Value as Nbt;
 use num_traits::FromPrimitive;
 
 use super::errors::UnknownPacket;
 use super::serverbound::ServerboundPacket;
-use crate::net::value_readers::{read_block_pos, read_varint};
+use crate::net::value_readers::{read_block_pos, read_str, read_varint};
+
+/// Longest line the client may send, vanilla kicks for anything longer
+const MAX_SIGN_LINE: usize = 384;
 
 impl ServerboundPacket {
     pub async fn read<R>(reader: &mut R) -> Result<Self> 
@@ -22,6 +26,33 @@ impl ServerboundPacket {
         reader.read_exact(buffer.as_mut()).await?;
         let mut payload = Cursor::new(buffer);
         match payload.read_u8().await? {
+            0x07 => {
+                let window = payload.read_u8().await?;
+                let action = payload.read_u16().await?;
+                let accepted = payload.read_u8().await? != 0;
+                Ok(Self::WindowConfirmation {
+                    window, action, accepted
+                })
+            }
+            0x09 => {
+                let window = payload.read_u8().await?;
+                let slot = payload.read_i16().await?;
+                let button = payload.read_u8().await?;
+                let action = payload.read_u16().await?;
+                let mode = read_varint(&mut payload).await?;
+                let clicked = read_slot(&mut payload).await?;
+                Ok(Self::ClickWindow {
+                    window, slot, button, action, clicked,
+                    mode: FromPrimitive::from_u32(mode)
+                        .ok_or(anyhow!("Invalid click mode"))?,
+                })
+            }
+            0x0A => {
+                let window = payload.read_u8().await?;
+                Ok(Self::CloseWindow {
+                    window
+                })
+            }
             0x12 => {
                 let x = f64::from_bits(payload.read_u64().await?);
                 let y = f64::from_bits(payload.read_u64().await?);
@@ -66,17 +97,7 @@ impl ServerboundPacket {
             }
             0x28 => {
                 let slot = payload.read_i16().await?;
-                let stack = if payload.read_u8().await? == 0 {
-                    None
-                } else {
-                    let id = read_varint(&mut payload).await?;
-                    let count = payload.read_u8().await?;
-                    Some(ItemStack {
-                        item: ItemType::from_numeric(id as u16)?,
-                        count,
-                        nbt: None,
-                    })
-                };
+                let stack = read_slot(&mut payload).await?;
                 Ok(Self::CreativeInventoryAction {
                     slot, stack
                 })
@@ -96,7 +117,44 @@ impl ServerboundPacket {
                     cursor_position: vector!(cursor_x, cursor_y, cursor_z),
                 })
             }
+            0x2B => {
+                let location = read_block_pos(&mut payload).await?;
+                let mut lines: [String; 4] = Default::default();
+                for line in lines.iter_mut() {
+                    *line = read_str(&mut payload).await?;
+                    if line.chars().count() > MAX_SIGN_LINE {
+                        return Err(anyhow!("Sign line too long"));
+                    }
+                }
+                Ok(Self::UpdateSign {
+                    location, lines
+                })
+            }
             id => Err(UnknownPacket(id).into()),
         }
     }
 }
+
+async fn read_slot(reader: &mut Cursor<Vec<u8>>) -> Result<Option<ItemStack>> {
+    if reader.read_u8().await? == 0 {
+        return Ok(None);
+    }
+    let id = read_varint(reader).await?;
+    let count = reader.read_u8().await?;
+    Ok(Some(ItemStack {
+        item: ItemType::from_numeric(id as u16)?,
+        count,
+        nbt: read_nbt(reader).await?,
+    }))
+}
+
+/// Reads a named root tag, the client sends a single TAG_End byte for no data
+async fn read_nbt(reader: &mut Cursor<Vec<u8>>) -> Result<Option<Nbt>> {
+    let id = reader.read_u8().await?;
+    if id == 0 {
+        return Ok(None);
+    }
+    let name_len = reader.read_u16().await?;
+    reader.set_position(reader.position() + name_len as u64);
+    Ok(Some(Nbt::from_reader(id, reader)?))
+}
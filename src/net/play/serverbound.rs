@@ -1,6 +1,7 @@
 use nalgebra::Vector3;
 use crate::items::ItemStack;
 use crate::blocks::BlockFace;
+use num_derive::FromPrimitive;
 
 pub enum ServerboundPacket {
     PlayerPosition {
@@ -41,7 +42,49 @@ pub enum ServerboundPacket {
         cursor_position: Vector3<f32>,
         inside_block: bool,
     },
+    UpdateSign {
+        location: Vector3<i32>,
+        lines: [String; 4],
+    },
+    ClickWindow {
+        window: u8,
+        /// -999 for clicks outside the window
+        slot: i16,
+        button: u8,
+        action: u16,
+        mode: ClickMode,
+        /// What the client thinks is in the slot now
+        clicked: Option<ItemStack>,
+    },
+    CloseWindow {
+        window: u8,
+    },
+    /// Acknowledges a click the server rejected
+    WindowConfirmation {
+        window: u8,
+        action: u16,
+        accepted: bool,
+    },
     Disconnect {
         reason: String,
     },
 }
+
+/// What the button means depends on the mode
+#[derive(FromPrimitive, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickMode {
+    /// Left or right click
+    Pickup = 0,
+    /// Shift click
+    QuickMove = 1,
+    /// Number keys, the button is the hotbar slot
+    Swap = 2,
+    /// Middle click in creative
+    Clone = 3,
+    /// Q or Ctrl+Q
+    Throw = 4,
+    /// Dragging over several slots, sent in start, slot and end steps
+    QuickCraft = 5,
+    /// Double click
+    PickupAll = 6,
+}
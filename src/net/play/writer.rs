@@ -1,17 +1,32 @@
 use anyhow::Result;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-use super::clientbound::ClientboundPacket;
+use super::clientbound::{ClientboundPacket, Metadata};
 use super::super::builder::PacketBuilder;
+use crate::items::ItemStack;
+use crate::recipes::{Ingredient, Recipe, RecipeKind};
+
+/// Vanilla default, the client doesn't use it for anything else
+const PORTAL_TELEPORT_BOUNDARY: u32 = 29999984;
 
 impl ClientboundPacket {
     pub async fn send<W>(&self, writer: &mut W) -> Result<()>
         where W: AsyncWrite + Unpin
     {
-        let bytes = match self {
+        match self {
+            Self::Raw(bytes) => writer.write_all(bytes).await?,
+            packet => writer.write_all(&packet.encode()).await?,
+        }
+        Ok(())
+    }
+
+    /// The whole packet, length prefix included
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Raw(bytes) => bytes.to_vec(),
             Self::JoinGame { 
                 entity_id, gamemode, world_names, dimension_codec, dimension, 
-                current_world, view_distance,
+                current_world, view_distance, is_flat,
             } => {
                 let mut pack = PacketBuilder::new(0x24);
                 pack.add_bytes(&entity_id.to_be_bytes()) // Entity ID
@@ -31,7 +46,7 @@ impl ClientboundPacket {
                     .add_bytes(&[0]) // Should debug info be hidden (F3)
                     .add_bytes(&[1]) // Show the "You died" screen instead of respawning immediately
                     .add_bytes(&[0]) // Is debug world
-                    .add_bytes(&[0]) // Is superflat world
+                    .add_bytes(&[*is_flat as u8]) // Is superflat world
                     .build()
             }
             Self::PluginMessage { channel, content } => {
@@ -150,7 +165,14 @@ impl ClientboundPacket {
                 packet.build()
             }
             Self::PlayerInfoUpdateGamemode(updates) => {
-                unimplemented!()
+                let mut packet = PacketBuilder::new(0x32);
+                packet.add_varint(1)
+                    .add_varint(updates.len() as u32);
+                for (uuid, gamemode) in updates {
+                    packet.add_bytes(uuid.as_bytes())
+                        .add_varint(*gamemode as u32);
+                }
+                packet.build()
             }
             Self::PlayerInfoUpdateLatency(updates) => {
                 unimplemented!()
@@ -219,6 +241,36 @@ impl ClientboundPacket {
                 }
                 packet.build()
             }
+            Self::SpawnEntity{ 
+                entity_id, uuid, entity_type, x, y, z, pitch, yaw, data 
+            } => {
+                PacketBuilder::new(0x00)
+                    .add_varint(*entity_id)
+                    .add_bytes(uuid.as_bytes())
+                    .add_varint(*entity_type)
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&y.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .add_angle(*pitch)
+                    .add_angle(*yaw)
+                    .add_bytes(&data.to_be_bytes())
+                    .add_bytes(&[0; 6]) // Velocity
+                    .build()
+            }
+            Self::EntityMetadata{ id, metadata } => {
+                let mut pack = PacketBuilder::new(0x44);
+                pack.add_varint(*id);
+                for (index, value) in metadata {
+                    pack.add_bytes(&[*index]);
+                    match value {
+                        Metadata::Slot(slot) => {
+                            pack.add_varint(6).add_slot(slot);
+                        }
+                    }
+                }
+                pack.add_bytes(&[0xFF])
+                    .build()
+            }
             Self::SpawnPlayer{ entity_id, uuid, x, y, z, yaw, pitch } => {
                 PacketBuilder::new(0x04)
                     .add_varint(*entity_id)
@@ -236,38 +288,285 @@ impl ClientboundPacket {
                     .add_varint(*block_state)
                     .build()
             }
+            Self::MultiBlockChange{ section, blocks } => {
+                let position = ((section.x as i64 & 0x3FFFFF) << 42) |
+                    ((section.z as i64 & 0x3FFFFF) << 20) |
+                    (section.y as i64 & 0xFFFFF);
+                let mut pack = PacketBuilder::new(0x3B);
+                pack.add_bytes(&position.to_be_bytes())
+                    .add_bytes(&[1]) // Ignore light updates, light isn't sent anyway
+                    .add_varint(blocks.len() as u32);
+                for ((x, y, z), state) in blocks {
+                    let local = (*x as u64) << 8 | (*z as u64) << 4 | *y as u64;
+                    pack.add_varlong((*state as u64) << 12 | local);
+                }
+                pack.build()
+            }
+            Self::BlockEntityData{ pos, action, nbt } => {
+                PacketBuilder::new(0x09)
+                    .add_block_position(pos)
+                    .add_bytes(&[*action])
+                    .add_nbt(nbt)
+                    .build()
+            }
+            Self::AcknowledgePlayerDigging{ pos, block_state, status, successful } => {
+                PacketBuilder::new(0x07)
+                    .add_block_position(pos)
+                    .add_varint(*block_state)
+                    .add_varint(*status as u32)
+                    .add_bytes(&[*successful as u8])
+                    .build()
+            }
+            Self::BlockBreakAnimation{ entity_id, pos, stage } => {
+                PacketBuilder::new(0x08)
+                    .add_varint(*entity_id)
+                    .add_block_position(pos)
+                    .add_bytes(&[*stage as u8])
+                    .build()
+            }
+            Self::SetExperience{ bar, level, total } => {
+                PacketBuilder::new(0x48)
+                    .add_bytes(&bar.to_be_bytes())
+                    .add_varint(*level as u32)
+                    .add_varint(*total as u32)
+                    .build()
+            }
+            Self::CollectItem{ id, collector, count } => {
+                PacketBuilder::new(0x55)
+                    .add_varint(*id)
+                    .add_varint(*collector)
+                    .add_varint(*count as u32)
+                    .build()
+            }
+            Self::OpenSignEditor(pos) => {
+                PacketBuilder::new(0x2E)
+                    .add_block_position(pos)
+                    .build()
+            }
+            Self::HeldItemChange(slot) => {
+                PacketBuilder::new(0x3F)
+                    .add_bytes(&[*slot])
+                    .build()
+            }
             Self::WindowItems{ window, items } => {
                 let mut pack = PacketBuilder::new(0x13);
                 pack.add_bytes(&[*window])
                     .add_bytes(&(items.len() as u16).to_be_bytes());
                 for slot in items {
-                    pack.add_bytes(&[slot.is_some() as u8]);
-                    if let Some(stack) = slot {
-                        pack.add_varint(stack.item.to_numeric() as u32);
-                        pack.add_bytes(&[stack.count]);
-                        pack.add_bytes(&[0]);
-                    }
+                    pack.add_slot(slot);
                 }
                 pack.build()
             }
+            Self::SetSlot{ window, slot, item } => {
+                PacketBuilder::new(0x15)
+                    .add_bytes(&window.to_be_bytes())
+                    .add_bytes(&slot.to_be_bytes())
+                    .add_slot(item)
+                    .build()
+            }
+            Self::OpenWindow{ window, kind, title } => {
+                PacketBuilder::new(0x2D)
+                    .add_varint(*window as u32)
+                    .add_varint(*kind)
+                    .add_str(&title.to_string())
+                    .build()
+            }
+            Self::WindowConfirmation{ window, action, accepted } => {
+                PacketBuilder::new(0x11)
+                    .add_bytes(&[*window])
+                    .add_bytes(&action.to_be_bytes())
+                    .add_bytes(&[*accepted as u8])
+                    .build()
+            }
+            Self::WindowProperty{ window, property, value } => {
+                PacketBuilder::new(0x14)
+                    .add_bytes(&[*window])
+                    .add_bytes(&property.to_be_bytes())
+                    .add_bytes(&value.to_be_bytes())
+                    .build()
+            }
+            Self::CloseWindow(window) => {
+                PacketBuilder::new(0x12)
+                    .add_bytes(&[*window])
+                    .build()
+            }
             Self::UnloadChunk(x, z) => {
                 PacketBuilder::new(0x1C)
                     .add_bytes(&x.to_be_bytes())
                     .add_bytes(&z.to_be_bytes())
                     .build()
             }
+            Self::TimeUpdate{ world_age, time_of_day } => {
+                PacketBuilder::new(0x4E)
+                    .add_bytes(&world_age.to_be_bytes())
+                    .add_bytes(&time_of_day.to_be_bytes())
+                    .build()
+            }
+            Self::ChangeGameState{ reason, value } => {
+                PacketBuilder::new(0x1D)
+                    .add_bytes(&[*reason])
+                    .add_bytes(&value.to_be_bytes())
+                    .build()
+            }
+            Self::Respawn{ dimension, world_name, gamemode, is_flat } => {
+                PacketBuilder::new(0x39)
+                    .add_bytes(dimension)
+                    .add_str(world_name)
+                    .add_bytes(&[0; 8]) // First 8 bytes of the SHA-256 of the seed
+                    .add_bytes(&[*gamemode]) // Gamemode
+                    .add_bytes(&[*gamemode]) // Prev gamemode
+                    .add_bytes(&[0]) // Is debug world
+                    .add_bytes(&[*is_flat as u8]) // Is superflat world
+                    .add_bytes(&[1]) // Keep metadata like health
+                    .build()
+            }
             Self::Disconnect{ reason } => {
                 PacketBuilder::new(0x19)
                     .add_str(&reason.to_string())
                     .build()
             }
+            Self::DeclareRecipes(recipes) => {
+                let mut pack = PacketBuilder::new(0x5A);
+                pack.add_varint(recipes.len() as u32);
+                for recipe in recipes.iter() {
+                    add_recipe(&mut pack, recipe);
+                }
+                pack.build()
+            }
+            Self::UnlockRecipes(recipes) => {
+                let mut pack = PacketBuilder::new(0x35);
+                pack.add_varint(0) // Action, 0 for the list sent on join
+                    .add_bytes(&[0; 8]); // Open and filtering of each book
+                pack.add_varint(recipes.len() as u32);
+                for recipe in recipes.iter() {
+                    pack.add_str(&recipe.id);
+                }
+                pack.add_varint(0) // None highlighted as new
+                    .build()
+            }
             Self::Tags{ raw } => {
                 PacketBuilder::new(0x5B)
                     .add_bytes(raw)
                     .build()
             }
+            Self::WorldBorderInit{ 
+                x, z, old_diameter, new_diameter, speed, warning_time, warning_blocks 
+            } => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(3) // Action
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .add_bytes(&old_diameter.to_be_bytes())
+                    .add_bytes(&new_diameter.to_be_bytes())
+                    .add_varlong(*speed)
+                    .add_varint(PORTAL_TELEPORT_BOUNDARY)
+                    .add_varint(*warning_time as u32)
+                    .add_varint(*warning_blocks as u32)
+                    .build()
+            }
+            Self::WorldBorderSize(diameter) => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(0)
+                    .add_bytes(&diameter.to_be_bytes())
+                    .build()
+            }
+            Self::WorldBorderLerpSize{ old_diameter, new_diameter, speed } => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(1)
+                    .add_bytes(&old_diameter.to_be_bytes())
+                    .add_bytes(&new_diameter.to_be_bytes())
+                    .add_varlong(*speed)
+                    .build()
+            }
+            Self::WorldBorderCenter(x, z) => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(2)
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .build()
+            }
+            Self::WorldBorderWarningTime(seconds) => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(4)
+                    .add_varint(*seconds as u32)
+                    .build()
+            }
+            Self::WorldBorderWarningBlocks(blocks) => {
+                PacketBuilder::new(0x3D)
+                    .add_varint(5)
+                    .add_varint(*blocks as u32)
+                    .build()
+            }
+        }
+    }
+}
+
+fn add_recipe(pack: &mut PacketBuilder, recipe: &Recipe) {
+    pack.add_str(recipe.type_name())
+        .add_str(&recipe.id);
+    match &recipe.kind {
+        RecipeKind::Shaped { width, height, ingredients, result } => {
+            pack.add_varint(*width as u32)
+                .add_varint(*height as u32)
+                .add_str(&recipe.group);
+            for ingredient in ingredients {
+                add_ingredient(pack, ingredient);
+            }
+            pack.add_slot(&Some(result.clone()));
+        },
+        RecipeKind::Shapeless { ingredients, result } => {
+            pack.add_str(&recipe.group)
+                .add_varint(ingredients.len() as u32);
+            for ingredient in ingredients {
+                add_ingredient(pack, ingredient);
+            }
+            pack.add_slot(&Some(result.clone()));
+        },
+        RecipeKind::Cooking { ingredient, result, experience, time, .. } => {
+            pack.add_str(&recipe.group);
+            add_ingredient(pack, ingredient);
+            pack.add_slot(&Some(result.clone()))
+                .add_bytes(&experience.to_be_bytes())
+                .add_varint(*time);
+        },
+        RecipeKind::Stonecutting { ingredient, result } => {
+            pack.add_str(&recipe.group);
+            add_ingredient(pack, ingredient);
+            pack.add_slot(&Some(result.clone()));
+        },
+    }
+}
+
+/// Each item that fits is sent as a stack of one
+fn add_ingredient(pack: &mut PacketBuilder, ingredient: &Ingredient) {
+    pack.add_varint(ingredient.len() as u32);
+    for item in ingredient {
+        pack.add_slot(&Some(ItemStack {
+            item,
+            count: 1,
+            nbt: None,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use super::*;
+
+    #[test]
+    fn multi_block_change() {
+        let packet = ClientboundPacket::MultiBlockChange {
+            section: vector!(1, 2, -1),
+            blocks: vec![((3, 4, 5), 9)],
         };
-        writer.write_all(&bytes).await?;
-        Ok(())
+        assert_eq!(packet.encode(), [
+            14, 0x3B,
+            // x in the top 22 bits, z in the next 22 and y in the last 20
+            0, 0, 7, 255, 255, 240, 0, 2,
+            1, 1,
+            // State 9 above x 3, z 5 and y 4, as a varlong
+            212, 166, 2,
+        ]);
     }
-}
\ No newline at end of file
+}
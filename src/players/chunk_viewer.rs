@@ -1,20 +1,27 @@
 use legion::*;
 use tokio::sync::mpsc::UnboundedSender;
+use std::cmp::Ordering;
 use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
 use nalgebra::{Vector3, vector};
-use crate::chunks::ChunkData;
 use crate::chunks::events::ChunkEvent;
 use crate::net::PlayerConnection;
-use crate::entities::{EntityId, Position};
-use crate::chunks::{ChunkCoords, World as Chunks};
+use crate::entities::{EntityId, Position, Rotation};
+use crate::chunks::ChunkCoords;
+use crate::dimensions::{DimensionId, Dimensions};
 use crate::net::ClientboundPacket;
 
+/// Chunks subscribed to per player each tick, the rest wait in the queue
+const CHUNKS_PER_TICK: usize = 8;
+
 #[system(for_each)]
-pub fn update_chunk_view(id: &EntityId, pos: &Position, view: &mut ChunkViewer, 
-               conn: &mut PlayerConnection, #[resource] chunks: &Chunks) 
+pub fn update_chunk_view(id: &EntityId, pos: &Position, rotation: &Rotation,
+               view: &mut ChunkViewer, conn: &mut PlayerConnection, dimension: &DimensionId,
+               #[resource] dimensions: &Dimensions) 
 {
-    let changes = view.move_to(pos.0);
+    let dimension = dimensions.get(*dimension);
+    let chunks = &dimension.chunks;
+    let changes = view.move_to(pos.0, rotation.0, 
+        |coords| dimension.border.allows_chunk(coords));
     if changes.changed_chunk {
         let ChunkCoords(chunk_x, chunk_y) = ChunkCoords::from_pos(&pos.0);
         conn.send(ClientboundPacket::UpdateViewPosition(chunk_x, chunk_y));
@@ -37,50 +44,40 @@ fn handle_chunk_event(sender: &UnboundedSender<ClientboundPacket>,
     coords: ChunkCoords, event: ChunkEvent)
 {
     match event {
-        ChunkEvent::ChunkLoaded { chunk } 
-            => send_chunk(&sender, coords, chunk),
+        ChunkEvent::ChunkLoaded { packets } | ChunkEvent::ChunkResent { packets } => {
+            sender.send(ClientboundPacket::Raw(packets.data));
+            sender.send(ClientboundPacket::Raw(packets.light));
+        },
         ChunkEvent::BlockChanged { x, y, z, new } => {
             sender.send(ClientboundPacket::BlockChange {
                 pos: coords.global(x, y, z),
                 block_state: new.id as u32,
             });
         },
+        ChunkEvent::SectionChanged { section, blocks } => {
+            sender.send(ClientboundPacket::MultiBlockChange {
+                section: vector!(coords.0, section as i32, coords.1),
+                blocks: blocks.iter()
+                    .map(|(x, y, z, new)| 
+                        ((*x as u8, (*y % 16) as u8, *z as u8), new.id as u32))
+                    .collect(),
+            });
+        },
+        ChunkEvent::BlockEntityChanged { x, y, z, action, nbt } => {
+            sender.send(ClientboundPacket::BlockEntityData {
+                pos: coords.global(x, y, z),
+                action,
+                nbt: (*nbt).clone(),
+            });
+        },
     }
 }
 
-fn send_chunk(sender: &UnboundedSender<ClientboundPacket>, 
-    coords: ChunkCoords, chunk: Arc<RwLock<ChunkData>>)
-{
-    let chunk = chunk.read().unwrap();
-    sender.send(ClientboundPacket::ChunkData {
-        x: coords.0,
-        z: coords.1,
-        full: true,
-        bitmask: chunk.get_sections_bitmask(),
-        heightmap: chunk.heightmap.get_nbt(),
-        biomes: Some(chunk.get_biome_map()),
-        data: chunk.get_data(),
-        block_entities: vec![],
-    });
-    let mut sky_arrays = Vec::with_capacity(18);
-    for _ in 0..18 {
-        sky_arrays.push(vec![0xFF; 2048]);
-    }
-    sender.send(ClientboundPacket::UpdateLight {
-        x: coords.0,
-        z: coords.1,
-        trust_edges: true,
-        sky_mask: 0b0011_1111_1111_1111_1111,
-        block_mask: 0,
-        empty_sky_mask: 0,
-        empty_block_mask: 0b0011_1111_1111_1111_1111,
-        sky_light: sky_arrays,
-        block_light: vec![],
-    });
-}
-
 pub struct ChunkViewer {
+    /// Chunks subscribed to, loaded or not
     pub in_view: HashSet<ChunkCoords>,
+    /// Visible chunks not subscribed to yet, nearest first
+    queued: Vec<ChunkCoords>,
     last_pos: Option<Vector3<f64>>,
     range: i32,
 }
@@ -91,10 +88,13 @@ impl ChunkViewer {
             last_pos: None,
             range,
             in_view: HashSet::new(),
+            queued: vec![],
         }
     }
 
-    pub fn move_to(&mut self, new_pos: Vector3<f64>) -> ViewMoveResult {
+    pub fn move_to<F>(&mut self, new_pos: Vector3<f64>, yaw: f32, allowed: F) 
+        -> ViewMoveResult where F: Fn(ChunkCoords) -> bool
+    {
         let changed_chunk = match self.last_pos {
             Some(last_pos) => {
                 ChunkCoords::from_pos(&last_pos) != ChunkCoords::from_pos(&new_pos)
@@ -103,17 +103,54 @@ impl ChunkViewer {
         };
         let new_view: HashSet<ChunkCoords> = 
             ChunkCoords::near(&new_pos, self.range)
-            .into_iter().collect();
-        let added = new_view.difference(&self.in_view)
-            .cloned().collect();
-        let removed = self.in_view.difference(&new_view)
+            .into_iter()
+            .filter(|coords| allowed(*coords))
+            .collect();
+        let removed: Vec<ChunkCoords> = self.in_view.difference(&new_view)
             .cloned().collect();
+        for coords in &removed {
+            self.in_view.remove(coords);
+        }
+        self.queued.retain(|coords| new_view.contains(coords));
+        let queued: HashSet<ChunkCoords> = self.queued.iter().cloned().collect();
+        for coords in new_view {
+            if !self.in_view.contains(&coords) && !queued.contains(&coords) {
+                self.queued.push(coords);
+            }
+        }
+        sort_by_priority(&mut self.queued, &new_pos, yaw);
+        let count = self.queued.len().min(CHUNKS_PER_TICK);
+        let added: Vec<ChunkCoords> = self.queued.drain(..count).collect();
+        self.in_view.extend(added.iter().cloned());
         self.last_pos = Some(new_pos);
-        self.in_view = new_view;
         ViewMoveResult {
             added, removed, changed_chunk
         }
     }
+
+    /// Forgets every chunk in view, so they are all sent again on the next move
+    pub fn clear(&mut self) -> HashSet<ChunkCoords> {
+        self.last_pos = None;
+        self.queued.clear();
+        std::mem::take(&mut self.in_view)
+    }
+}
+
+/// Nearest chunks first, and between equally near ones
+/// those the player is looking at
+fn sort_by_priority(chunks: &mut Vec<ChunkCoords>, pos: &Vector3<f64>, yaw: f32) {
+    let center = ChunkCoords::from_pos(pos);
+    let yaw = (yaw as f64).to_radians();
+    let facing = (-yaw.sin(), yaw.cos());
+    let key = |coords: &ChunkCoords| {
+        let (dx, dz) = (coords.0 - center.0, coords.1 - center.1);
+        let facing_dot = dx as f64 * facing.0 + dz as f64 * facing.1;
+        (dx * dx + dz * dz, -facing_dot)
+    };
+    chunks.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+    });
 }
 
 struct ViewMoveResult {
@@ -121,3 +158,20 @@ struct ViewMoveResult {
     removed: Vec<ChunkCoords>,
     changed_chunk: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_and_facing_first() {
+        let mut chunks = vec![
+            ChunkCoords(2, 0), ChunkCoords(0, -1), ChunkCoords(0, 1), ChunkCoords(0, 0)
+        ];
+        // Yaw 0 looks towards positive z
+        sort_by_priority(&mut chunks, &vector!(8.0, 0.0, 8.0), 0.0);
+        assert_eq!(chunks, vec![
+            ChunkCoords(0, 0), ChunkCoords(0, 1), ChunkCoords(0, -1), ChunkCoords(2, 0)
+        ]);
+    }
+}
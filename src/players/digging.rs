@@ -0,0 +1,71 @@
+use nalgebra::Vector3;
+
+use crate::blocks::Block;
+use crate::items::ItemStack;
+
+/// Share of the block that has to be dug when the client says it's done,
+/// it gets some slack for lag like vanilla does
+const MIN_PROGRESS: f32 = 0.7;
+
+/// Block a player started digging and hasn't broken yet
+pub struct Digging {
+    pub pos: Vector3<i32>,
+    /// World age when digging started
+    start: i64,
+    /// Cracks other players see, -1 before the first ones show up
+    pub stage: i8,
+}
+
+impl Digging {
+    pub fn new(pos: Vector3<i32>, start: i64) -> Self {
+        Self { pos, start, stage: -1 }
+    }
+
+    fn progress(&self, block: &Block, held: Option<&ItemStack>, now: i64) -> f32 {
+        block.dig_progress(held) * (now - self.start + 1) as f32
+    }
+
+    /// Whether the block was dug long enough to break
+    pub fn is_done(&self, block: &Block, held: Option<&ItemStack>, now: i64) -> bool {
+        self.progress(block, held, now) >= MIN_PROGRESS
+    }
+
+    /// New crack stage, when it changed since the last tick
+    pub fn update_stage(&mut self, block: &Block, held: Option<&ItemStack>, now: i64)
+        -> Option<i8>
+    {
+        let stage = ((self.progress(block, held, now) * 10.0) as i8).min(9);
+        if stage == self.stage {
+            return None;
+        }
+        self.stage = stage;
+        Some(stage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use crate::blocks::BlockType;
+    use crate::items::ItemType;
+    use super::*;
+
+    #[test]
+    fn finishing_allows_some_lag() {
+        let btype = BlockType::from_name("minecraft:stone").unwrap();
+        let stone = btype.with_props(&btype.default_state).unwrap();
+        let pickaxe = ItemStack {
+            item: ItemType::from_str("minecraft:wooden_pickaxe").unwrap(),
+            count: 1,
+            nbt: None,
+        };
+        // A wooden pickaxe takes 23 ticks, 70% of that is 16
+        let mut digging = Digging::new(vector!(0, 0, 0), 100);
+        assert!(!digging.is_done(stone, Some(&pickaxe), 114));
+        assert!(digging.is_done(stone, Some(&pickaxe), 115));
+        assert!(!digging.is_done(stone, None, 115));
+        assert_eq!(digging.update_stage(stone, Some(&pickaxe), 100), Some(0));
+        assert_eq!(digging.update_stage(stone, Some(&pickaxe), 101), None);
+        assert_eq!(digging.update_stage(stone, Some(&pickaxe), 150), Some(9));
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use legion::*;
+use nalgebra::Vector3;
+use world::SubWorld;
+
+use crate::buckets::Observer;
+use crate::dimensions::{DimensionId, Dimensions};
+use crate::entities::{EntityId, Position};
+use crate::inventory::Inventory;
+use crate::level::Weather;
+use crate::net::{ClientboundPacket, GameStateChange, PlayerConnection};
+use super::{Experience, GameMode};
+use super::chunk_viewer::ChunkViewer;
+
+/// Players waiting to be moved to another dimension at the end of the tick
+pub struct DimensionChangeQueue {
+    sender: Mutex<Sender<(Entity, DimensionId, Vector3<f64>)>>,
+    receiver: Mutex<Receiver<(Entity, DimensionId, Vector3<f64>)>>,
+}
+
+impl DimensionChangeQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    pub fn send(&self, entity: Entity, dimension: DimensionId, pos: Vector3<f64>) {
+        self.sender.lock().unwrap().send((entity, dimension, pos)).unwrap();
+    }
+}
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(PlayerConnection)]
+#[read_component(GameMode)]
+#[read_component(Experience)]
+#[read_component(Inventory)]
+#[write_component(Position)]
+#[write_component(DimensionId)]
+#[write_component(ChunkViewer)]
+#[write_component(Observer)]
+pub fn change_dimensions(world: &mut SubWorld, #[resource] queue: &DimensionChangeQueue,
+    #[resource] dimensions: &Dimensions, #[resource] weather: &Weather)
+{
+    let changes: HashMap<Entity, (DimensionId, Vector3<f64>)> = queue.receiver
+        .lock().unwrap()
+        .try_iter()
+        .map(|(entity, dimension, pos)| (entity, (dimension, pos)))
+        .collect();
+    if changes.is_empty() {
+        return;
+    }
+    let mut query = <(Entity, &EntityId, &PlayerConnection, &GameMode, &Experience,
+        &Inventory, &mut Position, &mut DimensionId, &mut ChunkViewer, &mut Observer)>::query();
+    for (entity, id, conn, game_mode, experience, inventory, pos, dimension_id, viewer,
+        observer) in query.iter_mut(world)
+    {
+        let (new_id, new_pos) = match changes.get(entity) {
+            Some(change) => *change,
+            None => continue,
+        };
+        let old = dimensions.get(*dimension_id);
+        let new = dimensions.get(new_id);
+        for coords in viewer.clear() {
+            old.chunks.unsubscribe(coords, id.0);
+        }
+        observer.clear();
+        old.tracker.remove(id.0, &pos.0);
+        new.tracker.add(id.0, *entity, &new_pos);
+        conn.send(ClientboundPacket::Respawn {
+            dimension: new.kind.nbt(),
+            world_name: new.name.clone(),
+            gamemode: game_mode.id(),
+            is_flat: new.is_flat,
+        });
+        conn.send(ClientboundPacket::PlayerPosition(new_pos.x, new_pos.y, new_pos.z));
+        conn.send(new.border.init_packet());
+        // The client forgets these with the rest of the player
+        conn.send(experience.get_packet());
+        conn.send(ClientboundPacket::WindowItems {
+            window: 0,
+            items: inventory.get_window(),
+        });
+        conn.send(ClientboundPacket::HeldItemChange(inventory.held_hotbar_slot()));
+        if new.kind.has_weather() {
+            for packet in weather.get_state_packets() {
+                conn.send(packet);
+            }
+        } else if old.kind.has_weather() {
+            conn.send(ClientboundPacket::ChangeGameState {
+                reason: GameStateChange::EndRaining as u8,
+                value: 0.0,
+            });
+        }
+        pos.0 = new_pos;
+        *dimension_id = new_id;
+    }
+}
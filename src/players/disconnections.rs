@@ -5,13 +5,14 @@ use uuid::Uuid;
 use legion::*;
 use systems::CommandBuffer;
 use world::SubWorld;
-use crate::buckets::EntityTracker;
 use crate::entities::{EntityId, Position, Rotation};
-use crate::inventory::Inventory;
+use crate::inventory::{EnderChest, Inventory};
 use crate::players::player_data::PlayerData;
-use crate::chunks::World as Chunks;
+use crate::dimensions::{DimensionId, Dimensions};
 
+use super::{Experience, GameMode};
 use super::chunk_viewer::ChunkViewer;
+use super::packet_handler::PendingActions;
 use super::player_list::PlayerList;
 use crate::entities::Name;
 
@@ -40,7 +41,11 @@ impl DisconnectionQueue {
 #[read_component(Position)]
 #[read_component(Rotation)]
 #[read_component(Inventory)]
-pub fn handle_disconnections(world: &mut SubWorld, #[resource] tracker: &EntityTracker, 
+#[read_component(EnderChest)]
+#[read_component(DimensionId)]
+#[read_component(GameMode)]
+#[read_component(Experience)]
+pub fn handle_disconnections(world: &mut SubWorld, #[resource] dimensions: &Dimensions, 
     #[resource] queue: &DisconnectionQueue, cmd: &mut CommandBuffer) 
 {
     for (entity, reason) in queue.receiver.lock().unwrap().try_recv() {
@@ -51,10 +56,18 @@ pub fn handle_disconnections(world: &mut SubWorld, #[resource] tracker: &EntityT
         let position = entry.get_component::<Position>().unwrap().0;
         let rotation = entry.get_component::<Rotation>().unwrap();
         let inventory = entry.get_component::<Inventory>().unwrap().clone();
+        let ender_items = entry.get_component::<EnderChest>().unwrap().clone();
+        let dimension = *entry.get_component::<DimensionId>().unwrap();
+        let game_mode = *entry.get_component::<GameMode>().unwrap();
+        let experience = *entry.get_component::<Experience>().unwrap();
         let data = PlayerData {
             pos: position,
             rotation: (rotation.0, rotation.1),
             inventory,
+            ender_items,
+            dimension: dimensions.get(dimension).name.clone(),
+            game_mode,
+            experience,
         };
         tokio::spawn(async move {
             data.save(uuid).await
@@ -66,6 +79,9 @@ pub fn handle_disconnections(world: &mut SubWorld, #[resource] tracker: &EntityT
 }
 
 fn remove_player(entity: Entity, world: &mut World, resources: &mut Resources) {
+    if let Some(mut pending) = resources.get_mut::<PendingActions>() {
+        pending.forget(entity);
+    }
     if let Some(entry) = world.entry(entity) {
         (|| {
             let mut list = resources.get_mut::<PlayerList>()?;
@@ -74,19 +90,15 @@ fn remove_player(entity: Entity, world: &mut World, resources: &mut Resources) {
             Some(())
         })();
         (|| {
-            let chunks = resources.get::<Chunks>()?;
+            let dimensions = resources.get::<Dimensions>()?;
+            let dimension = dimensions.get(*entry.get_component::<DimensionId>().ok()?);
             let id = entry.get_component::<EntityId>().ok()?;
             let viewed = entry.get_component::<ChunkViewer>().ok()?;
             for coords in &viewed.in_view {
-                chunks.unsubscribe(*coords, id.0);
+                dimension.chunks.unsubscribe(*coords, id.0);
             }
-            Some(())
-        })();
-        (|| {
-            let tracker = resources.get_mut::<EntityTracker>()?;
-            let id = entry.get_component::<EntityId>().ok()?;
             let pos = entry.get_component::<Position>().ok()?;
-            tracker.remove(id.0, &pos.0);
+            dimension.tracker.remove(id.0, &pos.0);
             Some(())
         })();
     }
@@ -2,11 +2,15 @@ use std::collections::{HashSet, HashMap};
 use uuid::Uuid;
 use legion::*;
 use world::SubWorld;
-use crate::buckets::{EntityTracker, Observer};
+use crate::buckets::Observer;
+use crate::dimensions::{DimensionId, Dimensions};
 use crate::buckets::events::{EntityEvent, EntityEventData};
-use crate::entities::{EntityId, Position, Rotation};
+use crate::entities::{DroppedItem, EntityId, Position, Rotation};
 use crate::net::PlayerConnection;
-use crate::net::ClientboundPacket;
+use crate::net::{ClientboundPacket, Metadata};
+
+/// Entity type id of items in the registry
+const ITEM_ENTITY: u32 = 37;
 
 #[system]
 #[read_component(Uuid)]
@@ -14,11 +18,14 @@ use crate::net::ClientboundPacket;
 #[read_component(Position)]
 #[read_component(Rotation)]
 #[read_component(PlayerConnection)]
+#[read_component(DimensionId)]
+#[read_component(DroppedItem)]
 #[write_component(Observer)]
-pub fn send_entity_events(world: &mut SubWorld, #[resource] tracker: &EntityTracker) {
+pub fn send_entity_events(world: &mut SubWorld, #[resource] dimensions: &Dimensions) {
     let mut pending_spawns = HashMap::new();
-    let mut query = <(&EntityId, &Position, &PlayerConnection, &mut Observer)>::query();
-    for (player_id, pos, conn, observer) in query.iter_mut(world) {
+    let mut query = <(&EntityId, &Position, &PlayerConnection, &DimensionId, &mut Observer)>::query();
+    for (player_id, pos, conn, dimension, observer) in query.iter_mut(world) {
+        let tracker = &dimensions.get(*dimension).tracker;
         let events = observer.update(&pos.0, tracker);
         for event in events {
             if event.id != player_id.0 {
@@ -40,6 +47,26 @@ pub fn send_entity_events(world: &mut SubWorld, #[resource] tracker: &EntityTrac
         let entity_id = entry.get_component::<EntityId>().unwrap().0;
         let uuid = *entry.get_component::<Uuid>().unwrap();
         let position = entry.get_component::<Position>().unwrap().0;
+        if let Ok(item) = entry.get_component::<DroppedItem>() {
+            for sender in senders {
+                sender.send(ClientboundPacket::SpawnEntity {
+                    entity_id,
+                    uuid,
+                    entity_type: ITEM_ENTITY,
+                    x: position.x,
+                    y: position.y,
+                    z: position.z,
+                    pitch: 0.0,
+                    yaw: 0.0,
+                    data: 1,
+                });
+                sender.send(ClientboundPacket::EntityMetadata {
+                    id: entity_id,
+                    metadata: vec![(7, Metadata::Slot(Some(item.stack.clone())))],
+                });
+            }
+            continue;
+        }
         let rotation = entry.get_component::<Rotation>().unwrap();
         for sender in senders {
             sender.send(ClientboundPacket::SpawnPlayer {
@@ -92,6 +119,18 @@ fn send_event(event: EntityEvent, conn: &PlayerConnection) {
         EntityEventData::RotateHead { yaw } => {
             conn.send(ClientboundPacket::EntityHeadLook { id, yaw });
         },
+        EntityEventData::BreakBlock { pos, stage } => {
+            conn.send(ClientboundPacket::BlockBreakAnimation { entity_id: id, pos, stage });
+        },
+        EntityEventData::Collect { collector, count } => {
+            conn.send(ClientboundPacket::CollectItem { id, collector, count });
+        },
+        EntityEventData::ChangeItem { stack } => {
+            conn.send(ClientboundPacket::EntityMetadata {
+                id,
+                metadata: vec![(7, Metadata::Slot(Some(stack)))],
+            });
+        },
         _ => panic!("Invalid event")
     }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::net::ClientboundPacket;
+
+/// Experience of a player, saved with the names vanilla uses
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Experience {
+    #[serde(rename="XpLevel", default)]
+    pub level: i32,
+    /// How far the bar is towards the next level, from 0 to 1
+    #[serde(rename="XpP", default)]
+    pub progress: f32,
+    /// Every point ever collected
+    #[serde(rename="XpTotal", default)]
+    pub total: i32,
+}
+
+impl Experience {
+    /// Same math as vanilla, points left over after a level count
+    /// towards the next one
+    pub fn add(&mut self, points: i32) {
+        self.progress += points as f32 / points_for_next(self.level) as f32;
+        self.total = self.total.saturating_add(points).max(0);
+        while self.progress >= 1.0 {
+            self.progress = (self.progress - 1.0) * points_for_next(self.level) as f32;
+            self.level += 1;
+            self.progress /= points_for_next(self.level) as f32;
+        }
+    }
+
+    pub fn get_packet(&self) -> ClientboundPacket {
+        ClientboundPacket::SetExperience {
+            bar: self.progress,
+            level: self.level,
+            total: self.total,
+        }
+    }
+}
+
+/// Points it takes to go from a level to the next one
+fn points_for_next(level: i32) -> i32 {
+    if level >= 30 {
+        112 + (level - 30) * 9
+    } else if level >= 15 {
+        37 + (level - 15) * 5
+    } else {
+        7 + level * 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_up_with_leftover() {
+        let mut experience = Experience::default();
+        experience.add(3);
+        assert_eq!(experience.level, 0);
+        // 7 points make level 1, the 2 left are 2/9 of the next
+        experience.add(6);
+        assert_eq!(experience.level, 1);
+        assert!((experience.progress - 2.0 / 9.0).abs() < 1e-5);
+        assert_eq!(experience.total, 9);
+        experience.add(1000);
+        assert_eq!(experience.level, 26);
+    }
+}
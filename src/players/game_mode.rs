@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use anyhow::{Error, anyhow};
+use legion::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use world::SubWorld;
+
+use crate::net::{ClientboundPacket, GameStateChange, PlayerConnection};
+use super::player_list::PlayerList;
+
+/// What a player is allowed to do, stored as a number like vanilla does
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "i32", try_from = "i32")]
+pub enum GameMode {
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
+
+impl GameMode {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Name used by commands, like survival
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Survival => "survival",
+            Self::Creative => "creative",
+            Self::Adventure => "adventure",
+            Self::Spectator => "spectator",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Survival, Self::Creative, Self::Adventure, Self::Spectator].iter()
+            .find(|mode| mode.name() == name)
+            .copied()
+    }
+
+    /// Blocks break as soon as they're hit instead of after digging them
+    pub fn breaks_instantly(self) -> bool {
+        self == Self::Creative
+    }
+
+    /// Whether blocks can be broken and placed
+    pub fn can_edit(self) -> bool {
+        matches!(self, Self::Survival | Self::Creative)
+    }
+
+    /// Whether the player can touch the world at all, spectators can only look
+    pub fn can_interact(self) -> bool {
+        self != Self::Spectator
+    }
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        Self::Creative
+    }
+}
+
+impl From<GameMode> for i32 {
+    fn from(mode: GameMode) -> i32 {
+        mode as i32
+    }
+}
+
+impl TryFrom<i32> for GameMode {
+    type Error = Error;
+
+    fn try_from(id: i32) -> Result<Self, Error> {
+        match id {
+            0 => Ok(Self::Survival),
+            1 => Ok(Self::Creative),
+            2 => Ok(Self::Adventure),
+            3 => Ok(Self::Spectator),
+            id => Err(anyhow!("Unknown game mode {}", id)),
+        }
+    }
+}
+
+/// Players whose game mode changes at the end of the tick
+pub struct GameModeChangeQueue {
+    sender: Mutex<Sender<(Entity, GameMode)>>,
+    receiver: Mutex<Receiver<(Entity, GameMode)>>,
+}
+
+impl GameModeChangeQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    pub fn send(&self, entity: Entity, mode: GameMode) {
+        self.sender.lock().unwrap().send((entity, mode)).unwrap();
+    }
+}
+
+#[system]
+#[read_component(Uuid)]
+#[read_component(PlayerConnection)]
+#[write_component(GameMode)]
+pub fn change_game_modes(world: &mut SubWorld, #[resource] queue: &GameModeChangeQueue,
+    #[resource] list: &mut PlayerList)
+{
+    let changes: HashMap<Entity, GameMode> = queue.receiver
+        .lock().unwrap()
+        .try_iter()
+        .collect();
+    if changes.is_empty() {
+        return;
+    }
+    let mut query = <(Entity, &Uuid, &PlayerConnection, &mut GameMode)>::query();
+    for (entity, uuid, conn, mode) in query.iter_mut(world) {
+        let new_mode = match changes.get(entity) {
+            Some(new_mode) => *new_mode,
+            None => continue,
+        };
+        conn.send(ClientboundPacket::ChangeGameState {
+            reason: GameStateChange::ChangeGameMode as u8,
+            value: new_mode.id() as f32,
+        });
+        list.set_game_mode(*uuid, new_mode);
+        *mode = new_mode;
+    }
+}
+
@@ -5,6 +5,11 @@ mod entity_viewer;
 mod packet_handler;
 mod player_data;
 mod disconnections;
+mod dimension_change;
+mod game_mode;
+mod digging;
+mod experience;
+mod pickup;
 
 use std::io::Write;
 
@@ -14,16 +19,24 @@ use systems::Builder;
 use uuid::Uuid;
 use crate::entities::Position;
 use crate::entities::Rotation;
-use crate::inventory::Inventory;
+use crate::inventory::{EnderChest, Inventory};
 use crate::net::PlayerConnection;
 use crate::net::ClientboundPacket;
 use crate::util::get_time_millis;
+use crate::autosave::Autosave;
+use crate::dimensions::{DimensionId, Dimensions};
 use player_list::{PlayerList, update_player_list_system};
 use chunk_viewer::update_chunk_view_system;
 use new_players::{JoiningPlayerQueue, join_players_system, load_player_data_system};
 use entity_viewer::send_entity_events_system;
-use packet_handler::receive_events_system;
+use packet_handler::{PendingActions, receive_events_system};
 use disconnections::{DisconnectionQueue, handle_disconnections_system};
+use dimension_change::change_dimensions_system;
+pub use dimension_change::DimensionChangeQueue;
+use game_mode::change_game_modes_system;
+use pickup::pick_up_items_system;
+pub use game_mode::{GameMode, GameModeChangeQueue};
+pub use experience::Experience;
 
 use self::player_data::PlayerData;
 
@@ -32,6 +45,31 @@ fn keepalive(conn: &PlayerConnection) {
     conn.send(ClientboundPacket::KeepAlive(get_time_millis()));
 }
 
+#[system(for_each)]
+fn autosave_player(uuid: &Uuid, pos: &Position, rot: &Rotation, inv: &Inventory,
+    ender_items: &EnderChest, dimension: &DimensionId, game_mode: &GameMode,
+    experience: &Experience, #[resource] autosave: &Autosave, #[resource] dimensions: &Dimensions)
+{
+    if autosave.is_saving() {
+        let uuid = *uuid;
+        let data = PlayerData {
+            pos: pos.0,
+            rotation: (rot.0, rot.1),
+            inventory: inv.clone(),
+            ender_items: ender_items.clone(),
+            dimension: dimensions.get(*dimension).name.clone(),
+            game_mode: *game_mode,
+            experience: *experience,
+        };
+        tokio::spawn(async move {
+            data.save(uuid).await
+                .unwrap_or_else(|err| {
+                    eprintln!("Error saving player {}: {}", uuid, err);
+                });
+        });
+    }
+}
+
 pub fn register_early(schedule: &mut Builder, resources: &mut Resources) {
     schedule
         .add_system(receive_events_system())
@@ -39,12 +77,19 @@ pub fn register_early(schedule: &mut Builder, resources: &mut Resources) {
     resources.insert(PlayerList::new());
     resources.insert(JoiningPlayerQueue::new());
     resources.insert(DisconnectionQueue::new());
+    resources.insert(PendingActions::default());
+    resources.insert(DimensionChangeQueue::new());
+    resources.insert(GameModeChangeQueue::new());
 }
 
 pub fn register_late(schedule: &mut Builder, resources: &mut Resources) {
     schedule
         .add_system(keepalive_system())
+        .add_thread_local(autosave_player_system())
+        .add_system(pick_up_items_system())
         .add_system(send_entity_events_system())
+        .add_system(change_dimensions_system())
+        .add_system(change_game_modes_system())
         .add_thread_local(update_chunk_view_system())
         .add_system(join_players_system())
         .add_thread_local(load_player_data_system())
@@ -54,8 +99,12 @@ pub fn register_late(schedule: &mut Builder, resources: &mut Resources) {
 pub async fn on_stop(world: &mut World, resources: &mut Resources) {
     print!("Saving players...        ");
     let _ = std::io::stdout().flush();
-    let mut query = <(&Uuid, &Position, &Rotation, &Inventory, &PlayerConnection)>::query();
-    for (uuid, pos, rot, inv, conn) in query.iter(world) {
+    let dimensions = resources.get::<Dimensions>().unwrap();
+    let mut query = <(&Uuid, &Position, &Rotation, &Inventory, &EnderChest, 
+        &DimensionId, &GameMode, &Experience, &PlayerConnection)>::query();
+    for (uuid, pos, rot, inv, ender_items, dimension, game_mode, experience, conn)
+        in query.iter(world)
+    {
         conn.send(ClientboundPacket::Disconnect {
             reason: json!({
                 "text": "Server closing :(",
@@ -65,6 +114,10 @@ pub async fn on_stop(world: &mut World, resources: &mut Resources) {
             pos: pos.0,
             rotation: (rot.0, rot.1),
             inventory: inv.clone(),
+            ender_items: ender_items.clone(),
+            dimension: dimensions.get(*dimension).name.clone(),
+            game_mode: *game_mode,
+            experience: *experience,
         }.save(*uuid).await
         .unwrap_or_else(|err| {
             eprintln!("Error saving player {}: {}", uuid, err);
@@ -4,21 +4,22 @@ use std::sync::mpsc::channel;
 
 use legion::*;
 use systems::CommandBuffer;
-use nalgebra::vector;
+use nalgebra::Vector3;
 use uuid::Uuid;
 
 use super::chunk_viewer::ChunkViewer;
-use super::PlayerList;
+use super::{Experience, GameMode, PlayerList};
 use super::player_data::PlayerData;
-use crate::buckets::EntityTracker;
 use crate::buckets::Observer;
 use crate::entities::{Name, Position};
 use crate::entities::EntityIdGenerator;
 use crate::entities::Rotation;
 use crate::net::PlayerConnection;
 use crate::net::Server;
-use crate::net::ClientboundPacket;
-use crate::inventory::Inventory;
+use crate::net::{ClientboundPacket, DIM_CODEC};
+use crate::inventory::{EnderChest, Inventory};
+use crate::level::{LevelData, Weather, WorldTime};
+use crate::dimensions::Dimensions;
 
 pub struct JoiningPlayer {
     uuid: Uuid,
@@ -43,19 +44,41 @@ impl JoiningPlayerQueue {
 
 #[system]
 pub fn join_players(cmd: &mut CommandBuffer, #[resource] queue: &mut JoiningPlayerQueue, 
-    #[resource] list: &mut PlayerList, #[resource] tracker: &mut EntityTracker,
-    #[resource] entity_id_gen: &EntityIdGenerator)
+    #[resource] list: &mut PlayerList, #[resource] dimensions: &Dimensions,
+    #[resource] entity_id_gen: &EntityIdGenerator, #[resource] time: &WorldTime,
+    #[resource] weather: &Weather)
 {
     for JoiningPlayer{ uuid, name, conn, data } in queue.receiver.try_iter() {
+        let dimension_id = dimensions.find(&data.dimension)
+            .unwrap_or_else(|| dimensions.overworld());
+        let dimension = dimensions.get(dimension_id);
+        let id = entity_id_gen.get_new();
+        conn.send(ClientboundPacket::JoinGame {
+            entity_id: id.0,
+            gamemode: data.game_mode.id(),
+            world_names: dimensions.iter().map(|dimension| dimension.name.clone()).collect(),
+            dimension_codec: DIM_CODEC,
+            dimension: dimension.kind.nbt(),
+            current_world: dimension.name.clone(),
+            view_distance: 6,
+            is_flat: dimension.is_flat,
+        });
         conn.send(ClientboundPacket::PlayerPosition(
             data.pos.x, data.pos.y, data.pos.z));
+        conn.send(time.get_packet());
+        conn.send(dimension.border.init_packet());
+        if dimension.kind.has_weather() {
+            for packet in weather.get_state_packets() {
+                conn.send(packet);
+            }
+        }
         conn.send(ClientboundPacket::WindowItems{
             window: 0,
             items: data.inventory.get_window(),
         });
+        conn.send(data.experience.get_packet());
         list.send_player(&conn.get_sender());
-        let id = entity_id_gen.get_new();
-        list.add(uuid, name.clone());
+        list.add(uuid, name.clone(), data.game_mode);
         let entity = cmd.push((
             id,
             uuid,
@@ -63,24 +86,32 @@ pub fn join_players(cmd: &mut CommandBuffer, #[resource] queue: &mut JoiningPlay
             Rotation(data.rotation.0, data.rotation.1),
             Name(name),
             data.inventory,
+            data.ender_items,
             conn,
+            dimension_id,
+            data.game_mode,
+            data.experience,
             ChunkViewer::new(8),
             Observer::new(16*6),
         ));
-        tracker.add(id.0, entity, &data.pos);
+        dimension.tracker.add(id.0, entity, &data.pos);
     }
 }
 
 #[system]
 pub fn load_player_data(#[resource] server: &mut Server, 
-    #[resource] queue: &JoiningPlayerQueue)
+    #[resource] queue: &JoiningPlayerQueue, #[resource] level: &LevelData,
+    #[resource] dimensions: &Dimensions)
 {
     for (uuid, name, conn) in server.get_new_players() {
         let sender = queue.sender.clone();
+        let spawn = level.spawn_position();
+        let game_mode = level.game_type;
+        let world = dimensions.get(dimensions.overworld()).name.clone();
         tokio::spawn(async move {
             let data = PlayerData::load(uuid).await
                 .unwrap_or_else(|_| { 
-                    gen_new_player() 
+                    gen_new_player(spawn, world, game_mode) 
                 });
             sender.send(JoiningPlayer {
                 uuid, name, conn, data
@@ -89,10 +120,14 @@ pub fn load_player_data(#[resource] server: &mut Server,
     }
 }
 
-fn gen_new_player() -> PlayerData {
+fn gen_new_player(spawn: Vector3<f64>, dimension: String, game_mode: GameMode) -> PlayerData {
     PlayerData {
-        pos: vector!(0.0, 3.0, 0.0),
+        pos: spawn,
         rotation: (0.0, 0.0),
         inventory: Inventory::new(),
+        ender_items: EnderChest::default(),
+        dimension,
+        game_mode,
+        experience: Experience::default(),
     }
 }
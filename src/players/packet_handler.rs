@@ -1,22 +1,56 @@
+use std::collections::HashMap;
+
 use legion::*;
-use nalgebra::vector;
+use legion::systems::CommandBuffer;
+use nalgebra::{Vector3, vector};
+use tokio::sync::mpsc::UnboundedSender;
 use crate::buckets::EntityTracker;
 use crate::buckets::events::{EntityEvent, EntityEventData};
 use crate::blocks::InteractionResult;
-use crate::entities::EntityId;
-use crate::net::PlayerConnection;
+use crate::entities::{EntityId, EntityIdGenerator, spawn_item, throw_item};
+use crate::chunks::World as ChunkWorld;
+use crate::net::{ClientboundPacket, PlayerConnection};
 use crate::entities::{Position, Rotation};
 use crate::net::ServerboundPacket;
-use crate::chunks::World as ChunkWorld;
+use crate::dimensions::{DimensionId, Dimensions};
+use super::{Experience, GameMode};
+use super::digging::Digging;
 use super::disconnections::DisconnectionQueue;
-use crate::inventory::{Inventory, SlotIndex};
+use crate::inventory::{EnderChest, Inventory, SlotIndex};
+use crate::items::ItemStack;
+use crate::level::WorldTime;
+use crate::loot::block_drops;
+use crate::windows::{Click, Container, Windows};
+
+/// What players started and haven't finished, forgotten when they leave
+#[derive(Default)]
+pub struct PendingActions {
+    /// Signs players placed and can write on
+    editing_signs: HashMap<Entity, Vector3<i32>>,
+    digging: HashMap<Entity, Digging>,
+}
+
+impl PendingActions {
+    pub fn forget(&mut self, entity: Entity) {
+        self.editing_signs.remove(&entity);
+        self.digging.remove(&entity);
+    }
+}
 
 #[system(for_each)]
-pub fn receive_events(entity: &Entity, id: &EntityId, conn: &mut PlayerConnection, 
+pub fn receive_events(entity: &Entity, id: &EntityId, conn: &mut PlayerConnection,
     position: &mut Position, rotation: &mut Rotation, inventory: &mut Inventory,
-    #[resource] chunks: &ChunkWorld, #[resource] disconnections: &DisconnectionQueue, 
-    #[resource] tracker: &mut EntityTracker) 
+    ender_chest: &mut EnderChest, experience: &mut Experience, dimension: &DimensionId,
+    game_mode: &GameMode, #[resource] pending: &mut PendingActions,
+    #[resource] dimensions: &Dimensions, #[resource] windows: &mut Windows,
+    #[resource] disconnections: &DisconnectionQueue, #[resource] time: &WorldTime,
+    #[resource] entity_ids: &EntityIdGenerator, cmd: &mut CommandBuffer) 
 {
+    let chunks = &dimensions.get(*dimension).chunks;
+    let tracker = &dimensions.get(*dimension).tracker;
+    let border = &dimensions.get(*dimension).border;
+    let sender = conn.get_sender();
+    let PendingActions { editing_signs, digging } = pending;
     for event in conn.receive() {
         match event {
             ServerboundPacket::PlayerPosition { x, y, z, .. } => {
@@ -70,18 +104,55 @@ pub fn receive_events(entity: &Entity, id: &EntityId, conn: &mut PlayerConnectio
                 *rotation = Rotation(yaw, pitch);
             },
             ServerboundPacket::PlayerDigging {
-                status, position, face
+                status, position: location, ..
             } => {
-                match status {
-                    0 => {
-                        let view = chunks.get_view(position);
-                        chunks.get_block(&position).destroy(&view);
-                    },
-                    2 => {
-                        
-                    },
-                    _ => (),
+                // Dropping the held stack or one item of it
+                if status == 3 || status == 4 {
+                    let count = if status == 3 { u8::MAX } else { 1 };
+                    if let Some(stack) = inventory.take_held(count) {
+                        throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
+                    }
+                    continue;
+                }
+                // Higher ones are for using items
+                if status > 2 {
+                    continue;
+                }
+                // Starting, cancelling or finishing all end the last dig
+                let dug = digging.remove(entity);
+                if let Some(dug) = &dug {
+                    if dug.stage >= 0 {
+                        send_cracks(tracker, id, position, dug.pos, -1);
+                    }
+                }
+                let allowed = border.contains_block(&location) && game_mode.can_edit();
+                let held = inventory.get_held();
+                let block = chunks.get_block(&location);
+                let breaks = allowed && match status {
+                    0 => game_mode.breaks_instantly() || block.dig_progress(held) >= 1.0,
+                    // Finished too fast or without starting, the client gets the block back
+                    2 => dug.map_or(false, |dug| {
+                        dug.pos == location && dug.is_done(block, held, time.world_age())
+                    }),
+                    _ => false,
+                };
+                if allowed && status == 0 && !breaks {
+                    digging.insert(*entity, Digging::new(location, time.world_age()));
+                }
+                if breaks {
+                    let pos = location.cast::<f64>() + vector!(0.5, 0.25, 0.5);
+                    for stack in broken_block_drops(chunks, location, held, *game_mode) {
+                        spawn_item(cmd, entity_ids, *dimension, pos, stack);
+                    }
+                    let view = chunks.get_view(location);
+                    block.destroy(&view);
                 }
+                let _ = sender.send(ClientboundPacket::AcknowledgePlayerDigging {
+                    pos: location,
+                    block_state: chunks.get_block(&location).id as u32,
+                    status,
+                    successful: allowed && (status != 2 || breaks),
+                });
             },
             ServerboundPacket::HeldItemChange { slot } => {
                 inventory.set_held_slot(
@@ -90,30 +161,173 @@ pub fn receive_events(entity: &Entity, id: &EntityId, conn: &mut PlayerConnectio
             ServerboundPacket::CreativeInventoryAction {
                 slot, stack
             } => {
-                if slot != -1 {
-                    let index = SlotIndex::from_network(slot as u8);
+                if *game_mode != GameMode::Creative {
+                    let _ = sender.send(ClientboundPacket::WindowItems {
+                        window: 0,
+                        items: inventory.get_window(),
+                    });
+                    continue;
+                }
+                // Slot -1 throws the stack out of the creative inventory
+                if slot == -1 {
+                    if let Some(stack) = stack {
+                        throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
+                    }
+                } else if let Some(index) = SlotIndex::from_network(slot as u8) {
                     inventory.set_slot(index, stack);
                 }
             },
             ServerboundPacket::PlayerBlockPlacement {
                 hand, location, face, cursor_position, ..
             } => {
+                let adjacent = face.get_adjacent(location);
+                if !border.contains_block(&location) || !border.contains_block(&adjacent)
+                    || !game_mode.can_interact()
+                {
+                    resend_block(&sender, chunks, location);
+                    resend_block(&sender, chunks, adjacent);
+                    continue;
+                }
                 let view = chunks.get_view(location);
-                match chunks.get_block(&location).interact(&view) {
+                let block = chunks.get_block(&location);
+                match block.interact(&view) {
                     InteractionResult::None => {
-                        if let Some(stack) = inventory.get_held() {
+                        if !game_mode.can_edit() {
+                            resend_block(&sender, chunks, adjacent);
+                            continue;
+                        }
+                        if let Some(stack) = inventory.get_held().cloned() {
+                            if let InteractionResult::PreventPlacing = 
+                                block.use_item(&view, stack.item, &face) 
+                            {
+                                continue;
+                            }
                             if let Some(block_type) = stack.item.get_block() {
-                                block_type.place(&view, face, cursor_position, 
+                                let placed = block_type.place(&view, face, cursor_position, 
                                     (rotation.0, rotation.1));
+                                if let Some(pos) = placed {
+                                    if *game_mode != GameMode::Creative {
+                                        inventory.take_held(1);
+                                    }
+                                    if let Some(mut block_entity) = chunks.get_block_entity(&pos) {
+                                        if block_entity.apply_item(pos, &stack) {
+                                            chunks.set_block_entity(&pos, block_entity);
+                                        }
+                                    }
+                                    let is_sign = chunks.get_block_entity(&pos)
+                                        .map(|entity| entity.id == "minecraft:sign")
+                                        .unwrap_or(false);
+                                    if is_sign {
+                                        editing_signs.insert(*entity, pos);
+                                        let _ = sender.send(ClientboundPacket::OpenSignEditor(pos));
+                                    }
+                                }
                             }
                         }
                     },
                     InteractionResult::PreventPlacing => (),
+                    InteractionResult::OpenContainer => {
+                        if let Some(container) = Container::at(chunks, *dimension, location) {
+                            let leftover = windows.open(*entity, sender.clone(), container,
+                                chunks, inventory, ender_chest);
+                            for stack in leftover {
+                                throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
+                            }
+                        }
+                    },
+                }
+            },
+            ServerboundPacket::UpdateSign { location, lines } => {
+                // Only the sign the player just placed can be written on
+                if editing_signs.remove(entity) != Some(location) {
+                    continue;
+                }
+                if let Some(mut sign) = chunks.get_block_entity(&location) {
+                    if sign.set_sign_text(&lines) {
+                        chunks.set_block_entity(&location, sign);
+                    }
+                }
+            },
+            ServerboundPacket::ClickWindow { 
+                window, slot, button, action, mode, clicked 
+            } => {
+                let click = Click { window, slot, button, action, mode, clicked };
+                let dropped = windows.click(*entity, &sender, click, chunks, inventory,
+                    ender_chest, experience, *game_mode);
+                for stack in dropped {
+                    throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
+                }
+            },
+            ServerboundPacket::WindowConfirmation { window, action, accepted } => {
+                windows.confirm(*entity, window, action, accepted);
+            },
+            ServerboundPacket::CloseWindow { window } => {
+                if !windows.is_current(*entity, window) {
+                    continue;
+                }
+                for stack in windows.close(*entity, &sender, inventory) {
+                    throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
                 }
             },
             ServerboundPacket::Disconnect { reason } => {
+                for stack in windows.close(*entity, &sender, inventory) {
+                    throw_item(cmd, entity_ids, *dimension, position, rotation, stack);
+                }
                 disconnections.send(*entity, reason);
             },
         }
     }
+    if let Some(dug) = digging.get_mut(entity) {
+        let block = chunks.get_block(&dug.pos);
+        if let Some(stage) = dug.update_stage(block, inventory.get_held(), time.world_age()) {
+            send_cracks(tracker, id, position, dug.pos, stage);
+        }
+    }
+}
+
+/// Loot of a block and what was inside it. Shulker boxes keep their contents
+/// in the dropped item, so creative players get that item when it isn't empty
+fn broken_block_drops(chunks: &ChunkWorld, pos: Vector3<i32>, held: Option<&ItemStack>,
+    game_mode: GameMode) -> Vec<ItemStack>
+{
+    let block = chunks.get_block(&pos);
+    let block_entity = chunks.get_block_entity(&pos);
+    let contents: Vec<ItemStack> = block_entity.as_ref()
+        .and_then(|entity| entity.items())
+        .map(|items| items.iter().flatten().cloned().collect())
+        .unwrap_or_default();
+    let is_shulker_box = block_entity.as_ref()
+        .map_or(false, |entity| entity.id == "minecraft:shulker_box");
+    let loot = match game_mode {
+        GameMode::Survival => block.can_harvest(held),
+        _ => is_shulker_box && !contents.is_empty(),
+    };
+    let mut drops = Vec::new();
+    if loot {
+        drops = block_drops(block, block_entity.as_ref(), pos, held);
+    }
+    if !is_shulker_box {
+        drops.extend(contents);
+    }
+    drops
+}
+
+/// Shows other players how far a block is dug, -1 removes the cracks
+fn send_cracks(tracker: &EntityTracker, id: &EntityId, position: &Position,
+    pos: Vector3<i32>, stage: i8)
+{
+    tracker.send_event(&position.0, EntityEvent {
+        id: id.0,
+        data: EntityEventData::BreakBlock { pos, stage },
+    });
+}
+
+/// Undoes what the client predicted for a rejected action
+fn resend_block(sender: &UnboundedSender<ClientboundPacket>, chunks: &ChunkWorld, 
+    pos: Vector3<i32>) 
+{
+    let _ = sender.send(ClientboundPacket::BlockChange {
+        pos,
+        block_state: chunks.get_block(&pos).id as u32,
+    });
 }
@@ -0,0 +1,88 @@
+use legion::*;
+use legion::systems::CommandBuffer;
+use world::SubWorld;
+
+use crate::buckets::events::{EntityEvent, EntityEventData};
+use crate::dimensions::{DimensionId, Dimensions};
+use crate::entities::{DroppedItem, EntityId, Position};
+use crate::inventory::Inventory;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use super::GameMode;
+
+/// How far from a player's feet items get picked up, the player's hitbox
+/// grown by a block sideways and half a block up and down
+const REACH_SIDEWAYS: f64 = 1.3;
+const REACH_BELOW: f64 = 0.5;
+const REACH_ABOVE: f64 = 2.3;
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(Position)]
+#[read_component(DimensionId)]
+#[read_component(GameMode)]
+#[read_component(PlayerConnection)]
+#[write_component(Inventory)]
+#[write_component(DroppedItem)]
+pub fn pick_up_items(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] dimensions: &Dimensions)
+{
+    let mut items = <(Entity, &EntityId, &Position, &DimensionId, &DroppedItem)>::query();
+    let ready: Vec<_> = items.iter(world)
+        .filter(|(.., item)| item.pickup_delay == 0)
+        .map(|(entity, id, pos, dimension, item)| (*entity, *id, pos.0, *dimension, item.stack.clone()))
+        .collect();
+    let mut players = <(&EntityId, &Position, &DimensionId, &GameMode, &PlayerConnection,
+        &mut Inventory)>::query();
+    for (entity, id, pos, dimension, mut stack) in ready {
+        let tracker = &dimensions.get(dimension).tracker;
+        let mut picked = false;
+        for (player_id, player_pos, player_dimension, game_mode, conn, inventory)
+            in players.iter_mut(world)
+        {
+            let offset = pos - player_pos.0;
+            if *player_dimension != dimension || *game_mode == GameMode::Spectator
+                || offset.x.abs() > REACH_SIDEWAYS || offset.z.abs() > REACH_SIDEWAYS
+                || offset.y < -REACH_BELOW || offset.y > REACH_ABOVE
+            {
+                continue;
+            }
+            let count = stack.count;
+            let left = inventory.add_stack(stack.clone());
+            let taken = count - left.as_ref().map_or(0, |left| left.count);
+            if taken == 0 {
+                continue;
+            }
+            conn.send(ClientboundPacket::WindowItems {
+                window: 0,
+                items: inventory.get_window(),
+            });
+            tracker.send_event(&pos, EntityEvent {
+                id: id.0,
+                data: EntityEventData::Collect { collector: player_id.0, count: taken },
+            });
+            picked = true;
+            match left {
+                Some(left) => stack = left,
+                None => {
+                    stack.count = 0;
+                    break;
+                }
+            }
+        }
+        if !picked {
+            continue;
+        }
+        if stack.count == 0 {
+            tracker.remove(id.0, &pos);
+            cmd.remove(entity);
+        } else if let Ok(mut entry) = world.entry_mut(entity) {
+            if let Ok(item) = entry.get_component_mut::<DroppedItem>() {
+                item.stack = stack.clone();
+            }
+            tracker.send_event(&pos, EntityEvent {
+                id: id.0,
+                data: EntityEventData::ChangeItem { stack },
+            });
+        }
+    }
+}
@@ -1,14 +1,17 @@
 use std::fs;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use anvil_nbt::{decode, encode};
 use nalgebra::Vector3;
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeSeq};
 use uuid::Uuid;
 
 use crate::util::{read_file, write_file};
 
-use crate::inventory::Inventory;
+use crate::inventory::{EnderChest, Inventory, read_item_tags, write_item_tags};
+use crate::dimensions::DimensionType;
+use super::{Experience, GameMode};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all="PascalCase")] 
@@ -17,17 +20,37 @@ pub struct PlayerData {
     #[serde(serialize_with="serialize_rotation")]
     pub rotation: (f32, f32),
     pub inventory: Inventory,
+    #[serde(default)]
+    pub ender_items: EnderChest,
+    #[serde(default="overworld")]
+    pub dimension: String,
+    #[serde(rename="playerGameType", default)]
+    pub game_mode: GameMode,
+    #[serde(flatten)]
+    pub experience: Experience,
 }
 
 impl PlayerData {
     pub async fn load(uuid: Uuid) -> Result<Self> {
         let file = read_file(get_path(uuid)).await?;
-        Ok(nbt::from_gzip_reader(file.as_slice())?)
+        let mut data: Self = nbt::from_gzip_reader(file.as_slice())?;
+        // Item tags are read on their own so they keep their NBT types
+        let compound = decode::read_gzip_compound_tag(&mut file.as_slice())
+            .map_err(|err| anyhow!("Invalid player data: {:?}", err))?;
+        data.inventory.set_item_tags(read_item_tags(&compound, "Inventory"));
+        data.ender_items.set_item_tags(read_item_tags(&compound, "EnderItems"));
+        Ok(data)
     }
 
     pub async fn save(&self, uuid: Uuid) -> Result<()> {
+        let mut raw = vec![];
+        nbt::to_writer(&mut raw, self, None)?;
+        let mut compound = decode::read_compound_tag(&mut raw.as_slice())
+            .map_err(|err| anyhow!("Invalid player data: {:?}", err))?;
+        write_item_tags(&mut compound, "Inventory", &self.inventory.item_tags());
+        write_item_tags(&mut compound, "EnderItems", &self.ender_items.item_tags());
         let mut data = vec![];
-        nbt::to_gzip_writer(&mut data, self, None)?;
+        encode::write_gzip_compound_tag(&mut data, compound)?;
         let path = get_path(uuid);
         fs::create_dir_all(path.parent().unwrap()).unwrap();
         write_file(path, data.as_slice()).await?;
@@ -35,6 +58,10 @@ impl PlayerData {
     }
 }
 
+fn overworld() -> String {
+    DimensionType::Overworld.name().into()
+}
+
 fn get_path(uuid: Uuid) -> PathBuf {
     PathBuf::from(format!("./world/playerdata/{}.dat", uuid))
 }
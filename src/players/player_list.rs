@@ -6,6 +6,7 @@ use crate::net::{PlayerConnection, Server};
 use std::mem::take;
 use std::collections::HashMap;
 use uuid::Uuid;
+use super::GameMode;
 
 #[system]
 #[read_component(PlayerConnection)]
@@ -17,20 +18,23 @@ pub fn update_player_list(world: &SubWorld, #[resource] list: &mut PlayerList,
         server.update_list(list.count(), list.get_sample());
         for update in updates {
             let packet = match &update {
-                PlayerListUpdate::Add(uuid, name) => {
+                PlayerListUpdate::Add(uuid, name, mode) => {
                     ClientboundPacket::PlayerInfoAddPlayers(vec![
                         (
                             *uuid,
                             PlayerInfo {
                                 name: name.clone(),
                                 properties: Vec::new(),
-                                gamemode: 0,
+                                gamemode: mode.id(),
                                 ping: 0,
                                 display_name: None,
                             }
                         )
                     ])
                 }
+                PlayerListUpdate::SetGameMode(uuid, mode) => {
+                    ClientboundPacket::PlayerInfoUpdateGamemode(vec![(*uuid, mode.id())])
+                }
                 PlayerListUpdate::Remove(uuid) => {
                     ClientboundPacket::PlayerInfoRemovePlayers(vec![*uuid])
                 }
@@ -44,7 +48,7 @@ pub fn update_player_list(world: &SubWorld, #[resource] list: &mut PlayerList,
 }
 
 pub struct PlayerList {
-    players: HashMap<Uuid, String>,
+    players: HashMap<Uuid, (String, GameMode)>,
     pending_updates: Vec<PlayerListUpdate>,
 }
 
@@ -56,8 +60,12 @@ impl PlayerList {
         }
     }
 
-    pub fn add(&mut self, uuid: Uuid, name: String) {
-        self.pending_updates.push(PlayerListUpdate::Add(uuid, name));
+    pub fn add(&mut self, uuid: Uuid, name: String, mode: GameMode) {
+        self.pending_updates.push(PlayerListUpdate::Add(uuid, name, mode));
+    }
+
+    pub fn set_game_mode(&mut self, uuid: Uuid, mode: GameMode) {
+        self.pending_updates.push(PlayerListUpdate::SetGameMode(uuid, mode));
     }
 
     pub fn remove(&mut self, uuid: Uuid) {
@@ -75,7 +83,7 @@ impl PlayerList {
     pub fn get_sample(&self) -> Vec<String> {
         (&self.players).into_iter()
             .take(5)
-            .map(|s| s.1.clone())
+            .map(|s| (s.1).0.clone())
             .collect()
     }
 
@@ -83,17 +91,17 @@ impl PlayerList {
         self.players.len()
     }
 
-    pub fn get_players(&self) -> &HashMap<Uuid, String> {
+    pub fn get_players(&self) -> &HashMap<Uuid, (String, GameMode)> {
         &self.players
     }
 
     pub fn send_player(&self, sender: &UnboundedSender<ClientboundPacket>) {
-        let players = self.get_players().into_iter().map(|(uuid, name)| (
+        let players = self.get_players().into_iter().map(|(uuid, (name, mode))| (
             *uuid,
             PlayerInfo {
                 name: name.clone(),
                 properties: Vec::new(),
-                gamemode: 0,
+                gamemode: mode.id(),
                 ping: 0,
                 display_name: None,
             }
@@ -104,15 +112,21 @@ impl PlayerList {
 
 #[derive(Debug, Clone)]
 pub enum PlayerListUpdate {
-    Add(Uuid, String),
+    Add(Uuid, String, GameMode),
+    SetGameMode(Uuid, GameMode),
     Remove(Uuid),
 }
 
 impl PlayerListUpdate {
     fn apply(&self, list: &mut PlayerList) {
         match self {
-            PlayerListUpdate::Add(uuid, name) => {
-                list.players.insert(*uuid, name.clone());
+            PlayerListUpdate::Add(uuid, name, mode) => {
+                list.players.insert(*uuid, (name.clone(), *mode));
+            }
+            PlayerListUpdate::SetGameMode(uuid, mode) => {
+                if let Some(player) = list.players.get_mut(uuid) {
+                    player.1 = *mode;
+                }
             }
             PlayerListUpdate::Remove(uuid) => {
                 list.players.remove(uuid);
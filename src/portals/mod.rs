@@ -0,0 +1,241 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use block_macro::block_id;
+use legion::*;
+use legion::systems::Builder;
+use nalgebra::{Vector3, vector};
+use world::SubWorld;
+
+use crate::blocks::{Block, nether_portal};
+use crate::chunks::{ChunkCoords, World as ChunkWorld, block_coords};
+use crate::dimensions::{DimensionId, DimensionType, Dimensions};
+use crate::entities::{EntityId, Position};
+use crate::level::LevelData;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use crate::players::DimensionChangeQueue;
+
+/// How far from the scaled position an existing portal gets reused
+const SEARCH_RADIUS: i32 = 16;
+const END_PLATFORM: Vector3<i32> = vector!(100, 48, 0);
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+    schedule
+        .add_system(enter_portals_system(HashSet::new()))
+        .add_system(finish_travels_system());
+    resources.insert(PortalTravels::new());
+}
+
+/// Where a player that went through a portal ends up, worked out once 
+/// the chunks around it are loaded
+#[derive(Clone, Copy)]
+enum Destination {
+    /// Closest nether portal, or a new one built at the scaled position
+    NetherPortal(Vector3<i32>),
+    EndPlatform,
+}
+
+struct Travel {
+    entity: Entity,
+    dimension: DimensionId,
+    destination: Destination,
+}
+
+pub struct PortalTravels {
+    pending: Mutex<Vec<Travel>>,
+}
+
+impl PortalTravels {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(vec![]),
+        }
+    }
+
+    fn is_pending(&self, entity: Entity) -> bool {
+        self.pending.lock().unwrap()
+            .iter().any(|travel| travel.entity == entity)
+    }
+
+    fn push(&self, entity: Entity, dimension: DimensionId, destination: Destination) {
+        self.pending.lock().unwrap().push(Travel {
+            entity, dimension, destination,
+        });
+    }
+}
+
+/// Players only travel when they walk into a portal, so they don't
+/// bounce back as soon as they arrive in the one on the other side
+#[system(for_each)]
+fn enter_portals(#[state] inside: &mut HashSet<Entity>, entity: &Entity,
+    pos: &Position, dimension: &DimensionId, #[resource] dimensions: &Dimensions,
+    #[resource] travels: &PortalTravels, #[resource] queue: &DimensionChangeQueue,
+    #[resource] level: &LevelData)
+{
+    if travels.is_pending(*entity) {
+        return;
+    }
+    let current = dimensions.get(*dimension);
+    let block = current.chunks.get_block(&block_coords(&pos.0));
+    let portal = block.btype.name.as_str();
+    if portal != "minecraft:nether_portal" && portal != "minecraft:end_portal" {
+        inside.remove(entity);
+        return;
+    }
+    if !inside.insert(*entity) {
+        return;
+    }
+    match (portal, current.kind) {
+        ("minecraft:nether_portal", DimensionType::Overworld | DimensionType::Nether) => {
+            let target_kind = if current.kind == DimensionType::Nether {
+                DimensionType::Overworld
+            } else {
+                DimensionType::Nether
+            };
+            let target = match dimensions.find_kind(target_kind) {
+                Some(target) => target,
+                None => return,
+            };
+            let scale = current.kind.coordinate_scale() / target_kind.coordinate_scale();
+            let new_pos = vector!(pos.0.x * scale, pos.0.y, pos.0.z * scale);
+            queue.send(*entity, target, new_pos);
+            travels.push(*entity, target, Destination::NetherPortal(block_coords(&new_pos)));
+        },
+        ("minecraft:end_portal", DimensionType::End) => {
+            queue.send(*entity, dimensions.overworld(), level.spawn_position());
+        },
+        ("minecraft:end_portal", _) => {
+            if let Some(target) = dimensions.find_kind(DimensionType::End) {
+                let platform = END_PLATFORM.cast::<f64>() + vector!(0.5, 1.0, 0.5);
+                queue.send(*entity, target, platform);
+                travels.push(*entity, target, Destination::EndPlatform);
+            }
+        },
+        _ => (),
+    }
+}
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(PlayerConnection)]
+#[read_component(DimensionId)]
+#[write_component(Position)]
+fn finish_travels(world: &mut SubWorld, #[resource] travels: &PortalTravels,
+    #[resource] dimensions: &Dimensions)
+{
+    let mut pending = travels.pending.lock().unwrap();
+    if pending.is_empty() {
+        return;
+    }
+    let mut query = <(Entity, &EntityId, &PlayerConnection, &DimensionId, 
+        &mut Position)>::query();
+    let mut players: HashMap<Entity, _> = query.iter_mut(world)
+        .map(|(entity, id, conn, dimension, pos)| (*entity, (id, conn, dimension, pos)))
+        .collect();
+    pending.retain(|travel| {
+        let (id, conn, dimension, pos) = match players.get_mut(&travel.entity) {
+            Some(player) => player,
+            None => return false,
+        };
+        // Still waiting for the dimension change at the end of the tick
+        if **dimension != travel.dimension {
+            return true;
+        }
+        let target = dimensions.get(travel.dimension);
+        let arrival = match travel.destination {
+            Destination::NetherPortal(center) => {
+                if !area_loaded(&target.chunks, center, SEARCH_RADIUS) {
+                    return true;
+                }
+                find_portal(&target.chunks, center)
+                    .unwrap_or_else(|| build_portal(&target.chunks, center))
+            },
+            Destination::EndPlatform => {
+                if !area_loaded(&target.chunks, END_PLATFORM, 2) {
+                    return true;
+                }
+                build_end_platform(&target.chunks);
+                END_PLATFORM + vector!(0, 1, 0)
+            },
+        };
+        let new_pos = arrival.cast::<f64>() + vector!(0.5, 0.0, 0.5);
+        target.tracker.move_entity(id.0, travel.entity, pos.0, new_pos);
+        conn.send(ClientboundPacket::PlayerPosition(new_pos.x, new_pos.y, new_pos.z));
+        pos.0 = new_pos;
+        false
+    });
+}
+
+fn area_loaded(chunks: &ChunkWorld, center: Vector3<i32>, radius: i32) -> bool {
+    let min = ChunkCoords::from_block(&(center - vector!(radius, 0, radius)));
+    let max = ChunkCoords::from_block(&(center + vector!(radius, 0, radius)));
+    (min.0..=max.0).all(|x| {
+        (min.1..=max.1).all(|z| chunks.is_loaded(ChunkCoords(x, z)))
+    })
+}
+
+/// Bottom of the closest column of portal blocks
+fn find_portal(chunks: &ChunkWorld, center: Vector3<i32>) -> Option<Vector3<i32>> {
+    let mut closest: Option<(i32, Vector3<i32>)> = None;
+    for x in -SEARCH_RADIUS..=SEARCH_RADIUS {
+        for z in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            for y in 1..256 {
+                let pos = vector!(center.x + x, y, center.z + z);
+                if !is_portal(chunks.get_block(&pos)) 
+                    || is_portal(chunks.get_block(&(pos - vector!(0, 1, 0)))) 
+                {
+                    continue;
+                }
+                let distance = (pos - center).map(|c| c * c).sum();
+                if closest.map(|(best, _)| distance < best).unwrap_or(true) {
+                    closest = Some((distance, pos));
+                }
+            }
+        }
+    }
+    closest.map(|(_, pos)| pos)
+}
+
+/// A 2x3 portal along x on top of the highest ground in the column, 
+/// with some obsidian to stand on at both sides
+fn build_portal(chunks: &ChunkWorld, center: Vector3<i32>) -> Vector3<i32> {
+    let ground = (1..255).rev()
+        .find(|y| chunks.get_block(&vector!(center.x, *y, center.z)).opaque)
+        .unwrap_or(63);
+    let base = vector!(center.x, (ground + 1).min(250), center.z);
+    let obsidian = Block::from_state_id(block_id!(obsidian)).unwrap();
+    let portal = nether_portal("x");
+    for x in -1..=2 {
+        for y in 0..=4 {
+            let frame = x == -1 || x == 2 || y == 0 || y == 4;
+            let block = if frame { obsidian } else { portal };
+            chunks.set_block(&(base + vector!(x, y, 0)), block);
+        }
+    }
+    for x in 0..=1 {
+        for z in [-1, 1] {
+            chunks.set_block(&(base + vector!(x, 0, z)), obsidian);
+            for y in 1..=3 {
+                chunks.set_block(&(base + vector!(x, y, z)), Block::air());
+            }
+        }
+    }
+    base + vector!(0, 1, 0)
+}
+
+/// Same 5x5 obsidian platform vanilla puts at 100, 48, 0
+fn build_end_platform(chunks: &ChunkWorld) {
+    let obsidian = Block::from_state_id(block_id!(obsidian)).unwrap();
+    for x in -2..=2 {
+        for z in -2..=2 {
+            chunks.set_block(&(END_PLATFORM + vector!(x, 0, z)), obsidian);
+            for y in 1..=3 {
+                chunks.set_block(&(END_PLATFORM + vector!(x, y, z)), Block::air());
+            }
+        }
+    }
+}
+
+fn is_portal(block: &Block) -> bool {
+    block.btype.name == "minecraft:nether_portal"
+}
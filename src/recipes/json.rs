@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+use crate::items::{ItemStack, ItemType};
+use crate::tags::item_tag;
+use super::{CookingMethod, Ingredient, Recipe, RecipeKind};
+
+/// Recipes as they're written in vanilla data packs
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RecipeJson {
+    #[serde(rename = "minecraft:crafting_shaped")]
+    Shaped {
+        #[serde(default)]
+        group: String,
+        pattern: Vec<String>,
+        key: HashMap<char, IngredientJson>,
+        result: ResultJson,
+    },
+    #[serde(rename = "minecraft:crafting_shapeless")]
+    Shapeless {
+        #[serde(default)]
+        group: String,
+        ingredients: Vec<IngredientJson>,
+        result: ResultJson,
+    },
+    #[serde(rename = "minecraft:smelting")]
+    Smelting(CookingJson),
+    #[serde(rename = "minecraft:blasting")]
+    Blasting(CookingJson),
+    #[serde(rename = "minecraft:smoking")]
+    Smoking(CookingJson),
+    #[serde(rename = "minecraft:campfire_cooking")]
+    Campfire(CookingJson),
+    #[serde(rename = "minecraft:stonecutting")]
+    Stonecutting {
+        #[serde(default)]
+        group: String,
+        ingredient: IngredientJson,
+        result: String,
+        #[serde(default = "one")]
+        count: u8,
+    },
+}
+
+#[derive(Deserialize)]
+struct CookingJson {
+    #[serde(default)]
+    group: String,
+    ingredient: IngredientJson,
+    result: String,
+    experience: f32,
+    #[serde(rename = "cookingtime")]
+    time: u32,
+}
+
+/// A single item or tag, or a list of them where any works
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IngredientJson {
+    One(ItemOrTag),
+    Any(Vec<ItemOrTag>),
+}
+
+#[derive(Deserialize)]
+struct ItemOrTag {
+    item: Option<String>,
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResultJson {
+    item: String,
+    #[serde(default = "one")]
+    count: u8,
+}
+
+fn one() -> u8 {
+    1
+}
+
+pub fn parse(json: &str) -> Result<Vec<Recipe>> {
+    let recipes: HashMap<String, RecipeJson> = serde_json::from_str(json)?;
+    let mut recipes = recipes.into_iter()
+        .map(|(id, recipe)| to_recipe(id, recipe))
+        .collect::<Result<Vec<Recipe>>>()?;
+    // Keeps the first match stable between runs
+    recipes.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(recipes)
+}
+
+fn to_recipe(id: String, recipe: RecipeJson) -> Result<Recipe> {
+    let (group, kind) = match recipe {
+        RecipeJson::Shaped { group, pattern, key, result } => {
+            let width = pattern.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+            let mut ingredients = vec![];
+            for row in &pattern {
+                let mut columns: Vec<char> = row.chars().collect();
+                columns.resize(width, ' ');
+                for symbol in columns {
+                    ingredients.push(match symbol {
+                        ' ' => vec![],
+                        symbol => key.get(&symbol)
+                            .ok_or_else(|| anyhow!("Unknown key {} in recipe {}", symbol, id))
+                            .and_then(to_ingredient)?,
+                    });
+                }
+            }
+            (group, RecipeKind::Shaped {
+                width,
+                height: pattern.len(),
+                ingredients,
+                result: to_stack(&result.item, result.count)?,
+            })
+        },
+        RecipeJson::Shapeless { group, ingredients, result } => {
+            (group, RecipeKind::Shapeless {
+                ingredients: ingredients.iter()
+                    .map(to_ingredient)
+                    .collect::<Result<_>>()?,
+                result: to_stack(&result.item, result.count)?,
+            })
+        },
+        RecipeJson::Smelting(cooking) => to_cooking(CookingMethod::Smelting, cooking)?,
+        RecipeJson::Blasting(cooking) => to_cooking(CookingMethod::Blasting, cooking)?,
+        RecipeJson::Smoking(cooking) => to_cooking(CookingMethod::Smoking, cooking)?,
+        RecipeJson::Campfire(cooking) => to_cooking(CookingMethod::Campfire, cooking)?,
+        RecipeJson::Stonecutting { group, ingredient, result, count } => {
+            (group, RecipeKind::Stonecutting {
+                ingredient: to_ingredient(&ingredient)?,
+                result: to_stack(&result, count)?,
+            })
+        },
+    };
+    Ok(Recipe { id, group, kind })
+}
+
+fn to_cooking(method: CookingMethod, cooking: CookingJson) -> Result<(String, RecipeKind)> {
+    Ok((cooking.group, RecipeKind::Cooking {
+        method,
+        ingredient: to_ingredient(&cooking.ingredient)?,
+        result: to_stack(&cooking.result, 1)?,
+        experience: cooking.experience,
+        time: cooking.time,
+    }))
+}
+
+fn to_ingredient(ingredient: &IngredientJson) -> Result<Ingredient> {
+    let options = match ingredient {
+        IngredientJson::One(option) => std::slice::from_ref(option),
+        IngredientJson::Any(options) => options.as_slice(),
+    };
+    let mut items = vec![];
+    for option in options {
+        match (&option.item, &option.tag) {
+            (Some(item), _) => items.push(ItemType::from_str(item)?),
+            (None, Some(tag)) => {
+                let tag = item_tag(tag).ok_or_else(|| anyhow!("Unknown item tag {}", tag))?;
+                items.extend(tag);
+            },
+            (None, None) => return Err(anyhow!("Ingredient without item or tag")),
+        }
+    }
+    Ok(items)
+}
+
+fn to_stack(item: &str, count: u8) -> Result<ItemStack> {
+    Ok(ItemStack {
+        item: ItemType::from_str(item)?,
+        count,
+        nbt: None,
+    })
+}
@@ -0,0 +1,260 @@
+mod json;
+
+use lazy_static::lazy_static;
+
+use crate::items::{ItemStack, ItemType};
+
+const RECIPES_JSON: &str = include_str!("recipes.json");
+
+lazy_static! {
+    static ref RECIPES: Vec<Recipe> = json::parse(RECIPES_JSON).unwrap();
+}
+
+/// Items a recipe slot takes, empty for slots that have to stay empty
+pub type Ingredient = Vec<&'static ItemType>;
+
+pub struct Recipe {
+    /// Like minecraft:oak_planks
+    pub id: String,
+    /// Recipes with the same group are shown together in the recipe book
+    pub group: String,
+    pub kind: RecipeKind,
+}
+
+pub enum RecipeKind {
+    /// Ingredients go row by row
+    Shaped {
+        width: usize,
+        height: usize,
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+    },
+    Shapeless {
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+    },
+    Cooking {
+        method: CookingMethod,
+        ingredient: Ingredient,
+        result: ItemStack,
+        experience: f32,
+        /// In ticks
+        time: u32,
+    },
+    Stonecutting {
+        ingredient: Ingredient,
+        result: ItemStack,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CookingMethod {
+    Smelting,
+    Blasting,
+    Smoking,
+    Campfire,
+}
+
+impl Recipe {
+    pub fn result(&self) -> &ItemStack {
+        match &self.kind {
+            RecipeKind::Shaped { result, .. }
+                | RecipeKind::Shapeless { result, .. }
+                | RecipeKind::Cooking { result, .. }
+                | RecipeKind::Stonecutting { result, .. } => result,
+        }
+    }
+
+    /// Name of the recipe type in data packs and the Declare Recipes packet
+    pub fn type_name(&self) -> &'static str {
+        match &self.kind {
+            RecipeKind::Shaped { .. } => "minecraft:crafting_shaped",
+            RecipeKind::Shapeless { .. } => "minecraft:crafting_shapeless",
+            RecipeKind::Cooking { method, .. } => match method {
+                CookingMethod::Smelting => "minecraft:smelting",
+                CookingMethod::Blasting => "minecraft:blasting",
+                CookingMethod::Smoking => "minecraft:smoking",
+                CookingMethod::Campfire => "minecraft:campfire_cooking",
+            },
+            RecipeKind::Stonecutting { .. } => "minecraft:stonecutting",
+        }
+    }
+
+    fn matches_grid(&self, grid: &Grid) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped { width, height, ingredients, .. } => {
+                if grid.width != *width || grid.height != *height {
+                    return false;
+                }
+                // Shaped recipes also work mirrored left to right
+                [false, true].iter().any(|mirrored| {
+                    grid.items.iter().enumerate().all(|(i, item)| {
+                        let (row, column) = (i / width, i % width);
+                        let column = if *mirrored { width - 1 - column } else { column };
+                        accepts(&ingredients[row * width + column], *item)
+                    })
+                })
+            },
+            RecipeKind::Shapeless { ingredients, .. } => {
+                let items: Vec<&ItemType> = grid.items.iter().flatten().cloned().collect();
+                let mut used = vec![false; items.len()];
+                items.len() == ingredients.len()
+                    && assign(ingredients, &items, &mut used)
+            },
+            _ => false,
+        }
+    }
+}
+
+pub fn all() -> &'static [Recipe] {
+    &RECIPES
+}
+
+pub fn find(id: &str) -> Option<&'static Recipe> {
+    RECIPES.iter().find(|recipe| recipe.id == id)
+}
+
+/// Crafting recipe for what's in a 2x2 or 3x3 grid, given row by row
+pub fn find_crafting(grid: &[Option<ItemStack>], width: usize) -> Option<&'static Recipe> {
+    let grid = Grid::trim(grid, width)?;
+    RECIPES.iter().find(|recipe| recipe.matches_grid(&grid))
+}
+
+/// Furnace, blast furnace, smoker or campfire recipe for an item
+pub fn find_cooking(method: CookingMethod, item: &ItemType) -> Option<&'static Recipe> {
+    RECIPES.iter().find(|recipe| match &recipe.kind {
+        RecipeKind::Cooking { method: own, ingredient, .. } => {
+            *own == method && accepts(ingredient, Some(item))
+        },
+        _ => false,
+    })
+}
+
+/// What's left in the grid after crafting with an item, like the
+/// empty bucket from a milk bucket
+pub fn remainder(item: &ItemType) -> Option<&'static ItemType> {
+    let name = match item.to_str() {
+        "minecraft:milk_bucket" | "minecraft:water_bucket"
+            | "minecraft:lava_bucket" => "minecraft:bucket",
+        "minecraft:honey_bottle" | "minecraft:dragon_breath" => "minecraft:glass_bottle",
+        _ => return None,
+    };
+    ItemType::from_str(name).ok()
+}
+
+fn accepts(ingredient: &Ingredient, item: Option<&ItemType>) -> bool {
+    match item {
+        Some(item) => ingredient.iter()
+            .any(|accepted| accepted.to_numeric() == item.to_numeric()),
+        None => ingredient.is_empty(),
+    }
+}
+
+/// Gives each ingredient a different item, trying every combination since
+/// an item can fit more than one ingredient
+fn assign(ingredients: &[Ingredient], items: &[&ItemType], used: &mut [bool]) -> bool {
+    let (ingredient, rest) = match ingredients.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for i in 0..items.len() {
+        if !used[i] && accepts(ingredient, Some(items[i])) {
+            used[i] = true;
+            if assign(rest, items, used) {
+                return true;
+            }
+            used[i] = false;
+        }
+    }
+    false
+}
+
+/// Smallest rectangle around the items of a crafting grid
+struct Grid {
+    width: usize,
+    height: usize,
+    items: Vec<Option<&'static ItemType>>,
+}
+
+impl Grid {
+    fn trim(grid: &[Option<ItemStack>], width: usize) -> Option<Self> {
+        let filled: Vec<(usize, usize)> = grid.iter().enumerate()
+            .filter(|(_, stack)| stack.is_some())
+            .map(|(i, _)| (i / width, i % width))
+            .collect();
+        let top = filled.iter().map(|(row, _)| *row).min()?;
+        let bottom = filled.iter().map(|(row, _)| *row).max()?;
+        let left = filled.iter().map(|(_, column)| *column).min()?;
+        let right = filled.iter().map(|(_, column)| *column).max()?;
+        let items = (top..=bottom)
+            .flat_map(|row| (left..=right).map(move |column| row * width + column))
+            .map(|i| grid[i].as_ref().map(|stack| stack.item))
+            .collect();
+        Some(Self {
+            width: right - left + 1,
+            height: bottom - top + 1,
+            items,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(names: &[&str]) -> Vec<Option<ItemStack>> {
+        names.iter()
+            .map(|name| match *name {
+                "" => None,
+                name => Some(ItemStack {
+                    item: ItemType::from_str(name).unwrap(),
+                    count: 1,
+                    nbt: None,
+                }),
+            })
+            .collect()
+    }
+
+    fn crafted(names: &[&str], width: usize) -> Option<&'static str> {
+        find_crafting(&grid(names), width).map(|recipe| recipe.result().item.to_str())
+    }
+
+    #[test]
+    fn shaped_anywhere_in_the_grid() {
+        let planks = "minecraft:birch_planks";
+        assert_eq!(crafted(&["", planks, "", planks], 2), Some("minecraft:stick"));
+        assert_eq!(crafted(&[
+            "", planks, "",
+            "", planks, "",
+            "", "", "",
+        ], 3), Some("minecraft:stick"));
+        assert_eq!(crafted(&[planks, "", "", planks], 2), None);
+    }
+
+    #[test]
+    fn shaped_mirrored() {
+        let planks = "minecraft:oak_planks";
+        let stick = "minecraft:stick";
+        let axe = [
+            "", planks, planks,
+            "", stick, planks,
+            "", stick, "",
+        ];
+        assert_eq!(crafted(&axe, 3), Some("minecraft:wooden_axe"));
+    }
+
+    #[test]
+    fn shapeless_any_order() {
+        let dye = ["minecraft:white_dye", "", "", "minecraft:black_dye"];
+        assert_eq!(crafted(&dye, 2), Some("minecraft:gray_dye"));
+        assert_eq!(crafted(&["", "minecraft:crimson_stem", "", ""], 2),
+            Some("minecraft:crimson_planks"));
+    }
+
+    #[test]
+    fn cooking() {
+        let ore = ItemType::from_str("minecraft:iron_ore").unwrap();
+        let recipe = find_cooking(CookingMethod::Blasting, ore).unwrap();
+        assert_eq!(recipe.result().item.to_str(), "minecraft:iron_ingot");
+    }
+}
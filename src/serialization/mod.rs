@@ -1,5 +1,7 @@
 mod compact_long;
 mod varint;
+mod nbt_tags;
 
 pub use compact_long::CompactLong;
-pub use varint::push_varint;
+pub use varint::{push_varint, push_varlong};
+pub use nbt_tags::{compound_to_value, overlay_compound, value_to_compound};
@@ -0,0 +1,107 @@
+use anvil_nbt::{CompoundTag, Tag};
+use nbt::Value;
+
+/// Region files use one nbt crate and packets another, 
+/// these move values between the two
+pub fn compound_to_value(compound: &CompoundTag) -> Value {
+    Value::Compound(compound.iter()
+        .map(|(name, tag)| (name.clone(), tag_to_value(tag)))
+        .collect())
+}
+
+pub fn value_to_compound(value: &Value) -> Option<CompoundTag> {
+    match value_to_tag(value) {
+        Tag::Compound(compound) => Some(compound),
+        _ => None,
+    }
+}
+
+pub fn tag_to_value(tag: &Tag) -> Value {
+    match tag {
+        Tag::Byte(value) => Value::Byte(*value),
+        Tag::Short(value) => Value::Short(*value),
+        Tag::Int(value) => Value::Int(*value),
+        Tag::Long(value) => Value::Long(*value),
+        Tag::Float(value) => Value::Float(*value),
+        Tag::Double(value) => Value::Double(*value),
+        Tag::ByteArray(value) => Value::ByteArray(value.clone()),
+        Tag::String(value) => Value::String(value.clone()),
+        Tag::List(list) => Value::List(list.iter().map(tag_to_value).collect()),
+        Tag::Compound(compound) => compound_to_value(compound),
+        Tag::IntArray(value) => Value::IntArray(value.clone()),
+        Tag::LongArray(value) => Value::LongArray(value.clone()),
+    }
+}
+
+pub fn value_to_tag(value: &Value) -> Tag {
+    match value {
+        Value::Byte(value) => Tag::Byte(*value),
+        Value::Short(value) => Tag::Short(*value),
+        Value::Int(value) => Tag::Int(*value),
+        Value::Long(value) => Tag::Long(*value),
+        Value::Float(value) => Tag::Float(*value),
+        Value::Double(value) => Tag::Double(*value),
+        Value::ByteArray(value) => Tag::ByteArray(value.clone()),
+        Value::String(value) => Tag::String(value.clone()),
+        Value::List(list) => Tag::List(list.iter().map(value_to_tag).collect()),
+        Value::Compound(map) => {
+            let mut compound = CompoundTag::new();
+            for (name, value) in map {
+                compound.insert(name, value_to_tag(value));
+            }
+            Tag::Compound(compound)
+        },
+        Value::IntArray(value) => Tag::IntArray(value.clone()),
+        Value::LongArray(value) => Tag::LongArray(value.clone()),
+    }
+}
+
+/// Copies the tags in `ours` over `base`, merging nested compounds so the
+/// tags only `base` has are kept at every level
+pub fn overlay_compound(mut base: CompoundTag, ours: CompoundTag) -> CompoundTag {
+    for (name, tag) in ours {
+        let merged = match (base.get_compound_tag(&name), tag) {
+            (Ok(old), Tag::Compound(new)) => Tag::Compound(overlay_compound(old.clone(), new)),
+            (_, tag) => tag,
+        };
+        base.insert(name, merged);
+    }
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut compound = CompoundTag::new();
+        compound.insert_str("id", "minecraft:sign");
+        compound.insert_i32("x", -3);
+        compound.insert_compound_tag_vec("Items", vec![CompoundTag::new()]);
+        let back = value_to_compound(&compound_to_value(&compound)).unwrap();
+        assert_eq!(back.get_str("id").unwrap(), "minecraft:sign");
+        assert_eq!(back.get_i32("x").unwrap(), -3);
+        assert_eq!(back.get_compound_tag_vec("Items").unwrap().len(), 1);
+    }
+    #[test]
+    fn overlay_keeps_unknown_tags() {
+        let mut base = CompoundTag::new();
+        base.insert_str("WanderingTraderId", "trader");
+        let mut rules = CompoundTag::new();
+        rules.insert_str("doFireTick", "true");
+        rules.insert_str("keepInventory", "false");
+        base.insert_compound_tag("GameRules", rules);
+        let mut ours = CompoundTag::new();
+        ours.insert_i64("Time", 20);
+        let mut rules = CompoundTag::new();
+        rules.insert_str("keepInventory", "true");
+        ours.insert_compound_tag("GameRules", rules);
+        let merged = overlay_compound(base, ours);
+        assert_eq!(merged.get_str("WanderingTraderId").unwrap(), "trader");
+        assert_eq!(merged.get_i64("Time").unwrap(), 20);
+        let rules = merged.get_compound_tag("GameRules").unwrap();
+        assert_eq!(rules.get_str("doFireTick").unwrap(), "true");
+        assert_eq!(rules.get_str("keepInventory").unwrap(), "true");
+    }
+}
@@ -12,9 +12,23 @@ pub fn push_varint(mut value: u32, buffer: &mut Vec<u8>) {
     }
 }
 
+pub fn push_varlong(mut value: u64, buffer: &mut Vec<u8>) {
+    loop {
+        let mut byte = value as u8 & 0b01111111;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0b10000000;
+        }
+        buffer.push(byte);
+        if value == 0 {
+            break
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::push_varint;
+    use super::{push_varint, push_varlong};
 
     #[test]
     fn push_varint_test() {
@@ -25,6 +39,13 @@ mod tests {
         assert_eq!(test_value(-1i32 as u32), vec![0xff, 0xff, 0xff, 0xff, 0x0f]);
     }
 
+    #[test]
+    fn push_varlong_test() {
+        let mut buffer = vec![];
+        push_varlong(-1i64 as u64, &mut buffer);
+        assert_eq!(buffer, vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+    }
+
     fn test_value(value: u32) -> Vec<u8> {
         let mut buffer = vec![];
         push_varint(value, &mut buffer);
@@ -1 +1,59 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::items::ItemType;
+
 pub static TAG_PACKET: &[u8] = include_bytes!("tag_packet.dat");
+
+/// Embeds the item tags the tag packet is generated from
+macro_rules! item_tags {
+    ($($name:literal),* $(,)?) => {
+        &[$(($name, include_str!(concat!("tag_packet_gen/tags/items/", $name, ".json")))),*]
+    };
+}
+
+const ITEM_TAG_FILES: &[(&str, &str)] = item_tags![
+    "acacia_logs", "anvil", "arrows", "banners", "beacon_payment_items", "beds",
+    "birch_logs", "boats", "buttons", "carpets", "coals", "creeper_drop_music_discs",
+    "crimson_stems", "dark_oak_logs", "doors", "fences", "fishes", "flowers", "gold_ores",
+    "jungle_logs", "leaves", "lectern_books", "logs", "logs_that_burn", "music_discs",
+    "non_flammable_wood", "oak_logs", "piglin_loved", "piglin_repellents", "planks", "rails",
+    "sand", "saplings", "signs", "slabs", "small_flowers", "soul_fire_base_blocks",
+    "spruce_logs", "stairs", "stone_bricks", "stone_crafting_materials",
+    "stone_tool_materials", "tall_flowers", "trapdoors", "walls", "warped_stems",
+    "wooden_buttons", "wooden_doors", "wooden_fences", "wooden_pressure_plates",
+    "wooden_slabs", "wooden_stairs", "wooden_trapdoors", "wool",
+];
+
+lazy_static! {
+    static ref ITEM_TAGS: HashMap<String, Vec<&'static ItemType>> = ITEM_TAG_FILES.iter()
+        .map(|(name, _)| (format!("minecraft:{}", name), resolve_item_tag(name)))
+        .collect();
+}
+
+#[derive(Deserialize)]
+struct TagJson {
+    values: Vec<String>,
+}
+
+/// Items in a tag like minecraft:planks
+pub fn item_tag(name: &str) -> Option<&'static [&'static ItemType]> {
+    ITEM_TAGS.get(name).map(Vec::as_slice)
+}
+
+/// Entries starting with # are other tags, included with all their items
+fn resolve_item_tag(name: &str) -> Vec<&'static ItemType> {
+    let json = ITEM_TAG_FILES.iter()
+        .find(|(file, _)| *file == name)
+        .map(|(_, json)| *json)
+        .unwrap_or_else(|| panic!("Unknown item tag {}", name));
+    let tag: TagJson = serde_json::from_str(json).unwrap();
+    tag.values.iter()
+        .flat_map(|value| match value.strip_prefix("#minecraft:") {
+            Some(other) => resolve_item_tag(other),
+            None => ItemType::from_str(value).into_iter().collect(),
+        })
+        .collect()
+}
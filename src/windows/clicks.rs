@@ -0,0 +1,694 @@
+use std::ops::Range;
+
+use crate::block_entities::is_fuel;
+use crate::items::ItemStack;
+use crate::net::ClickMode;
+use crate::players::GameMode;
+use crate::recipes::{CookingMethod, find_cooking, find_crafting, remainder};
+
+/// Slot number of clicks outside the window
+pub const OUTSIDE: i16 = -999;
+const ARMOR: [&str; 4] = ["head", "chest", "legs", "feet"];
+
+/// What the slots of a window are, decides where shift clicks send
+/// items and what each slot takes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// Crafting result and grid, armor, main inventory, hotbar and offhand
+    Inventory,
+    /// Crafting result and 3x3 grid, then the player's storage and
+    /// the hidden offhand
+    CraftingTable,
+    /// Slots of a container and then the player's storage. The offhand
+    /// isn't shown but goes last, so it can still be swapped with
+    Container(usize),
+    /// Input, fuel and result, then the storage and offhand like containers
+    Furnace(CookingMethod),
+}
+
+impl Layout {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Inventory => 46,
+            Self::CraftingTable => 47,
+            Self::Container(size) => size + 37,
+            Self::Furnace(_) => 40,
+        }
+    }
+
+    /// Slots the client knows about
+    pub fn visible(&self) -> usize {
+        match self {
+            Self::Inventory => 46,
+            Self::CraftingTable => 46,
+            Self::Container(size) => size + 36,
+            Self::Furnace(_) => 39,
+        }
+    }
+
+    /// First slot of the main inventory, everything before it belongs
+    /// to the window
+    pub fn storage_start(&self) -> usize {
+        self.len() - 37
+    }
+
+    /// Slots of the crafting grid and its width, the result is slot 0
+    fn crafting_grid(&self) -> Option<(Range<usize>, usize)> {
+        match self {
+            Self::Inventory => Some((1..5, 2)),
+            Self::CraftingTable => Some((1..10, 3)),
+            Self::Container(_) | Self::Furnace(_) => None,
+        }
+    }
+
+    fn is_result(&self, slot: usize) -> bool {
+        slot == 0 && self.crafting_grid().is_some()
+    }
+
+    fn offhand(&self) -> usize {
+        self.len() - 1
+    }
+
+    fn hotbar(&self, button: u8) -> Option<usize> {
+        match button {
+            0..=8 => Some(self.len() - 10 + button as usize),
+            40 => Some(self.offhand()),
+            _ => None,
+        }
+    }
+
+    fn accepts(&self, slot: usize, stack: &ItemStack) -> bool {
+        match (self, slot) {
+            _ if self.is_result(slot) => false,
+            (Self::Inventory, 5..=8) => {
+                stack.item.equipment_slot() == Some(ARMOR[slot - 5])
+            },
+            // Empty buckets go in the fuel slot for drying sponges
+            (Self::Furnace(_), 1) => {
+                is_fuel(stack.item) || stack.item.to_str() == "minecraft:bucket"
+            },
+            (Self::Furnace(_), 2) => false,
+            _ => true,
+        }
+    }
+
+    fn max_in_slot(&self, slot: usize, stack: &ItemStack) -> u8 {
+        match (self, slot) {
+            (Self::Inventory, 5..=8) => 1,
+            _ => stack.max_stack_size(),
+        }
+    }
+
+    /// Range a shift click moves a stack into, and whether it's filled
+    /// from the end. Armor only goes to its slot while that one is empty
+    fn quick_move_target(&self, slot: usize, stack: &ItemStack,
+        slots: &[Option<ItemStack>]) -> (Range<usize>, bool)
+    {
+        match *self {
+            Self::Container(size) if slot < size => (size..size + 36, true),
+            Self::Container(size) => (0..size, false),
+            Self::Furnace(method) => match slot {
+                0..=2 => (3..39, slot == 2),
+                _ if find_cooking(method, stack.item).is_some() => (0..1, false),
+                _ if is_fuel(stack.item) => (1..2, false),
+                3..=29 => (30..39, false),
+                _ => (3..30, false),
+            },
+            Self::CraftingTable => match slot {
+                0 => (10..46, true),
+                1..=9 => (10..46, false),
+                10..=36 => (37..46, false),
+                _ => (10..37, false),
+            },
+            Self::Inventory => {
+                let equipment = stack.item.equipment_slot();
+                let armor = ARMOR.iter().position(|piece| Some(*piece) == equipment)
+                    .map(|piece| 5 + piece)
+                    .filter(|armor| slots[*armor].is_none());
+                match slot {
+                    0 => (9..45, true),
+                    1..=8 => (9..45, false),
+                    _ if armor.is_some() => {
+                        let armor = armor.unwrap();
+                        (armor..armor + 1, false)
+                    },
+                    _ if equipment == Some("offhand") && slot != 45
+                        && slots[45].is_none() => (45..46, false),
+                    9..=35 => (36..45, false),
+                    36..=44 => (9..36, false),
+                    _ => (9..45, false),
+                }
+            },
+        }
+    }
+}
+
+/// Slots a stack is being spread over by dragging
+pub struct Drag {
+    /// 0 to split the stack evenly, 1 for one item each and 2 for
+    /// full stacks in creative
+    kind: u8,
+    slots: Vec<usize>,
+}
+
+pub struct ClickResult {
+    /// What vanilla returns for the click, the client sends what it got
+    /// so mismatches can be told apart
+    pub clicked: Option<ItemStack>,
+    /// Thrown out of the window
+    pub dropped: Vec<ItemStack>,
+}
+
+/// Applies a click to the slots of a window. None if it makes no sense,
+/// in which case nothing changed and the client has to be resynced
+pub fn apply_click(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, drag: &mut Option<Drag>, slot: i16,
+    button: u8, mode: ClickMode, game_mode: GameMode) -> Option<ClickResult>
+{
+    let result = click_slots(layout, slots, cursor, drag, slot, button, mode, game_mode);
+    update_result(layout, slots);
+    result
+}
+
+fn click_slots(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, drag: &mut Option<Drag>, slot: i16,
+    button: u8, mode: ClickMode, game_mode: GameMode) -> Option<ClickResult>
+{
+    let creative = game_mode == GameMode::Creative;
+    let mut result = ClickResult {
+        clicked: None,
+        dropped: vec![],
+    };
+    if mode != ClickMode::QuickCraft {
+        *drag = None;
+    }
+    if slot == OUTSIDE {
+        match (mode, button) {
+            (ClickMode::Pickup, 0) => result.dropped.extend(cursor.take()),
+            (ClickMode::Pickup, 1) => {
+                if let Some(held) = cursor.take() {
+                    result.dropped.push(ItemStack { count: 1, ..held.clone() });
+                    *cursor = remove(held, 1);
+                }
+            },
+            (ClickMode::QuickCraft, _) => {
+                quick_craft(layout, slots, cursor, drag, None, button, creative)?
+            },
+            (ClickMode::Throw, _) => (),
+            _ => return None,
+        }
+        return Some(result);
+    }
+    if slot < 0 || slot as usize >= layout.visible() {
+        return None;
+    }
+    let index = slot as usize;
+    if layout.is_result(index) && mode != ClickMode::Clone {
+        result.clicked = slots[index].clone();
+        click_result(layout, slots, cursor, &mut result, button, mode)?;
+        return Some(result);
+    }
+    match (mode, button) {
+        (ClickMode::Pickup, 0) | (ClickMode::Pickup, 1) => {
+            result.clicked = slots[index].clone();
+            if button == 0 {
+                left_click(layout, index, slots, cursor);
+            } else {
+                right_click(layout, index, slots, cursor);
+            }
+        },
+        (ClickMode::QuickMove, _) => {
+            if let Some(stack) = slots[index].take() {
+                // Vanilla returns nothing when nothing could be moved
+                let clicked = stack.clone();
+                let (range, reverse) = layout.quick_move_target(index, &stack, slots);
+                slots[index] = move_into(layout, stack, slots, range, reverse);
+                if slots[index].as_ref().map_or(0, |left| left.count) < clicked.count {
+                    result.clicked = Some(clicked);
+                }
+            }
+        },
+        (ClickMode::Swap, _) => {
+            let hotbar = layout.hotbar(button)?;
+            let fits = slots[hotbar].as_ref()
+                .map(|stack| layout.accepts(index, stack))
+                .unwrap_or(true);
+            if fits {
+                slots.swap(index, hotbar);
+            }
+        },
+        (ClickMode::Clone, 2) if creative => {
+            if cursor.is_none() {
+                *cursor = slots[index].clone()
+                    .map(|stack| ItemStack { count: stack.max_stack_size(), ..stack });
+            }
+        },
+        (ClickMode::Throw, 0) | (ClickMode::Throw, 1) => {
+            if cursor.is_none() {
+                if let Some(stack) = slots[index].take() {
+                    let count = if button == 0 { 1 } else { stack.count };
+                    result.dropped.push(ItemStack { count, ..stack.clone() });
+                    slots[index] = remove(stack, count);
+                }
+            }
+        },
+        (ClickMode::QuickCraft, _) => {
+            quick_craft(layout, slots, cursor, drag, Some(index), button, creative)?;
+        },
+        (ClickMode::PickupAll, 0) | (ClickMode::PickupAll, 1) => {
+            if slots[index].is_none() {
+                pickup_all(layout, slots, cursor, button == 1);
+            }
+        },
+        _ => return None,
+    }
+    Some(result)
+}
+
+fn left_click(layout: Layout, index: usize, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>)
+{
+    let slot = &mut slots[index];
+    match (slot.as_mut(), cursor.as_mut()) {
+        (_, Some(held)) if !layout.accepts(index, held) => {
+            take_whole(slot, cursor);
+        },
+        (Some(stack), Some(held)) if stack.is_same_kind(held) => {
+            let limit = layout.max_in_slot(index, stack);
+            let moved = held.count.min(limit.saturating_sub(stack.count));
+            stack.count += moved;
+            *cursor = remove(cursor.take().unwrap(), moved);
+        },
+        (_, Some(held)) if held.count > layout.max_in_slot(index, held) => {
+            if slot.is_none() {
+                let limit = layout.max_in_slot(index, held);
+                *slot = Some(ItemStack { count: limit, ..held.clone() });
+                *cursor = remove(cursor.take().unwrap(), limit);
+            }
+        },
+        _ => std::mem::swap(slot, cursor),
+    }
+}
+
+fn right_click(layout: Layout, index: usize, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>)
+{
+    let slot = &mut slots[index];
+    match (slot.as_mut(), cursor.as_mut()) {
+        (Some(stack), None) => {
+            let taken = (stack.count + 1) / 2;
+            *cursor = Some(ItemStack { count: taken, ..stack.clone() });
+            *slot = remove(slot.take().unwrap(), taken);
+        },
+        (_, Some(held)) if !layout.accepts(index, held) => {
+            take_whole(slot, cursor);
+        },
+        (None, Some(held)) => {
+            *slot = Some(ItemStack { count: 1, ..held.clone() });
+            *cursor = remove(cursor.take().unwrap(), 1);
+        },
+        (Some(stack), Some(held)) if stack.is_same_kind(held) => {
+            if stack.count < layout.max_in_slot(index, stack) {
+                stack.count += 1;
+                *cursor = remove(cursor.take().unwrap(), 1);
+            }
+        },
+        (Some(_), Some(held)) if held.count > layout.max_in_slot(index, held) => (),
+        _ => std::mem::swap(slot, cursor),
+    }
+}
+
+/// Slots that can't take what the cursor holds can still be emptied into it
+fn take_whole(slot: &mut Option<ItemStack>, cursor: &mut Option<ItemStack>) {
+    if let (Some(stack), Some(held)) = (slot.as_ref(), cursor.as_mut()) {
+        if stack.is_same_kind(held) && held.count + stack.count <= held.max_stack_size() {
+            held.count += stack.count;
+            *slot = None;
+        }
+    }
+}
+
+/// Dragging goes start, a packet for each slot, end. The stack is only
+/// spread once it ends
+fn quick_craft(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, drag: &mut Option<Drag>, slot: Option<usize>,
+    button: u8, creative: bool) -> Option<()>
+{
+    let kind = button >> 2;
+    // Full stacks out of nowhere are only for creative
+    let allowed = kind < 2 || (kind == 2 && creative);
+    match button & 3 {
+        0 if slot.is_none() && allowed && cursor.is_some() => {
+            *drag = Some(Drag { kind, slots: vec![] });
+        },
+        1 => {
+            let (current, held, index) = (drag.as_mut()?, cursor.as_ref()?, slot?);
+            let fits = slots[index].as_ref()
+                .map(|stack| stack.is_same_kind(held)
+                    && stack.count < layout.max_in_slot(index, stack))
+                .unwrap_or(true);
+            let enough = kind == 2 || current.slots.len() < held.count as usize;
+            if current.kind == kind && fits && enough && layout.accepts(index, held)
+                && !current.slots.contains(&index)
+            {
+                current.slots.push(index);
+            }
+        },
+        2 => {
+            let finished = drag.take()?;
+            if finished.kind != kind || cursor.is_none() {
+                return Some(());
+            }
+            if let [index] = finished.slots[..] {
+                // A drag over one slot is a normal click
+                return match kind {
+                    0 => Some(left_click(layout, index, slots, cursor)),
+                    1 => Some(right_click(layout, index, slots, cursor)),
+                    _ => Some(()),
+                };
+            }
+            spread(layout, slots, cursor, &finished);
+        },
+        _ => {
+            *drag = None;
+            return None;
+        },
+    }
+    Some(())
+}
+
+fn spread(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, drag: &Drag)
+{
+    let held = match cursor.take() {
+        Some(held) => held,
+        None => return,
+    };
+    let each = match drag.kind {
+        0 => held.count / drag.slots.len().max(1) as u8,
+        1 => 1,
+        _ => held.max_stack_size(),
+    };
+    let mut consumed = 0;
+    for index in &drag.slots {
+        let current = slots[*index].as_ref().map(|stack| stack.count).unwrap_or(0);
+        let limit = layout.max_in_slot(*index, &held);
+        let count = (current + each).min(limit);
+        if drag.kind != 2 {
+            consumed += count - current;
+        }
+        slots[*index] = Some(ItemStack { count, ..held.clone() });
+    }
+    *cursor = remove(held, consumed);
+}
+
+/// Double click, fills the cursor with matching items from the window,
+/// taking from partial stacks first
+fn pickup_all(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, reverse: bool)
+{
+    let held = match cursor.as_mut() {
+        Some(held) => held,
+        None => return,
+    };
+    let mut order: Vec<usize> = (0..layout.visible()).collect();
+    if reverse {
+        order.reverse();
+    }
+    for full_stacks in [false, true] {
+        for index in &order {
+            if held.count >= held.max_stack_size() {
+                return;
+            }
+            if layout.is_result(*index) {
+                continue;
+            }
+            if let Some(stack) = &mut slots[*index] {
+                let is_full = stack.count >= stack.max_stack_size();
+                if stack.is_same_kind(held) && (full_stacks || !is_full) {
+                    let taken = stack.count.min(held.max_stack_size() - held.count);
+                    held.count += taken;
+                    stack.count -= taken;
+                    if stack.count == 0 {
+                        slots[*index] = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Taking from the result slot crafts, every way of taking it
+/// takes the whole result
+fn click_result(layout: Layout, slots: &mut [Option<ItemStack>],
+    cursor: &mut Option<ItemStack>, result: &mut ClickResult, button: u8,
+    mode: ClickMode) -> Option<()>
+{
+    let crafted = match &slots[0] {
+        Some(crafted) => crafted.clone(),
+        None => return Some(()),
+    };
+    match (mode, button) {
+        (ClickMode::Pickup, 0) | (ClickMode::Pickup, 1) => match cursor {
+            None => *cursor = take_result(layout, slots),
+            Some(held) if held.is_same_kind(&crafted)
+                && held.count + crafted.count <= held.max_stack_size() =>
+            {
+                held.count += crafted.count;
+                take_result(layout, slots);
+            },
+            _ => (),
+        },
+        (ClickMode::QuickMove, _) => {
+            // Crafts as many times as fit, without leaving part of a result behind
+            let (range, reverse) = layout.quick_move_target(0, &crafted, slots);
+            while let Some(stack) = slots[0].clone() {
+                if !stack.is_same_kind(&crafted) {
+                    break;
+                }
+                let mut moved = slots.to_vec();
+                if move_into(layout, stack, &mut moved, range.clone(), reverse).is_some() {
+                    break;
+                }
+                slots.clone_from_slice(&moved);
+                take_result(layout, slots);
+            }
+        },
+        (ClickMode::Swap, _) => {
+            let hotbar = layout.hotbar(button)?;
+            if slots[hotbar].is_none() {
+                slots[hotbar] = take_result(layout, slots);
+            }
+        },
+        (ClickMode::Throw, 0) | (ClickMode::Throw, 1) => {
+            if cursor.is_none() {
+                result.dropped.extend(take_result(layout, slots));
+            }
+        },
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Uses up one of each ingredient and returns what was crafted
+fn take_result(layout: Layout, slots: &mut [Option<ItemStack>]) -> Option<ItemStack> {
+    let (grid, _) = layout.crafting_grid()?;
+    let crafted = slots[0].take()?;
+    for index in grid {
+        if let Some(stack) = slots[index].take() {
+            let left = remainder(stack.item);
+            slots[index] = remove(stack, 1);
+            if slots[index].is_none() {
+                slots[index] = left.map(|item| ItemStack { item, count: 1, nbt: None });
+            }
+        }
+    }
+    update_result(layout, slots);
+    Some(crafted)
+}
+
+/// Puts what the grid crafts into the result slot
+fn update_result(layout: Layout, slots: &mut [Option<ItemStack>]) {
+    if let Some((grid, width)) = layout.crafting_grid() {
+        slots[0] = find_crafting(&slots[grid], width)
+            .map(|recipe| recipe.result().clone());
+    }
+}
+
+/// Merges a stack into the slots in a range, filling existing stacks before
+/// empty slots like vanilla does. Returns what didn't fit
+fn move_into(layout: Layout, mut stack: ItemStack, slots: &mut [Option<ItemStack>],
+    range: Range<usize>, reverse: bool) -> Option<ItemStack>
+{
+    let mut order: Vec<usize> = range.collect();
+    if reverse {
+        order.reverse();
+    }
+    for index in &order {
+        if let Some(current) = &mut slots[*index] {
+            if current.is_same_kind(&stack) {
+                let limit = layout.max_in_slot(*index, current);
+                let moved = stack.count.min(limit.saturating_sub(current.count));
+                current.count += moved;
+                stack.count -= moved;
+                if stack.count == 0 {
+                    return None;
+                }
+            }
+        }
+    }
+    for index in order {
+        if slots[index].is_none() && layout.accepts(index, &stack) {
+            let limit = layout.max_in_slot(index, &stack);
+            if stack.count <= limit {
+                slots[index] = Some(stack);
+                return None;
+            }
+            slots[index] = Some(ItemStack { count: limit, ..stack.clone() });
+            stack.count -= limit;
+        }
+    }
+    Some(stack)
+}
+
+/// Takes some items out of a stack, None if there's nothing left
+fn remove(mut stack: ItemStack, count: u8) -> Option<ItemStack> {
+    stack.count = stack.count.saturating_sub(count);
+    if stack.count == 0 {
+        None
+    } else {
+        Some(stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::items::ItemType;
+    use super::*;
+
+    fn stack(name: &str, count: u8) -> Option<ItemStack> {
+        Some(ItemStack {
+            item: ItemType::from_str(name).unwrap(),
+            count,
+            nbt: None,
+        })
+    }
+
+    fn click(layout: Layout, slots: &mut [Option<ItemStack>],
+        cursor: &mut Option<ItemStack>, slot: i16, button: u8, mode: ClickMode)
+    {
+        assert!(apply_click(layout, slots, cursor, &mut None, slot, button, mode,
+            GameMode::Survival).is_some());
+    }
+
+    #[test]
+    fn pickup_and_merge() {
+        let layout = Layout::Container(2);
+        let mut slots = vec![None; layout.len()];
+        slots[0] = stack("minecraft:stone", 40);
+        slots[1] = stack("minecraft:stone", 40);
+        let mut cursor = None;
+        click(layout, &mut slots, &mut cursor, 0, 0, ClickMode::Pickup);
+        assert_eq!(cursor.as_ref().unwrap().count, 40);
+        click(layout, &mut slots, &mut cursor, 1, 0, ClickMode::Pickup);
+        assert_eq!(slots[1].as_ref().unwrap().count, 64);
+        assert_eq!(cursor.as_ref().unwrap().count, 16);
+        click(layout, &mut slots, &mut cursor, 0, 1, ClickMode::Pickup);
+        assert_eq!(slots[0].as_ref().unwrap().count, 1);
+        assert_eq!(cursor.as_ref().unwrap().count, 15);
+    }
+
+    #[test]
+    fn shift_click_fills_stacks_first() {
+        let layout = Layout::Container(1);
+        let mut slots = vec![None; layout.len()];
+        slots[0] = stack("minecraft:dirt", 10);
+        slots[36] = stack("minecraft:dirt", 60);
+        let mut cursor = None;
+        click(layout, &mut slots, &mut cursor, 0, 0, ClickMode::QuickMove);
+        assert!(slots[0].is_none());
+        assert_eq!(slots[36].as_ref().unwrap().count, 64);
+        assert_eq!(slots[35].as_ref().unwrap().count, 6);
+    }
+
+    #[test]
+    fn armor_slots_take_one_piece() {
+        let layout = Layout::Inventory;
+        let mut slots = vec![None; layout.len()];
+        slots[20] = stack("minecraft:iron_boots", 1);
+        let mut cursor = stack("minecraft:stone", 5);
+        click(layout, &mut slots, &mut cursor, 5, 0, ClickMode::Pickup);
+        assert!(slots[5].is_none());
+        click(layout, &mut slots, &mut cursor, 20, 0, ClickMode::QuickMove);
+        assert!(slots[20].is_none());
+        assert!(slots[8].is_some());
+    }
+
+    #[test]
+    fn shift_click_into_full_inventory_moves_nothing() {
+        let layout = Layout::Container(1);
+        let mut slots = vec![stack("minecraft:stone", 64); layout.len()];
+        slots[0] = stack("minecraft:dirt", 10);
+        let result = apply_click(layout, &mut slots, &mut None, &mut None, 0, 0,
+            ClickMode::QuickMove, GameMode::Survival).unwrap();
+        assert!(result.clicked.is_none());
+        assert_eq!(slots[0].as_ref().unwrap().count, 10);
+    }
+
+    #[test]
+    fn armor_moves_within_inventory_when_worn() {
+        let layout = Layout::Inventory;
+        let mut slots = vec![None; layout.len()];
+        slots[8] = stack("minecraft:iron_boots", 1);
+        slots[20] = stack("minecraft:golden_boots", 1);
+        click(layout, &mut slots, &mut None, 20, 0, ClickMode::QuickMove);
+        assert!(slots[20].is_none());
+        let iron_boots = stack("minecraft:iron_boots", 1).unwrap();
+        assert!(slots[8].as_ref().unwrap().is_same_kind(&iron_boots));
+        // From the main inventory it goes to the hotbar instead
+        assert!(slots[36..45].iter().any(|slot| slot.is_some()));
+    }
+
+    #[test]
+    fn shift_click_crafts_everything() {
+        let layout = Layout::Inventory;
+        let mut slots = vec![None; layout.len()];
+        slots[1] = stack("minecraft:oak_log", 3);
+        update_result(layout, &mut slots);
+        assert_eq!(slots[0].as_ref().unwrap().item.to_str(), "minecraft:oak_planks");
+        let mut cursor = None;
+        click(layout, &mut slots, &mut cursor, 0, 0, ClickMode::QuickMove);
+        assert!(slots[0].is_none() && slots[1].is_none());
+        assert_eq!(slots[44].as_ref().unwrap().count, 12);
+    }
+
+    #[test]
+    fn drag_splits_evenly() {
+        let layout = Layout::Container(3);
+        let mut slots = vec![None; layout.len()];
+        let mut cursor = stack("minecraft:dirt", 10);
+        let mut drag = None;
+        for (slot, button) in [(OUTSIDE, 0), (0, 1), (1, 1), (2, 1), (OUTSIDE, 2)] {
+            apply_click(layout, &mut slots, &mut cursor, &mut drag, slot, button,
+                ClickMode::QuickCraft, GameMode::Survival).unwrap();
+        }
+        assert_eq!(slots[2].as_ref().unwrap().count, 3);
+        assert_eq!(cursor.as_ref().unwrap().count, 1);
+    }
+
+    #[test]
+    fn copying_stacks_needs_creative() {
+        let layout = Layout::Container(2);
+        let mut slots = vec![None; layout.len()];
+        slots[0] = stack("minecraft:diamond", 1);
+        let mut cursor = None;
+        assert!(apply_click(layout, &mut slots, &mut cursor, &mut None, 0, 2,
+            ClickMode::Clone, GameMode::Survival).is_none());
+        assert!(apply_click(layout, &mut slots, &mut cursor, &mut None, 0, 2,
+            ClickMode::Clone, GameMode::Creative).is_some());
+        assert_eq!(cursor.as_ref().unwrap().count, 64);
+        // Starting a drag of full stacks
+        assert!(apply_click(layout, &mut slots, &mut cursor, &mut None, OUTSIDE, 8,
+            ClickMode::QuickCraft, GameMode::Survival).is_none());
+    }
+}
@@ -0,0 +1,560 @@
+mod clicks;
+
+use std::collections::HashMap;
+
+use legion::*;
+use legion::systems::{Builder, CommandBuffer};
+use nalgebra::Vector3;
+use serde_json::{Value as Json, json};
+use tokio::sync::mpsc::UnboundedSender;
+use world::SubWorld;
+
+use crate::blocks::chest_partner;
+use crate::block_entities::{CONTAINER_SLOTS, cooking_method};
+use crate::chunks::World as ChunkWorld;
+use crate::dimensions::{DimensionId, Dimensions};
+use crate::entities::{EntityIdGenerator, Position, Rotation, throw_item};
+use crate::inventory::{EnderChest, Inventory, SlotIndex};
+use crate::items::ItemStack;
+use crate::net::{ClickMode, ClientboundPacket, PlayerConnection};
+use crate::players::{Experience, GameMode};
+use clicks::{Drag, Layout, apply_click};
+
+/// Players further than this from the block get their window closed
+const MAX_DISTANCE: f64 = 8.0;
+const BLAST_FURNACE_MENU: u32 = 9;
+const CRAFTING_MENU: u32 = 11;
+const FURNACE_MENU: u32 = 13;
+const SHULKER_BOX_MENU: u32 = 19;
+const SMOKER_MENU: u32 = 21;
+/// Result and the 3x3 grid
+const CRAFTING_TABLE_SLOTS: usize = 10;
+const FURNACE_RESULT: usize = 2;
+
+pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+    schedule.add_system(close_invalid_windows_system());
+    schedule.add_system(sync_furnace_windows_system());
+    resources.insert(Windows::new());
+}
+
+/// Where the slots of a window are stored
+#[derive(Clone, PartialEq)]
+pub enum Container {
+    /// Block entities of chests, barrels, shulker boxes and furnaces,
+    /// two for double chests with the top half first
+    Blocks(DimensionId, Vec<Vector3<i32>>),
+    /// The ender chest storage of the player that opened it
+    EnderChest,
+    /// The grid is kept in the window and emptied when it's closed
+    CraftingTable(DimensionId, Vector3<i32>),
+}
+
+impl Container {
+    /// What a block opens when clicked, if anything
+    pub fn at(chunks: &ChunkWorld, dimension: DimensionId, pos: Vector3<i32>)
+        -> Option<Self>
+    {
+        let block = chunks.get_block(&pos);
+        if block.btype.name == "minecraft:crafting_table" {
+            return Some(Self::CraftingTable(dimension, pos));
+        }
+        match block.btype.block_entity_id()? {
+            "minecraft:ender_chest" => Some(Self::EnderChest),
+            "minecraft:chest" | "minecraft:trapped_chest" => {
+                let mut positions = vec![pos];
+                if let Some(side) = chest_partner(block) {
+                    let other = pos + side;
+                    if chunks.get_block_entity(&other).is_some() {
+                        // The right half is the top of the window
+                        if block.props["type"] == "right" {
+                            positions.push(other);
+                        } else {
+                            positions.insert(0, other);
+                        }
+                    }
+                }
+                Some(Self::Blocks(dimension, positions))
+            },
+            "minecraft:barrel" | "minecraft:shulker_box" | "minecraft:furnace"
+                | "minecraft:blast_furnace" | "minecraft:smoker" =>
+            {
+                Some(Self::Blocks(dimension, vec![pos]))
+            },
+            _ => None,
+        }
+    }
+
+    fn get_items(&self, chunks: &ChunkWorld, ender_chest: &EnderChest)
+        -> Option<Vec<Option<ItemStack>>>
+    {
+        match self {
+            Self::Blocks(_, positions) => {
+                let mut items = vec![];
+                for pos in positions {
+                    let entity = chunks.get_block_entity(pos)?;
+                    items.extend(entity.items()?.iter().cloned());
+                }
+                Some(items)
+            },
+            Self::EnderChest => Some(ender_chest.items.clone()),
+            Self::CraftingTable(..) => Some(vec![None; CRAFTING_TABLE_SLOTS]),
+        }
+    }
+
+    fn set_items(&self, chunks: &ChunkWorld, ender_chest: &mut EnderChest,
+        items: &[Option<ItemStack>])
+    {
+        match self {
+            Self::Blocks(_, positions) => {
+                for (pos, items) in positions.iter().zip(items.chunks(CONTAINER_SLOTS)) {
+                    if let Some(mut entity) = chunks.get_block_entity(pos) {
+                        entity.set_items(items);
+                        chunks.set_block_entity(pos, entity);
+                    }
+                }
+            },
+            Self::EnderChest => ender_chest.items = items.to_vec(),
+            Self::CraftingTable(..) => (),
+        }
+    }
+
+    /// Slot of this container that shows a slot of another one,
+    /// for keeping everyone that looks at the same chest in sync
+    fn map_slot(&self, other: &Container, slot: usize) -> Option<usize> {
+        match (self, other) {
+            (Self::Blocks(dimension, positions), Self::Blocks(other_dimension, others))
+                if dimension == other_dimension =>
+            {
+                let pos = others.get(slot / CONTAINER_SLOTS)?;
+                let index = positions.iter().position(|own| own == pos)?;
+                Some(index * CONTAINER_SLOTS + slot % CONTAINER_SLOTS)
+            },
+            _ => None,
+        }
+    }
+
+    /// How clicks in the window work
+    fn layout(&self, chunks: &ChunkWorld, size: usize) -> Layout {
+        match self {
+            Self::CraftingTable(..) => Layout::CraftingTable,
+            Self::Blocks(_, positions) => chunks.get_block_entity(&positions[0])
+                .and_then(|entity| cooking_method(&entity.id))
+                .map(Layout::Furnace)
+                .unwrap_or(Layout::Container(size)),
+            Self::EnderChest => Layout::Container(size),
+        }
+    }
+
+    fn menu_kind(&self, chunks: &ChunkWorld, size: usize) -> u32 {
+        if let Self::CraftingTable(..) = self {
+            return CRAFTING_MENU;
+        }
+        if let Self::Blocks(_, positions) = self {
+            let entity = chunks.get_block_entity(&positions[0]);
+            match entity.as_ref().map(|entity| entity.id.as_str()) {
+                Some("minecraft:shulker_box") => return SHULKER_BOX_MENU,
+                Some("minecraft:furnace") => return FURNACE_MENU,
+                Some("minecraft:blast_furnace") => return BLAST_FURNACE_MENU,
+                Some("minecraft:smoker") => return SMOKER_MENU,
+                _ => (),
+            }
+        }
+        // Generic 9 by n menus go from 0 to 5, 1 to 6 rows
+        (size / 9) as u32 - 1
+    }
+
+    fn title(&self, chunks: &ChunkWorld) -> Json {
+        let key = match self {
+            Self::EnderChest => return json!({ "translate": "container.enderchest" }),
+            Self::CraftingTable(..) => "container.crafting",
+            Self::Blocks(_, positions) if positions.len() > 1 => "container.chestDouble",
+            Self::Blocks(_, positions) => {
+                let entity = chunks.get_block_entity(&positions[0]);
+                let custom_name = entity.as_ref()
+                    .and_then(|entity| entity.custom_name())
+                    .and_then(|name| serde_json::from_str(name).ok());
+                if let Some(name) = custom_name {
+                    return name;
+                }
+                match entity.map(|entity| entity.id) {
+                    Some(id) if id == "minecraft:barrel" => "container.barrel",
+                    Some(id) if id == "minecraft:shulker_box" => "container.shulkerBox",
+                    Some(id) if id == "minecraft:furnace" => "container.furnace",
+                    Some(id) if id == "minecraft:blast_furnace" => "container.blast_furnace",
+                    Some(id) if id == "minecraft:smoker" => "container.smoker",
+                    _ => "container.chest",
+                }
+            },
+        };
+        json!({ "translate": key })
+    }
+}
+
+struct OpenWindow {
+    id: u8,
+    container: Container,
+    /// Slots that belong to the container, the player's storage goes after
+    size: usize,
+    /// Result and grid of a crafting table
+    crafting: Vec<Option<ItemStack>>,
+    /// Container slots and window properties as the client last got them,
+    /// furnaces change on their own so they're compared every tick
+    shown: Vec<Option<ItemStack>>,
+    properties: Vec<i16>,
+    sender: UnboundedSender<ClientboundPacket>,
+}
+
+/// Click Window packet, the action number is what gets confirmed
+pub struct Click {
+    pub window: u8,
+    pub slot: i16,
+    pub button: u8,
+    pub action: u16,
+    pub mode: ClickMode,
+    pub clicked: Option<ItemStack>,
+}
+
+#[derive(Default)]
+struct ClickState {
+    drag: Option<Drag>,
+    /// Window and action of a rejected click, clicks are ignored until
+    /// the client acknowledges it like vanilla does
+    unconfirmed: Option<(u8, u16)>,
+}
+
+/// Container windows players have open, at most one each, and the
+/// state of clicks in them or the inventory
+pub struct Windows {
+    open: HashMap<Entity, OpenWindow>,
+    states: HashMap<Entity, ClickState>,
+    last_id: u8,
+}
+
+impl Windows {
+    pub fn new() -> Self {
+        Self {
+            open: HashMap::new(),
+            states: HashMap::new(),
+            last_id: 0,
+        }
+    }
+
+    /// Opens the window of a container, replacing whatever the player had open.
+    /// Returns what closing the previous one left over
+    pub fn open(&mut self, entity: Entity, sender: UnboundedSender<ClientboundPacket>,
+        container: Container, chunks: &ChunkWorld, inventory: &mut Inventory,
+        ender_chest: &EnderChest) -> Vec<ItemStack>
+    {
+        let leftover = self.close(entity, &sender, inventory);
+        let mut items = match container.get_items(chunks, ender_chest) {
+            Some(items) => items,
+            None => return leftover,
+        };
+        // Window 0 is the player's inventory
+        self.last_id = self.last_id % 100 + 1;
+        let window = OpenWindow {
+            id: self.last_id,
+            size: items.len(),
+            crafting: match container {
+                Container::CraftingTable(..) => items.clone(),
+                _ => vec![],
+            },
+            shown: items.clone(),
+            properties: vec![],
+            sender,
+            container,
+        };
+        let _ = window.sender.send(ClientboundPacket::OpenWindow {
+            window: window.id,
+            kind: window.container.menu_kind(chunks, window.size),
+            title: window.container.title(chunks),
+        });
+        items.extend(inventory.get_storage());
+        send_contents(&window.sender, window.id, items, inventory);
+        self.open.insert(entity, window);
+        leftover
+    }
+
+    /// Closes the window without telling the client, for when it closed it.
+    /// The cursor and the crafting grids are emptied into the inventory,
+    /// what doesn't fit is returned so it can be thrown like vanilla does
+    pub fn close(&mut self, entity: Entity, sender: &UnboundedSender<ClientboundPacket>,
+        inventory: &mut Inventory) -> Vec<ItemStack>
+    {
+        let mut leftover = vec![];
+        if let Some(window) = self.open.remove(&entity) {
+            leftover.extend(window.crafting.into_iter().skip(1).flatten()
+                .filter_map(|stack| inventory.add_stack(stack)));
+        }
+        self.states.remove(&entity);
+        leftover.extend(inventory.clear_crafting());
+        let _ = sender.send(ClientboundPacket::WindowItems {
+            window: 0,
+            items: inventory.get_window(),
+        });
+        leftover
+    }
+
+    /// Returns the items thrown out of the window. Taking the result
+    /// of a furnace gives the experience of what it cooked
+    #[allow(clippy::too_many_arguments)]
+    pub fn click(&mut self, entity: Entity, sender: &UnboundedSender<ClientboundPacket>,
+        click: Click, chunks: &ChunkWorld, inventory: &mut Inventory,
+        ender_chest: &mut EnderChest, experience: &mut Experience, game_mode: GameMode)
+        -> Vec<ItemStack>
+    {
+        let state = self.states.entry(entity).or_default();
+        if state.unconfirmed.is_some() {
+            return Vec::new();
+        }
+        let (layout, mut slots, container) = if click.window == 0 {
+            (Layout::Inventory, inventory.get_window(), None)
+        } else {
+            let open = match self.open.get(&entity) {
+                Some(open) if open.id == click.window => open,
+                _ => return Vec::new(),
+            };
+            let layout = open.container.layout(chunks, open.size);
+            let mut slots = match &open.container {
+                Container::CraftingTable(..) => open.crafting.clone(),
+                container => match container.get_items(chunks, ender_chest) {
+                    Some(items) if items.len() == open.size => items,
+                    _ => return Vec::new(),
+                },
+            };
+            slots.extend(inventory.get_storage());
+            slots.push(inventory.get_slot(SlotIndex::OFFHAND).cloned());
+            (layout, slots, Some(open.container.clone()))
+        };
+        let before = slots.clone();
+        let result = apply_click(layout, &mut slots, &mut inventory.cursor, 
+            &mut state.drag, click.slot, click.button, click.mode, game_mode);
+        let accepted = result.as_ref()
+            .map(|result| same_slot(&result.clicked, &click.clicked))
+            .unwrap_or(false);
+        let _ = sender.send(ClientboundPacket::WindowConfirmation {
+            window: click.window,
+            action: click.action,
+            accepted,
+        });
+        let changed: Vec<usize> = (0..layout.visible())
+            .filter(|i| !same_slot(&before[*i], &slots[*i]))
+            .collect();
+        if let Some(container) = container {
+            let size = layout.storage_start();
+            let offhand = slots.pop().unwrap();
+            inventory.set_slot(SlotIndex::OFFHAND, offhand);
+            inventory.set_storage(slots[size..].to_vec());
+            if let Some(open) = self.open.get_mut(&entity) {
+                open.shown = slots[..size].to_vec();
+            }
+            if let Container::CraftingTable(..) = container {
+                if let Some(open) = self.open.get_mut(&entity) {
+                    open.crafting = slots[..size].to_vec();
+                }
+            } else if changed.iter().any(|i| *i < size) {
+                container.set_items(chunks, ender_chest, &slots[..size]);
+                self.broadcast(entity, &container, &changed, &slots);
+            }
+            let count = |slot: &Option<ItemStack>| slot.as_ref().map_or(0, |stack| stack.count);
+            let took_result = matches!(layout, Layout::Furnace(_))
+                && count(&slots[FURNACE_RESULT]) < count(&before[FURNACE_RESULT]);
+            if let (true, Container::Blocks(_, positions)) = (took_result, &container) {
+                if let Some(mut furnace) = chunks.get_block_entity(&positions[0]) {
+                    let points = furnace.take_experience();
+                    chunks.set_block_entity(&positions[0], furnace);
+                    if points > 0 {
+                        experience.add(points);
+                        let _ = sender.send(experience.get_packet());
+                    }
+                }
+            }
+        } else {
+            inventory.set_window(slots.clone());
+        }
+        if accepted {
+            for i in changed {
+                send_slot(sender, click.window, i, slots[i].clone());
+            }
+        } else {
+            // The client rolls back once it gets everything again
+            if let Some(state) = self.states.get_mut(&entity) {
+                state.unconfirmed = Some((click.window, click.action));
+            }
+            slots.truncate(layout.visible());
+            send_contents(sender, click.window, slots, inventory);
+        }
+        result.map(|result| result.dropped).unwrap_or_default()
+    }
+
+    /// The client's answer to a rejected click
+    pub fn confirm(&mut self, entity: Entity, window: u8, action: u16, accepted: bool) {
+        if let Some(state) = self.states.get_mut(&entity) {
+            if !accepted && state.unconfirmed == Some((window, action)) {
+                state.unconfirmed = None;
+            }
+        }
+    }
+
+    /// Whether a window id is the one the player has open, the inventory
+    /// is 0 when no container is
+    pub fn is_current(&self, entity: Entity, window: u8) -> bool {
+        match self.open.get(&entity) {
+            Some(open) => open.id == window,
+            None => window == 0,
+        }
+    }
+
+    /// Sends the changed slots of a container to everyone else looking at it
+    fn broadcast(&self, entity: Entity, container: &Container, changed: &[usize],
+        slots: &[Option<ItemStack>])
+    {
+        for (other, window) in &self.open {
+            if *other == entity {
+                continue;
+            }
+            for i in changed {
+                if let Some(mapped) = window.container.map_slot(container, *i) {
+                    send_slot(&window.sender, window.id, mapped, slots[*i].clone());
+                }
+            }
+        }
+    }
+
+}
+
+fn send_contents(sender: &UnboundedSender<ClientboundPacket>, window: u8,
+    items: Vec<Option<ItemStack>>, inventory: &Inventory)
+{
+    let _ = sender.send(ClientboundPacket::WindowItems {
+        window,
+        items,
+    });
+    let _ = sender.send(ClientboundPacket::SetSlot {
+        window: -1,
+        slot: -1,
+        item: inventory.cursor.clone(),
+    });
+}
+
+fn send_slot(sender: &UnboundedSender<ClientboundPacket>, window: u8, slot: usize,
+    item: Option<ItemStack>)
+{
+    let _ = sender.send(ClientboundPacket::SetSlot {
+        window: window as i8,
+        slot: slot as i16,
+        item,
+    });
+}
+
+fn same_slot(a: &Option<ItemStack>, b: &Option<ItemStack>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.is_same_kind(b) && a.count == b.count,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Vanilla closes windows of blocks that were broken or left behind
+#[system]
+#[read_component(Position)]
+#[read_component(DimensionId)]
+#[read_component(Rotation)]
+#[read_component(PlayerConnection)]
+#[write_component(Inventory)]
+fn close_invalid_windows(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] windows: &mut Windows, #[resource] dimensions: &Dimensions,
+    #[resource] entity_ids: &EntityIdGenerator)
+{
+    let mut invalid = vec![];
+    for (entity, window) in &windows.open {
+        let (dimension, positions) = match &window.container {
+            Container::Blocks(dimension, positions) => (dimension, positions.clone()),
+            Container::CraftingTable(dimension, pos) => (dimension, vec![*pos]),
+            Container::EnderChest => continue,
+        };
+        let entry = match world.entry_ref(*entity) {
+            Ok(entry) => entry,
+            Err(_) => {
+                invalid.push(*entity);
+                continue;
+            },
+        };
+        let in_dimension = entry.get_component::<DimensionId>()
+            .map(|current| current == dimension)
+            .unwrap_or(false);
+        let near = entry.get_component::<Position>()
+            .map(|pos| {
+                let center = positions[0].cast::<f64>().add_scalar(0.5);
+                (pos.0 - center).norm() <= MAX_DISTANCE
+            })
+            .unwrap_or(false);
+        let chunks = &dimensions.get(*dimension).chunks;
+        let exists = match &window.container {
+            Container::CraftingTable(..) => {
+                chunks.get_block(&positions[0]).btype.name == "minecraft:crafting_table"
+            },
+            _ => positions.iter().all(|pos| chunks.get_block_entity(pos).is_some()),
+        };
+        if !in_dimension || !near || !exists {
+            invalid.push(*entity);
+        }
+    }
+    for entity in invalid {
+        let id = windows.open[&entity].id;
+        if let Ok(mut entry) = world.entry_mut(entity) {
+            let sender = entry.get_component::<PlayerConnection>()
+                .map(|conn| conn.get_sender());
+            let dimension = entry.get_component::<DimensionId>().map(|dimension| *dimension);
+            let position = entry.get_component::<Position>().map(|pos| *pos);
+            let rotation = entry.get_component::<Rotation>().map(|rotation| *rotation);
+            if let (Ok(sender), Ok(inventory)) = (sender, entry.get_component_mut::<Inventory>()) {
+                let _ = sender.send(ClientboundPacket::CloseWindow(id));
+                let leftover = windows.close(entity, &sender, inventory);
+                if let (Ok(dimension), Ok(position), Ok(rotation)) = (dimension, position, rotation) {
+                    for stack in leftover {
+                        throw_item(cmd, entity_ids, dimension, &position, &rotation, stack);
+                    }
+                }
+            }
+        }
+        windows.open.remove(&entity);
+    }
+}
+
+/// Sends what furnaces did since the last tick to whoever has them open
+#[system]
+fn sync_furnace_windows(#[resource] windows: &mut Windows,
+    #[resource] dimensions: &Dimensions)
+{
+    for window in windows.open.values_mut() {
+        let (dimension, pos) = match &window.container {
+            Container::Blocks(dimension, positions) => (*dimension, positions[0]),
+            _ => continue,
+        };
+        let entity = match dimensions.get(dimension).chunks.get_block_entity(&pos) {
+            Some(entity) => entity,
+            None => continue,
+        };
+        let (items, properties) = match (entity.items(), entity.furnace_properties()) {
+            (Some(items), Some(properties)) => (items, properties),
+            _ => continue,
+        };
+        for (i, item) in items.iter().enumerate() {
+            if !window.shown.get(i).map_or(false, |shown| same_slot(shown, item)) {
+                send_slot(&window.sender, window.id, i, item.clone());
+            }
+        }
+        for (i, value) in properties.iter().enumerate() {
+            if window.properties.get(i) != Some(value) {
+                let _ = window.sender.send(ClientboundPacket::WindowProperty {
+                    window: window.id,
+                    property: i as i16,
+                    value: *value,
+                });
+            }
+        }
+        window.shown = items.clone();
+        window.properties = properties.to_vec();
+    }
+}